@@ -1,21 +1,67 @@
 use std::collections::VecDeque;
-use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
-use crate::config::AppConfig;
-use crate::hardware::{spawn_worker, HardwareEvent, HardwareHandle, HardwareRequest};
+use crate::cli::Cli;
+use crate::color_picker::{ColorInput, ColorOutcome};
+use crate::config::{control_writes, refresh_interval, AppConfig, Profile};
+use crate::conflicts;
+use crate::export::export_history;
+use crate::hardware::{
+    spawn_worker, HardwareEvent, HardwareHandle, HardwareRequest, KeyboardSetup, PowerStatus,
+    SensorSetup,
+};
+use crate::hotkeys::{Hotkey, HotkeyAction, HotkeyListener};
+use crate::idle::{IdleChange, IdleWatcher};
+use crate::keymap::{Action, KeyMap};
 use crate::models::{
     ControlId, ControlItem, ControlKind, FanMode, FocusPanel, RgbField, RgbSettings, SensorMetric,
-    SensorSnapshot,
+    SensorSnapshot, SliderMove,
+};
+use crate::notify::{self, Notifier};
+use crate::permissions::{lacks_write_access, UsbAccess};
+use crate::profiles::{ProfileCommand, ProfilesPopup};
+use crate::quirks;
+use crate::rgb_protocol::{
+    probed_label, Rgb, BREATHING_EFFECT_INDEX, OFF_EFFECT_INDEX, STATIC_EFFECT_INDEX,
+    ZONES_EFFECT_INDEX, ZONE_COUNT,
 };
-use crate::permissions::UsbAccess;
+use crate::schedule::{self, Entry, ScheduleWatch, WeekMinute};
+use crate::theme::Theme;
 use crate::ui::draw;
 
 const FRAME_INTERVAL: Duration = Duration::from_millis(33);
-const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
-const HISTORY_LIMIT: usize = 500;
+/// Snapshots kept per sensor: ten minutes at the default one-second refresh.
+const HISTORY_LIMIT: usize = 600;
+/// Window for the temperature change shown next to each reading.
+const DELTA_WINDOW: Duration = Duration::from_secs(60);
+/// How long a reconnected keyboard must stay present before lighting is restored.
+const KEYBOARD_SETTLE: Duration = Duration::from_secs(2);
+/// How long a control that appears after the first scan is marked NEW.
+const NEW_CONTROL_HIGHLIGHT: Duration = Duration::from_secs(5);
+/// How long a control changed outside arch-sense is marked CHANGED.
+const EXTERNAL_CHANGE_HIGHLIGHT: Duration = Duration::from_secs(4);
+/// How long a rejected typed value flashes in the Controls panel.
+const EDIT_FLASH: Duration = Duration::from_millis(400);
+const READ_ONLY_HINT: &str =
+    "Read-only — run with sudo or `arch-sense --install-permissions` to change settings";
+/// Quiet period after the last RGB change before auto-apply writes it.
+const RGB_APPLY_DEBOUNCE: Duration = Duration::from_millis(300);
+/// Typed values never need more digits than "100".
+const EDIT_MAX_DIGITS: usize = 3;
+/// Status messages kept for the log panel.
+const LOG_LIMIT: usize = 200;
+/// Entries moved per PageUp/PageDown in the log panel.
+const LOG_PAGE: usize = 10;
+/// Rows moved per PageUp/PageDown in the Controls panel.
+const CONTROL_PAGE: isize = 5;
+/// Points above the low-battery threshold before the pulse can fire again.
+const LOW_BATTERY_HYSTERESIS: u8 = 5;
+/// Three breaths of the red pulse at full speed.
+const LOW_BATTERY_PULSE: Duration = Duration::from_secs(6);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum MessageLevel {
@@ -31,6 +77,59 @@ pub(crate) struct StatusMessage {
     pub(crate) text: String,
 }
 
+#[derive(Clone, Debug)]
+pub(crate) struct LogEntry {
+    pub(crate) at: SystemTime,
+    pub(crate) level: MessageLevel,
+    pub(crate) text: String,
+}
+
+/// Every status message of the session, newest first, for the log panel.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StatusLog {
+    entries: VecDeque<LogEntry>,
+    pub(crate) open: bool,
+    /// Entries hidden above the panel; 0 shows the newest.
+    pub(crate) scroll: usize,
+}
+
+impl StatusLog {
+    fn push(&mut self, level: MessageLevel, text: &str, at: SystemTime) {
+        // Repeats, such as fan previews while holding an arrow key, only
+        // refresh the timestamp.
+        if let Some(newest) = self.entries.front_mut() {
+            if newest.level == level && newest.text == text {
+                newest.at = at;
+                return;
+            }
+        }
+
+        self.entries.push_front(LogEntry {
+            at,
+            level,
+            text: text.to_string(),
+        });
+        self.entries.truncate(LOG_LIMIT);
+        // Keep a scrolled view on the same entries.
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + 1).min(self.entries.len() - 1);
+        }
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        let last = self.entries.len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(last);
+    }
+
+    pub(crate) fn visible(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().skip(self.scroll)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct AnimatedMetric {
     pub(crate) value: f64,
@@ -73,16 +172,20 @@ pub(crate) struct SensorsState {
     pub(crate) gpu_temp: AnimatedMetric,
     pub(crate) cpu_fan: AnimatedMetric,
     pub(crate) gpu_fan: AnimatedMetric,
-    pub(crate) cpu_temp_history: VecDeque<u64>,
-    pub(crate) gpu_temp_history: VecDeque<u64>,
-    pub(crate) cpu_fan_history: VecDeque<u64>,
-    pub(crate) gpu_fan_history: VecDeque<u64>,
+    pub(crate) cpu_temp_history: VecDeque<Option<u64>>,
+    pub(crate) gpu_temp_history: VecDeque<Option<u64>>,
+    pub(crate) cpu_fan_history: VecDeque<Option<u64>>,
+    pub(crate) gpu_fan_history: VecDeque<Option<u64>>,
+    /// Wall-clock time of each history sample, for exports.
+    pub(crate) sample_times: VecDeque<SystemTime>,
     pub(crate) cpu_fan_mode: FanMode,
     pub(crate) gpu_fan_mode: FanMode,
+    pub(crate) cpu_fan_percent: Option<u8>,
+    pub(crate) gpu_fan_percent: Option<u8>,
 }
 
 impl SensorsState {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             cpu_temp: AnimatedMetric::new(105.0),
             gpu_temp: AnimatedMetric::new(105.0),
@@ -92,8 +195,11 @@ impl SensorsState {
             gpu_temp_history: VecDeque::with_capacity(HISTORY_LIMIT),
             cpu_fan_history: VecDeque::with_capacity(HISTORY_LIMIT),
             gpu_fan_history: VecDeque::with_capacity(HISTORY_LIMIT),
+            sample_times: VecDeque::with_capacity(HISTORY_LIMIT),
             cpu_fan_mode: FanMode::Auto,
             gpu_fan_mode: FanMode::Auto,
+            cpu_fan_percent: None,
+            gpu_fan_percent: None,
         }
     }
 
@@ -122,8 +228,14 @@ impl SensorsState {
             snapshot.gpu_fan.value,
             self.gpu_fan.max,
         );
+        self.sample_times.push_back(SystemTime::now());
+        if self.sample_times.len() > HISTORY_LIMIT {
+            let _ = self.sample_times.pop_front();
+        }
         self.cpu_fan_mode = snapshot.cpu_fan_mode;
         self.gpu_fan_mode = snapshot.gpu_fan_mode;
+        self.cpu_fan_percent = snapshot.cpu_fan_percent;
+        self.gpu_fan_percent = snapshot.gpu_fan_percent;
     }
 
     fn advance(&mut self, dt: Duration) {
@@ -133,9 +245,27 @@ impl SensorsState {
         self.gpu_fan.advance(dt);
     }
 
-    fn push_history(history: &mut VecDeque<u64>, value: Option<f64>, max: f64) {
-        let clamped = value.unwrap_or(0.0).clamp(0.0, max).round() as u64;
-        history.push_back(clamped);
+    /// Newest CPU or GPU temperature, whichever is hotter.
+    pub(crate) fn hottest_temp(&self) -> Option<u64> {
+        [&self.cpu_temp_history, &self.gpu_temp_history]
+            .into_iter()
+            .filter_map(|history| history.back().copied().flatten())
+            .max()
+    }
+
+    /// Change between the newest sample and the one `DELTA_WINDOW` earlier,
+    /// or `None` if either is a gap or the history is not that long yet.
+    /// Samples are `interval` apart.
+    pub(crate) fn delta(history: &VecDeque<Option<u64>>, interval: Duration) -> Option<i64> {
+        let span = (DELTA_WINDOW.as_millis() / interval.as_millis().max(1)) as usize;
+        let newest = (*history.back()?)?;
+        let oldest = (*history.get(history.len().checked_sub(span + 1)?)?)?;
+        Some(newest as i64 - oldest as i64)
+    }
+
+    /// Records a sample; unavailable readings are kept as gaps rather than zeros.
+    fn push_history(history: &mut VecDeque<Option<u64>>, value: Option<f64>, max: f64) {
+        history.push_back(value.map(|value| value.clamp(0.0, max).round() as u64));
 
         while history.len() > HISTORY_LIMIT {
             let _ = history.pop_front();
@@ -143,61 +273,426 @@ impl SensorsState {
     }
 }
 
+/// A number being typed into the selected control's value column.
+#[derive(Clone, Debug)]
+pub(crate) struct ControlEdit {
+    pub(crate) id: ControlId,
+    pub(crate) buffer: String,
+    rejected_at: Option<Instant>,
+}
+
+impl ControlEdit {
+    pub(crate) fn flashing(&self) -> bool {
+        self.rejected_at
+            .is_some_and(|rejected| rejected.elapsed() < EDIT_FLASH)
+    }
+}
+
+/// A control write held back until the user confirms it.
+#[derive(Clone, Debug)]
+pub(crate) struct PendingConfirm {
+    pub(crate) id: ControlId,
+    pub(crate) value: String,
+    pub(crate) consequence: &'static str,
+}
+
+/// y/Enter confirms, n/Esc cancels, anything else leaves the popup open.
+fn confirm_answer(key: &KeyEvent) -> Option<bool> {
+    match key.code {
+        KeyCode::Char('y' | 'Y') | KeyCode::Enter => Some(true),
+        KeyCode::Char('n' | 'N') | KeyCode::Esc => Some(false),
+        _ => None,
+    }
+}
+
+/// Slider movement for a key on the Brightness or Speed row: Shift makes
+/// the arrows fine, Home and End jump to either end.
+fn slider_move(action: Action, modifiers: KeyModifiers) -> Option<SliderMove> {
+    let fine = modifiers.contains(KeyModifiers::SHIFT);
+    match action {
+        Action::Left if fine => Some(SliderMove::Fine(-1)),
+        Action::Right if fine => Some(SliderMove::Fine(1)),
+        Action::Left => Some(SliderMove::Step(-1)),
+        Action::Right => Some(SliderMove::Step(1)),
+        Action::Top => Some(SliderMove::Min),
+        Action::Bottom => Some(SliderMove::Max),
+        _ => None,
+    }
+}
+
+/// Clears and reports a debounced RGB write whose quiet period has passed.
+/// A write still in flight holds it until that one finishes.
+fn take_due_apply(pending_apply_at: &mut Option<Instant>, busy: bool, now: Instant) -> bool {
+    if busy || pending_apply_at.is_none_or(|due| now < due) {
+        return false;
+    }
+
+    *pending_apply_at = None;
+    true
+}
+
+/// What a fresh read changed in the Controls list.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ControlChanges {
+    /// Ids that were not there before.
+    appeared: Vec<ControlId>,
+    /// Rows readable before and after whose value moved, with the value
+    /// shown before.
+    changed: Vec<(ControlId, String)>,
+}
+
+/// Updates `current` from a fresh read, keyed by id, and reports what
+/// appeared or changed. Values, choices and errors come from the read.
+/// Pending previews survive unless the choices changed under them. A
+/// `complete` read also removes rows it no longer contains.
+fn merge_controls(
+    current: &mut Vec<ControlItem>,
+    incoming: Vec<ControlItem>,
+    complete: bool,
+) -> ControlChanges {
+    if complete {
+        current.retain(|item| incoming.iter().any(|fresh| fresh.id == item.id));
+    }
+
+    let mut changes = ControlChanges::default();
+    for fresh in incoming {
+        match current.iter_mut().find(|item| item.id == fresh.id) {
+            Some(item) => {
+                if item.kind != fresh.kind {
+                    item.pending = None;
+                }
+                let readable = item.last_error.is_none() && fresh.last_error.is_none();
+                if readable && item.raw != fresh.raw {
+                    changes.changed.push((item.id, item.display.clone()));
+                }
+                item.raw = fresh.raw;
+                item.display = fresh.display;
+                item.kind = fresh.kind;
+                item.last_error = fresh.last_error;
+                item.writable = fresh.writable;
+            }
+            None => {
+                changes.appeared.push(fresh.id);
+                current.push(fresh);
+            }
+        }
+    }
+
+    current.sort_by_key(|item| ControlId::ALL.iter().position(|id| *id == item.id));
+    changes
+}
+
+/// Tracks keyboard re-enumeration so lighting is restored once the device settles.
+#[derive(Clone, Debug, Default)]
+struct KeyboardHotplug {
+    scanned: bool,
+    present: bool,
+    arrived_at: Option<Instant>,
+}
+
+impl KeyboardHotplug {
+    fn observe(&mut self, present: bool, now: Instant) {
+        if !present {
+            self.arrived_at = None;
+        } else if !self.present && self.scanned {
+            self.arrived_at = Some(now);
+        }
+
+        self.present = present;
+        self.scanned = true;
+    }
+
+    fn take_due(&mut self, now: Instant) -> bool {
+        match self.arrived_at {
+            Some(arrived) if now.saturating_duration_since(arrived) >= KEYBOARD_SETTLE => {
+                self.arrived_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Fires once when the battery drops under the threshold while
+/// discharging, and re-arms only after it has recovered past the
+/// hysteresis band.
+#[derive(Clone, Debug)]
+struct LowBatteryWatch {
+    armed: bool,
+}
+
+impl Default for LowBatteryWatch {
+    fn default() -> Self {
+        Self { armed: true }
+    }
+}
+
+impl LowBatteryWatch {
+    fn observe(&mut self, power: &PowerStatus, threshold: u8) -> bool {
+        let Some(percent) = power.battery_percent else {
+            return false;
+        };
+        let discharging = power.ac_online != Some(true)
+            && (power.ac_online == Some(false)
+                || power.battery_status.as_deref() == Some("Discharging"));
+
+        if percent >= threshold.saturating_add(LOW_BATTERY_HYSTERESIS) {
+            self.armed = true;
+        } else if self.armed && discharging && percent < threshold {
+            self.armed = false;
+            return true;
+        }
+        false
+    }
+}
+
+/// Why saved lighting is re-sent without being edited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RgbRestore {
+    Reconnect,
+    IdleBlank,
+    IdleWake,
+    LowBattery,
+    PulseDone,
+}
+
+impl RgbRestore {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Reconnect => "Keyboard reconnected",
+            Self::IdleBlank => "Keyboard idle",
+            Self::IdleWake => "Input resumed",
+            Self::LowBattery => "Battery low",
+            Self::PulseDone => "Battery warning shown",
+        }
+    }
+
+    fn progress(self) -> &'static str {
+        match self {
+            Self::Reconnect => "Keyboard reconnected; restoring lighting",
+            Self::IdleBlank => "Keyboard idle; switching lighting off",
+            Self::IdleWake => "Input resumed; restoring lighting",
+            Self::LowBattery => "Battery low; pulsing the keyboard red",
+            Self::PulseDone => "Battery warning shown; restoring lighting",
+        }
+    }
+
+    /// What to send instead of `saved`. While idle-blanked the keyboard
+    /// stays dark until input, also after a reconnect or a warning pulse.
+    fn settings(self, saved: RgbSettings, blanked: bool) -> RgbSettings {
+        match self {
+            Self::IdleBlank => RgbSettings {
+                effect_idx: OFF_EFFECT_INDEX,
+                ..saved
+            },
+            Self::Reconnect | Self::PulseDone if blanked => RgbSettings {
+                effect_idx: OFF_EFFECT_INDEX,
+                ..saved
+            },
+            Self::LowBattery => RgbSettings {
+                effect_idx: BREATHING_EFFECT_INDEX,
+                custom: Some(Rgb { r: 255, g: 0, b: 0 }),
+                brightness: 100,
+                speed: 100,
+                ..saved
+            },
+            _ => saved,
+        }
+    }
+}
+
 pub struct App {
     pub(crate) focus: FocusPanel,
     pub(crate) controls: Vec<ControlItem>,
     pub(crate) selected_control: usize,
+    /// Only controls matching this are listed; empty shows all.
+    pub(crate) control_filter: String,
+    /// The filter is being typed.
+    pub(crate) filter_editing: bool,
+    /// First `g` of `gg` was pressed.
+    g_pending: bool,
     pub(crate) rgb: RgbSettings,
     pub(crate) selected_rgb_field: usize,
     pub(crate) sensors: SensorsState,
     pub(crate) module_loaded: bool,
     pub(crate) keyboard: UsbAccess,
+    pub(crate) probed_keyboards: String,
+    pub(crate) power: PowerStatus,
     pub(crate) message: StatusMessage,
+    pub(crate) log: StatusLog,
     pub(crate) hardware_note: Option<String>,
     pub(crate) snapshot_pending: bool,
     pub(crate) control_pending: Option<ControlId>,
     pub(crate) rgb_pending: bool,
     pub(crate) rgb_dirty: bool,
     pub(crate) focus_pulse: f64,
+    pub(crate) keymap: KeyMap,
+    pub(crate) help_open: bool,
+    pub(crate) editing: Option<ControlEdit>,
+    /// Hex entry or HSV picker open on the Color row.
+    pub(crate) color_input: Option<ColorInput>,
+    /// Zone the Color row edits while the Zones effect is selected.
+    pub(crate) selected_zone: usize,
+    /// When the debounced auto-apply write goes out.
+    pub(crate) pending_apply_at: Option<Instant>,
+    /// Started without write access: values are shown but never written.
+    pub(crate) read_only: bool,
+    /// Another power manager switched back the last thermal profile set
+    /// here; cleared by the next thermal profile write.
+    pub(crate) thermal_reverted: bool,
+    /// Detected laptop model, shown in the header.
+    pub(crate) model: String,
+    pub(crate) theme: Arc<Theme>,
+    /// Controls that showed up after the first scan, and when.
+    appeared: Vec<(ControlId, Instant)>,
+    /// Controls whose value a firmware hotkey or another program changed,
+    /// and when that was noticed.
+    changed_externally: Vec<(ControlId, Instant)>,
+    /// Write waiting for a yes/no in the confirmation popup.
+    pub(crate) confirm: Option<PendingConfirm>,
+    /// Open profiles popup.
+    pub(crate) profiles_popup: Option<ProfilesPopup>,
     pub(crate) rgb_phase: f64,
     config: AppConfig,
+    notifier: Notifier,
+    idle: IdleWatcher,
+    schedule: ScheduleWatch,
+    /// A schedule entry that came due while another write was in flight.
+    scheduled: Option<Entry>,
+    hotkeys: HotkeyListener,
+    /// Why the pending `RestoreRgb` was sent, for its status message.
+    restore_reason: &'static str,
+    low_battery: LowBatteryWatch,
+    /// When the low-battery pulse ends and the lighting is restored.
+    pulse_until: Option<Instant>,
     hardware: HardwareHandle,
+    dry_run: bool,
+    calibration_was_active: bool,
+    keyboard_hotplug: KeyboardHotplug,
+    /// Time between sensor snapshots, from `--refresh` or `refresh_ms`.
+    pub(crate) snapshot_interval: Duration,
     last_snapshot_request: Instant,
     quit: bool,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
+    pub fn new(cli: &Cli) -> Result<Self> {
         let (config, config_warning) = AppConfig::load_with_warning();
-        let rgb = RgbSettings::from_config(&config.rgb);
-        let hardware = spawn_worker()?;
+        let model = quirks::detected();
+        let mut rgb = RgbSettings::from_config(&config.rgb);
+        let effect_warning = (!model.quirks().offers_effect(rgb.effect_idx)).then(|| {
+            let name = rgb.effect().name;
+            rgb.effect_idx = STATIC_EFFECT_INDEX;
+            format!("{name} is not supported on this model; using Static")
+        });
+        let (keyboard_setup, keyboard_warning) = KeyboardSetup::resolve(&config, cli.rgb_backend);
+        let probed_keyboards = probed_label(&keyboard_setup.models);
+        let (keymap, keymap_warnings) = KeyMap::from_config(&config.keybindings);
+        let keymap_warning = (!keymap_warnings.is_empty())
+            .then(|| format!("Ignored keybindings: {}", keymap_warnings.join("; ")));
+        let (theme, theme_warnings) = Theme::from_config(&config.theme, cli.theme.as_deref());
+        let theme_warning =
+            (!theme_warnings.is_empty()).then(|| format!("Theme: {}", theme_warnings.join("; ")));
+        let (idle, idle_warning) = IdleWatcher::new(config.rgb_idle_minutes, Instant::now());
+        let (schedules, schedule_warnings) = schedule::parse_all(&config.schedules);
+        let schedule_warning = (!schedule_warnings.is_empty())
+            .then(|| format!("Ignored {}", schedule_warnings.join("; ")));
+        let (hotkeys, hotkey_warning) = HotkeyListener::new(&config.hotkeys, Instant::now());
+        let (snapshot_interval, refresh_warning) = refresh_interval(cli.refresh, config.refresh_ms);
+        let read_only = lacks_write_access();
+        let read_only_warning = read_only.then(|| READ_ONLY_HINT.to_string());
+        let conflicts = if config.manages_thermal_profile() {
+            conflicts::detect()
+        } else {
+            Vec::new()
+        };
+        let conflict_warning = (!conflicts.is_empty()).then(|| {
+            format!(
+                "{} also manages platform_profile and may switch thermal profiles back; \
+                 set \"manage_thermal_profile\": false to leave it alone",
+                conflicts::describe(&conflicts)
+            )
+        });
+        let hardware = spawn_worker(
+            keyboard_setup,
+            SensorSetup::from_config(&config),
+            cli.dry_run,
+        )?;
         let now = Instant::now();
 
         let mut app = Self {
             focus: FocusPanel::Controls,
             controls: Vec::new(),
             selected_control: 0,
+            control_filter: String::new(),
+            filter_editing: false,
+            g_pending: false,
             rgb,
             selected_rgb_field: 0,
             sensors: SensorsState::new(),
             module_loaded: false,
             keyboard: UsbAccess::NotFound,
+            probed_keyboards,
+            power: PowerStatus::default(),
             message: StatusMessage {
                 level: MessageLevel::Info,
-                text: config_warning.unwrap_or_else(|| "Starting hardware scan".to_string()),
+                text: String::new(),
             },
+            log: StatusLog::default(),
             hardware_note: None,
             snapshot_pending: false,
             control_pending: None,
             rgb_pending: false,
             rgb_dirty: false,
             focus_pulse: 1.0,
+            keymap,
+            help_open: false,
+            editing: None,
+            confirm: None,
+            color_input: None,
+            selected_zone: 0,
+            pending_apply_at: None,
+            read_only,
+            thermal_reverted: false,
+            model: model.label(),
+            theme: Arc::new(theme),
+            appeared: Vec::new(),
+            changed_externally: Vec::new(),
+            profiles_popup: None,
             rgb_phase: 0.0,
+            notifier: Notifier::new(&config.notifications),
+            idle,
+            schedule: ScheduleWatch::new(schedules),
+            scheduled: None,
+            hotkeys,
+            restore_reason: "Keyboard reconnected",
+            low_battery: LowBatteryWatch::default(),
+            pulse_until: None,
             config,
             hardware,
-            last_snapshot_request: now - SNAPSHOT_INTERVAL,
+            dry_run: cli.dry_run,
+            calibration_was_active: false,
+            keyboard_hotplug: KeyboardHotplug::default(),
+            snapshot_interval,
+            last_snapshot_request: now - snapshot_interval,
             quit: false,
         };
+        app.set_message(
+            MessageLevel::Info,
+            config_warning
+                .or(read_only_warning)
+                .or(keyboard_warning)
+                .or(effect_warning)
+                .or(keymap_warning)
+                .or(theme_warning)
+                .or(idle_warning)
+                .or(schedule_warning)
+                .or(hotkey_warning)
+                .or(refresh_warning)
+                .or(conflict_warning)
+                .or_else(|| model.unknown_warning())
+                .unwrap_or_else(|| "Starting hardware scan".to_string()),
+        );
         app.request_snapshot();
         Ok(app)
     }
@@ -236,9 +731,124 @@ impl App {
         self.rgb_phase = (self.rgb_phase + dt.as_secs_f64() * 18.0) % 1000.0;
         self.handle_hardware_events();
 
-        if self.last_snapshot_request.elapsed() >= SNAPSHOT_INTERVAL {
+        for hotkey in self.hotkeys.poll(Instant::now()) {
+            self.on_hotkey(hotkey);
+        }
+
+        if self.last_snapshot_request.elapsed() >= self.snapshot_interval {
             self.request_snapshot();
+            self.run_schedule();
+        }
+
+        if !self.rgb_pending && self.keyboard_hotplug.take_due(Instant::now()) && !self.read_only {
+            self.restore_rgb(RgbRestore::Reconnect);
+        }
+
+        let now = Instant::now();
+        if self.pulse_until.is_some_and(|until| until <= now) && !self.rgb_pending {
+            self.pulse_until = None;
+            self.restore_rgb(RgbRestore::PulseDone);
+        }
+
+        if !self.rgb_pending && !self.read_only {
+            let hardware_timeout = self.hardware_backlight_timeout();
+            match self.idle.poll(Instant::now(), hardware_timeout) {
+                Some(IdleChange::Blank) => self.restore_rgb(RgbRestore::IdleBlank),
+                Some(IdleChange::Wake) => self.restore_rgb(RgbRestore::IdleWake),
+                None => {}
+            }
+        }
+
+        if take_due_apply(&mut self.pending_apply_at, self.rgb_pending, Instant::now()) {
+            self.apply_rgb();
+        }
+    }
+
+    /// Applies a schedule entry once its time comes round. One that comes
+    /// while another write is in flight waits for it to finish.
+    fn run_schedule(&mut self) {
+        if !self.schedule.enabled() {
+            return;
+        }
+        if let Some(entry) = WeekMinute::now().and_then(|now| self.schedule.poll(now)) {
+            self.scheduled = Some(entry);
+        }
+        if self.control_pending.is_some() || self.rgb_pending {
+            return;
+        }
+        let Some(entry) = self.scheduled.take() else {
+            return;
+        };
+
+        match self
+            .config
+            .profiles
+            .iter()
+            .position(|profile| profile.name == entry.profile)
+        {
+            Some(index) => {
+                self.set_message(
+                    MessageLevel::Info,
+                    format!("Schedule {}: profile '{}'", entry.at, entry.profile),
+                );
+                self.apply_profile(index);
+            }
+            None => self.set_message(
+                MessageLevel::Warning,
+                format!(
+                    "Schedule {}: no profile named '{}'",
+                    entry.at, entry.profile
+                ),
+            ),
+        }
+    }
+
+    /// Runs a global hotkey. Fan changes skip the confirmation popup: the
+    /// key was bound to exactly this in the config.
+    fn on_hotkey(&mut self, hotkey: Hotkey) {
+        if self.read_only {
+            self.set_message(MessageLevel::Warning, READ_ONLY_HINT);
+            return;
+        }
+        self.set_message(
+            MessageLevel::Info,
+            format!("Hotkey {}: {}", hotkey.key, hotkey.action.name()),
+        );
+
+        match hotkey.action {
+            HotkeyAction::ToggleRgb => self.toggle_rgb_power(),
+            HotkeyAction::CycleFanMode => self.cycle_fan_mode(),
+        }
+    }
+
+    /// Both fans to the fastest step from Auto, and back to Auto otherwise.
+    fn cycle_fan_mode(&mut self) {
+        let Some(fan) = self.controls.iter().find(|item| item.id.is_fan()) else {
+            self.set_message(MessageLevel::Warning, "No fan control on this model");
+            return;
+        };
+        if self.control_pending.is_some() {
+            self.set_message(MessageLevel::Warning, "Another write is still in progress");
+            return;
         }
+
+        let fastest = match &fan.kind {
+            ControlKind::Choice(choices) => choices
+                .iter()
+                .filter_map(|choice| choice.value.parse::<u8>().ok())
+                .max(),
+            ControlKind::Toggle => None,
+        };
+        let auto = self
+            .controls
+            .iter()
+            .filter(|item| item.id.is_fan())
+            .all(|item| item.raw.trim() == "0");
+        let value = match fastest {
+            Some(fastest) if auto && fastest > 0 => format!("{fastest},{fastest}"),
+            _ => "0,0".to_string(),
+        };
+        self.send_control(fan.id, value);
     }
 
     fn request_snapshot(&mut self) {
@@ -263,19 +873,71 @@ impl App {
                     self.snapshot_pending = false;
                     self.module_loaded = snapshot.module_loaded;
                     self.keyboard = snapshot.keyboard;
+                    let now = Instant::now();
+                    let keyboard_present = !matches!(self.keyboard, UsbAccess::NotFound);
+                    let keyboard_lost = self.keyboard_hotplug.present && !keyboard_present;
+                    self.keyboard_hotplug.observe(keyboard_present, now);
+                    for alert in self.notifier.check(
+                        snapshot.sensors.cpu_temp.value,
+                        snapshot.sensors.gpu_temp.value,
+                        keyboard_lost,
+                        now,
+                    ) {
+                        notify::send(alert);
+                    }
                     self.hardware_note = snapshot.note;
+                    self.power = snapshot.power;
+                    let threshold = self.config.low_battery.threshold;
+                    if self.config.low_battery.enabled
+                        && keyboard_present
+                        && !self.read_only
+                        && !self.rgb_pending
+                        && self.low_battery.observe(&self.power, threshold)
+                    {
+                        self.restore_rgb(RgbRestore::LowBattery);
+                        self.pulse_until = Some(now + LOW_BATTERY_PULSE);
+                    }
                     self.sensors.update(&snapshot.sensors);
-                    self.replace_controls(snapshot.controls, true);
+                    let changed = self.update_controls(snapshot.controls, true);
+                    self.note_external_changes(changed, &snapshot.self_written, now);
+
+                    let calibrating = self.calibration_active();
+                    if let Some(reverted) = snapshot.reverted {
+                        self.thermal_reverted = true;
+                        self.set_message(MessageLevel::Warning, reverted);
+                    }
+                    if let Some(warning) = snapshot.warning {
+                        self.set_message(MessageLevel::Warning, warning);
+                    } else if self.calibration_was_active && !calibrating {
+                        self.set_message(MessageLevel::Success, "Battery calibration finished");
+                    }
+                    self.calibration_was_active = calibrating;
 
                     if self.message.text == "Starting hardware scan" {
                         self.set_message(MessageLevel::Success, "Hardware scan complete");
                     }
                 }
-                HardwareEvent::ControlApplied { id, controls } => {
-                    self.control_pending = None;
+                HardwareEvent::ControlApplied {
+                    controls,
+                    message,
+                    verified,
+                } => {
+                    let applied = self.control_pending.take();
                     self.clear_pending_controls();
-                    self.replace_controls(controls, false);
-                    self.set_message(MessageLevel::Success, format!("{} applied", id.label()));
+                    self.update_controls(controls, false);
+                    self.calibration_was_active = self.calibration_active();
+                    match self.remember_hardware(|id| {
+                        applied.is_some_and(|applied| {
+                            id == applied || (id.is_fan() && applied.is_fan())
+                        })
+                    }) {
+                        Ok(()) if verified => self.set_message(MessageLevel::Success, message),
+                        Ok(()) => self.set_message(MessageLevel::Warning, message),
+                        Err(error) => self.set_message(
+                            MessageLevel::Error,
+                            format!("{message}; config save failed: {error}"),
+                        ),
+                    }
                 }
                 HardwareEvent::ControlFailed { id, error } => {
                     self.control_pending = None;
@@ -289,47 +951,235 @@ impl App {
                 HardwareEvent::RgbApplied(message) => {
                     self.rgb_pending = false;
                     self.rgb_dirty = false;
-                    self.config.rgb = self.rgb.to_config();
-                    match self.config.save() {
-                        Ok(()) => self.set_message(MessageLevel::Success, message),
+                    // Nothing reached the keyboard, so don't persist it for --apply.
+                    if self.dry_run {
+                        self.set_message(MessageLevel::Info, message);
+                    } else {
+                        self.config.rgb = self.rgb.to_config();
+                        match self.config.save() {
+                            Ok(()) => self.set_message(MessageLevel::Success, message),
+                            Err(error) => self.set_message(
+                                MessageLevel::Error,
+                                format!("{message}; config save failed: {error}"),
+                            ),
+                        }
+                    }
+                }
+                HardwareEvent::RgbRestored(message) => {
+                    self.rgb_pending = false;
+                    let reason = self.restore_reason;
+                    self.set_message(MessageLevel::Success, format!("{reason}; {message}"));
+                }
+                HardwareEvent::RgbRestoreFailed(error) => {
+                    self.rgb_pending = false;
+                    let reason = self.restore_reason;
+                    self.set_message(
+                        MessageLevel::Error,
+                        format!("{reason}; restoring lighting failed: {error}"),
+                    );
+                }
+                HardwareEvent::RgbFailed(error) => {
+                    self.rgb_pending = false;
+                    if self.config.auto_apply {
+                        // One error, not one per keystroke while the keyboard is gone.
+                        self.config.auto_apply = false;
+                        self.pending_apply_at = None;
+                        let _ = self.config.save();
+                        self.set_message(
+                            MessageLevel::Error,
+                            format!("RGB apply failed: {error}; auto-apply turned off"),
+                        );
+                    } else {
+                        self.set_message(MessageLevel::Error, format!("RGB apply failed: {error}"));
+                    }
+                }
+                HardwareEvent::ProfileApplied {
+                    controls,
+                    message,
+                    rgb_applied,
+                    failed,
+                } => {
+                    self.rgb_pending = false;
+                    self.clear_pending_controls();
+                    self.update_controls(controls, true);
+                    self.calibration_was_active = self.calibration_active();
+                    let level = if failed {
+                        MessageLevel::Warning
+                    } else {
+                        MessageLevel::Success
+                    };
+                    // Keep --apply and the next start in step with the hardware.
+                    if rgb_applied && !self.dry_run {
+                        self.config.rgb = self.rgb.to_config();
+                    }
+                    match self.remember_hardware(|_| true) {
+                        Ok(()) => self.set_message(level, message),
                         Err(error) => self.set_message(
                             MessageLevel::Error,
                             format!("{message}; config save failed: {error}"),
                         ),
                     }
                 }
-                HardwareEvent::RgbFailed(error) => {
-                    self.rgb_pending = false;
-                    self.set_message(MessageLevel::Error, format!("RGB apply failed: {error}"));
-                }
             }
         }
     }
 
     fn on_key(&mut self, key: KeyEvent) {
+        self.idle.input(Instant::now());
         if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
             self.quit = true;
             return;
         }
 
-        match key.code {
-            KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.quit = true;
+        let action = self.keymap.action_for(&key);
+        let g_pending = std::mem::take(&mut self.g_pending);
+
+        // The overlay swallows everything except the keys that close it.
+        if self.help_open {
+            if matches!(action, Some(Action::Help | Action::Cancel | Action::Quit)) {
+                self.help_open = false;
+            }
+            return;
+        }
+
+        // The popup blocks everything else until it is answered.
+        if self.confirm.is_some() {
+            match confirm_answer(&key) {
+                Some(true) => {
+                    if let Some(PendingConfirm { id, value, .. }) = self.confirm.take() {
+                        self.send_control(id, value);
+                    }
+                }
+                Some(false) => {
+                    self.confirm = None;
+                    self.clear_pending_controls();
+                    self.set_message(MessageLevel::Info, "Change cancelled");
+                }
+                None => {}
+            }
+            return;
+        }
+
+        if self.editing.is_some() {
+            self.on_edit_key(key);
+            return;
+        }
+
+        if self.filter_editing {
+            self.on_filter_key(key);
+            return;
+        }
+
+        if let Some(input) = self.color_input.as_mut() {
+            match input.on_key(key) {
+                Some(ColorOutcome::Chosen(color)) => {
+                    self.color_input = None;
+                    if self.rgb.is_zones() {
+                        self.rgb.zones[self.selected_zone] = color;
+                    } else {
+                        self.rgb.custom = Some(color);
+                    }
+                    self.rgb_changed(format!("Preview colour {}", color.hex()));
+                }
+                Some(ColorOutcome::Invalid) => self.set_message(
+                    MessageLevel::Warning,
+                    "Enter 3 or 6 hex digits, e.g. 2EB8B8",
+                ),
+                Some(ColorOutcome::Cancelled) => {
+                    self.color_input = None;
+                    self.set_message(MessageLevel::Info, "Colour entry cancelled");
+                }
+                None => {}
+            }
+            return;
+        }
+
+        if self.log.open && key.modifiers.is_empty() {
+            match key.code {
+                KeyCode::PageUp => return self.log.scroll_by(-(LOG_PAGE as isize)),
+                KeyCode::PageDown => return self.log.scroll_by(LOG_PAGE as isize),
+                _ => {}
+            }
+        }
+
+        if let Some(popup) = self.profiles_popup.as_mut() {
+            let names = profile_names(&self.config.profiles);
+            if let Some(command) = popup.on_key(key, action, &names) {
+                self.on_profile_command(command);
+            }
+            return;
+        }
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            if let KeyCode::Char(digit @ '1'..='9') = key.code {
+                self.apply_profile(usize::from(digit as u8 - b'1'));
+                return;
+            }
+        }
+
+        let Some(action) = action else {
+            if self.focus != FocusPanel::Controls || !key.modifiers.is_empty() {
+                return;
+            }
+            if key.code == KeyCode::Char('g') {
+                if g_pending {
+                    self.jump_control_selection(isize::MIN);
+                } else {
+                    self.g_pending = true;
+                }
+            } else if let KeyCode::Char(digit @ '0'..='9') = key.code {
+                if self.selection_visible() {
+                    if self.read_only {
+                        self.set_message(MessageLevel::Warning, READ_ONLY_HINT);
+                    } else {
+                        self.start_edit(Some(digit));
+                    }
+                }
+            }
+            return;
+        };
+
+        match action {
+            Action::Quit => self.quit = true,
+            Action::Help => self.help_open = true,
+            Action::Profiles => self.profiles_popup = Some(ProfilesPopup::default()),
+            Action::Log => {
+                self.log.open = !self.log.open;
+                self.log.scroll = 0;
             }
-            KeyCode::Tab => self.set_focus(self.focus.next()),
-            KeyCode::BackTab => self.set_focus(self.focus.previous()),
-            KeyCode::Char('r') | KeyCode::Char('R') => {
+            Action::NextPanel => self.set_focus(self.focus.next()),
+            Action::PreviousPanel => self.set_focus(self.focus.previous()),
+            Action::Refresh => {
                 self.request_snapshot();
                 self.set_message(MessageLevel::Info, "Refresh requested");
             }
-            KeyCode::Esc => {
+            Action::ExportHistory => match export_history(&self.sensors) {
+                Ok(path) => self.set_message(
+                    MessageLevel::Success,
+                    format!("Saved sensor history to {}", path.display()),
+                ),
+                Err(error) => self.set_message(
+                    MessageLevel::Error,
+                    format!("History export failed: {error}"),
+                ),
+            },
+            Action::Cancel if !self.control_filter.is_empty() => {
+                self.clear_pending_controls();
+                self.set_control_filter(String::new());
+                self.set_message(MessageLevel::Info, "Filter cleared");
+            }
+            Action::Cancel => {
                 self.clear_pending_controls();
                 self.set_message(MessageLevel::Info, "Pending change cancelled");
             }
+            // Only navigation reaches the panels; nothing is written.
+            _ if self.read_only && self.focus != FocusPanel::Sensors && !action.is_navigation() => {
+                self.set_message(MessageLevel::Warning, READ_ONLY_HINT);
+            }
             _ => match self.focus {
-                FocusPanel::Controls => self.on_controls_key(key),
-                FocusPanel::Rgb => self.on_rgb_key(key),
-                FocusPanel::Sensors => self.on_sensors_key(key),
+                FocusPanel::Controls => self.on_controls_action(action),
+                FocusPanel::Rgb => self.on_rgb_action(action, key.modifiers),
+                FocusPanel::Sensors => self.on_sensors_action(action),
             },
         }
     }
@@ -341,54 +1191,496 @@ impl App {
         }
     }
 
-    fn on_controls_key(&mut self, key: KeyEvent) {
+    fn on_controls_action(&mut self, action: Action) {
         if self.controls.is_empty() {
             return;
         }
 
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => self.move_control_selection(-1),
-            KeyCode::Down | KeyCode::Char('j') => self.move_control_selection(1),
-            KeyCode::Left | KeyCode::Char('h') => self.cycle_control(-1),
-            KeyCode::Right | KeyCode::Char('l') => self.cycle_control(1),
-            KeyCode::Enter | KeyCode::Char(' ') => self.apply_selected_control(),
-            _ => {}
+        if action == Action::Filter {
+            self.filter_editing = true;
+            self.set_message(
+                MessageLevel::Info,
+                "Type to filter controls; Enter keeps the filter, Esc clears it",
+            );
+            return;
+        }
+        // Everything else acts on the selected row, which must be listed.
+        if !self.selection_visible() {
+            return;
         }
-    }
 
-    fn on_rgb_key(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                self.selected_rgb_field = self
-                    .selected_rgb_field
-                    .checked_sub(1)
-                    .unwrap_or(RgbField::ALL.len() - 1);
-            }
-            KeyCode::Down | KeyCode::Char('j') => {
-                self.selected_rgb_field = (self.selected_rgb_field + 1) % RgbField::ALL.len();
-            }
-            KeyCode::Left | KeyCode::Char('h') => self.adjust_rgb(-1),
-            KeyCode::Right | KeyCode::Char('l') => self.adjust_rgb(1),
-            KeyCode::Enter | KeyCode::Char(' ') => self.apply_rgb(),
+        match action {
+            Action::Up => self.move_control_selection(-1),
+            Action::Down => self.move_control_selection(1),
+            Action::Top => self.jump_control_selection(isize::MIN),
+            Action::Bottom => self.jump_control_selection(isize::MAX),
+            Action::PageUp => self.jump_control_selection(-CONTROL_PAGE),
+            Action::PageDown => self.jump_control_selection(CONTROL_PAGE),
+            Action::Left => self.cycle_control(-1),
+            Action::Right => self.cycle_control(1),
+            Action::Apply => self.apply_selected_control(),
+            Action::Edit => self.start_edit(None),
             _ => {}
         }
     }
 
-    fn on_sensors_key(&mut self, key: KeyEvent) {
-        if matches!(key.code, KeyCode::Enter | KeyCode::Char(' ')) {
+    fn on_profile_command(&mut self, command: ProfileCommand) {
+        match command {
+            ProfileCommand::Apply(index) => {
+                self.profiles_popup = None;
+                self.apply_profile(index);
+            }
+            ProfileCommand::SaveAs(name) => self.save_current_profile(name),
+            ProfileCommand::Rename(index, name) => {
+                if let Some(profile) = self.config.profiles.get_mut(index) {
+                    let old = std::mem::replace(&mut profile.name, name.clone());
+                    self.save_profiles(format!("Renamed profile '{old}' to '{name}'"));
+                }
+            }
+            ProfileCommand::ArmDelete(index) => {
+                if let Some(profile) = self.config.profiles.get(index) {
+                    let text = format!("Press d again to delete profile '{}'", profile.name);
+                    self.set_message(MessageLevel::Warning, text);
+                }
+            }
+            ProfileCommand::Delete(index) => {
+                if index < self.config.profiles.len() {
+                    let profile = self.config.profiles.remove(index);
+                    self.save_profiles(format!("Deleted profile '{}'", profile.name));
+                }
+            }
+            ProfileCommand::Close => self.profiles_popup = None,
+        }
+    }
+
+    /// Saves the current controls and lighting, replacing a profile of the same name.
+    fn save_current_profile(&mut self, name: String) {
+        let controls = self
+            .controls
+            .iter()
+            .filter(|item| item.id.is_persistent() && item.last_error.is_none())
+            .map(|item| (item.id.key().to_string(), item.raw.clone()))
+            .collect();
+        let rgb = self.rgb.to_config();
+
+        let existing = self
+            .config
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name);
+        let message = match existing {
+            Some(index) => {
+                let profile = &mut self.config.profiles[index];
+                profile.controls = controls;
+                profile.rgb = rgb;
+                if let Some(popup) = self.profiles_popup.as_mut() {
+                    popup.selected = index;
+                }
+                format!("Updated profile '{name}'")
+            }
+            None => {
+                self.config.profiles.push(Profile {
+                    name: name.clone(),
+                    controls,
+                    rgb,
+                    extra: Default::default(),
+                });
+                if let Some(popup) = self.profiles_popup.as_mut() {
+                    popup.selected = self.config.profiles.len() - 1;
+                }
+                format!("Saved profile '{name}'")
+            }
+        };
+        self.save_profiles(message);
+    }
+
+    /// Records the current value of the matching controls for `--apply`.
+    fn remember_hardware(&mut self, matches: impl Fn(ControlId) -> bool) -> Result<()> {
+        // Nothing was written in a dry run, so there is nothing to restore.
+        if self.dry_run {
+            return Ok(());
+        }
+
+        for item in &self.controls {
+            if matches(item.id) && item.id.is_persistent() && item.last_error.is_none() {
+                self.config
+                    .hardware
+                    .insert(item.id.key().to_string(), item.raw.clone());
+            }
+        }
+        self.config.save()
+    }
+
+    fn save_profiles(&mut self, message: String) {
+        match self.config.save() {
+            Ok(()) => self.set_message(MessageLevel::Success, message),
+            Err(error) => self.set_message(
+                MessageLevel::Error,
+                format!("{message}; config save failed: {error}"),
+            ),
+        }
+    }
+
+    fn apply_profile(&mut self, index: usize) {
+        if self.read_only {
+            self.set_message(MessageLevel::Warning, READ_ONLY_HINT);
+            return;
+        }
+        let Some(profile) = self.config.profiles.get(index) else {
+            self.set_message(
+                MessageLevel::Warning,
+                format!("No profile in slot {}", index + 1),
+            );
+            return;
+        };
+        if self.control_pending.is_some() || self.rgb_pending {
+            self.set_message(
+                MessageLevel::Warning,
+                "A hardware write is already in progress",
+            );
+            return;
+        }
+
+        let name = profile.name.clone();
+        let rgb = RgbSettings::from_config(&profile.rgb);
+        let request = HardwareRequest::ApplyProfile {
+            name: name.clone(),
+            controls: control_writes(&profile.controls),
+            rgb,
+        };
+        let sets_thermal = profile
+            .controls
+            .contains_key(ControlId::ThermalProfile.key());
+        self.clear_pending_controls();
+        self.rgb = rgb;
+        self.rgb_dirty = false;
+
+        match self.hardware.send(request) {
+            Ok(()) => {
+                self.rgb_pending = true;
+                self.thermal_reverted &= !sets_thermal;
+                self.set_message(MessageLevel::Info, format!("Applying profile '{name}'"));
+            }
+            Err(error) => self.set_message(MessageLevel::Error, error.to_string()),
+        }
+    }
+
+    fn start_edit(&mut self, first_digit: Option<char>) {
+        let Some(item) = self.selected_control() else {
+            return;
+        };
+        let (id, label) = (item.id, item.label());
+        let Some(hint) = item.typed_hint() else {
+            self.set_message(
+                MessageLevel::Info,
+                format!("{label} only takes its listed choices"),
+            );
+            return;
+        };
+
+        self.editing = Some(ControlEdit {
+            id,
+            buffer: first_digit.map(String::from).unwrap_or_default(),
+            rejected_at: None,
+        });
+        self.set_message(
+            MessageLevel::Info,
+            format!("Type {label} ({hint}), Enter to apply, Esc to cancel"),
+        );
+    }
+
+    /// Keys while typing a value: digits and Backspace edit, Enter applies
+    /// and Esc cancels. Everything else is ignored.
+    fn on_edit_key(&mut self, key: KeyEvent) {
+        let Some(edit) = self.editing.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Char(digit @ '0'..='9') if edit.buffer.len() < EDIT_MAX_DIGITS => {
+                edit.buffer.push(digit);
+            }
+            KeyCode::Backspace => {
+                edit.buffer.pop();
+            }
+            KeyCode::Esc => {
+                self.editing = None;
+                self.set_message(MessageLevel::Info, "Edit cancelled");
+            }
+            KeyCode::Enter => self.commit_edit(),
+            _ => {}
+        }
+    }
+
+    fn commit_edit(&mut self) {
+        let Some(edit) = self.editing.as_mut() else {
+            return;
+        };
+        let id = edit.id;
+        let item = self.controls.iter().find(|item| item.id == id);
+
+        let value = edit
+            .buffer
+            .parse::<u8>()
+            .ok()
+            .filter(|&value| item.is_some_and(|item| item.accepts_typed(value)));
+        let Some(value) = value else {
+            edit.rejected_at = Some(Instant::now());
+            let hint = item.and_then(ControlItem::typed_hint).unwrap_or_default();
+            self.set_message(
+                MessageLevel::Warning,
+                format!("{} accepts {hint}", id.label()),
+            );
+            return;
+        };
+
+        self.editing = None;
+        if let Some(item) = self.controls.iter_mut().find(|item| item.id == id) {
+            item.preview_typed(value);
+        }
+        self.apply_selected_control();
+    }
+
+    fn on_rgb_action(&mut self, action: Action, modifiers: KeyModifiers) {
+        let field = RgbField::ALL[self.selected_rgb_field];
+        if let Some(movement) = slider_move(action, modifiers).filter(|_| field.is_slider()) {
+            self.move_rgb_slider(field, movement);
+            return;
+        }
+
+        match action {
+            Action::Up => {
+                self.selected_rgb_field = self
+                    .selected_rgb_field
+                    .checked_sub(1)
+                    .unwrap_or(RgbField::ALL.len() - 1);
+            }
+            Action::Down => {
+                self.selected_rgb_field = (self.selected_rgb_field + 1) % RgbField::ALL.len();
+            }
+            Action::Top => self.selected_rgb_field = 0,
+            Action::Bottom => self.selected_rgb_field = RgbField::ALL.len() - 1,
+            Action::Left => self.adjust_rgb(-1),
+            Action::Right => self.adjust_rgb(1),
+            Action::Apply => self.apply_rgb(),
+            Action::HexColor => self.open_color_input(ColorInput::Hex(String::new())),
+            Action::ColorPicker => self.open_color_input(ColorInput::hsv(self.edited_color())),
+            Action::Zones => self.toggle_zones(),
+            Action::Direction => self.cycle_direction(),
+            Action::Power => self.toggle_rgb_power(),
+            Action::AutoApply => self.toggle_auto_apply(),
+            _ => {}
+        }
+    }
+
+    /// Switches between Off and the remembered effect and applies it. Waits
+    /// for a pending write, since nothing would be sent for the new state.
+    fn toggle_rgb_power(&mut self) {
+        if self.rgb_pending {
+            self.set_message(MessageLevel::Warning, "RGB write is already in progress");
+            return;
+        }
+        self.rgb.toggle_power();
+        self.rgb_dirty = true;
+        self.apply_rgb();
+    }
+
+    /// Switches between the Zones effect and Static; the two are never
+    /// combined with an animation.
+    fn toggle_zones(&mut self) {
+        if self.rgb.is_zones() {
+            self.rgb.effect_idx = STATIC_EFFECT_INDEX;
+        } else {
+            self.rgb.effect_idx = ZONES_EFFECT_INDEX;
+            self.selected_rgb_field = RgbField::ALL
+                .iter()
+                .position(|field| *field == RgbField::Zone)
+                .unwrap_or(0);
+        }
+        self.rgb_changed(format!("{} mode", self.rgb.effect().name));
+    }
+
+    /// The colour the Color row edits: the selected zone's in Zones mode.
+    fn edited_color(&self) -> Rgb {
+        if self.rgb.is_zones() {
+            self.rgb.zones[self.selected_zone]
+        } else {
+            self.rgb.color_rgb()
+        }
+    }
+
+    fn open_color_input(&mut self, input: ColorInput) {
+        if RgbField::ALL[self.selected_rgb_field] != RgbField::Color {
+            self.set_message(MessageLevel::Info, "Select the Color row first");
+            return;
+        }
+        if !self.rgb.uses_color() {
+            self.set_message(
+                MessageLevel::Info,
+                format!("{} does not use a colour", self.rgb.effect().name),
+            );
+            return;
+        }
+
+        let hint = match input {
+            ColorInput::Hex(_) => "Type RGB or RRGGBB, Enter to preview, Esc to cancel",
+            ColorInput::Hsv { .. } => {
+                "←→ hue, ↑↓ value, Shift+←→ saturation, Enter to preview, Esc to cancel"
+            }
+        };
+        self.color_input = Some(input);
+        self.set_message(MessageLevel::Info, hint);
+    }
+
+    fn on_sensors_action(&mut self, action: Action) {
+        if action == Action::Apply {
             self.request_snapshot();
             self.set_message(MessageLevel::Info, "Sensor refresh requested");
         }
     }
 
+    /// Steps one listed row, wrapping around.
     fn move_control_selection(&mut self, step: isize) {
-        self.clear_pending_controls();
-        let len = self.controls.len();
-        if step < 0 {
-            self.selected_control = self.selected_control.checked_sub(1).unwrap_or(len - 1);
+        let visible = self.visible_controls();
+        let len = visible.len();
+        let Some(position) = self.selected_position(&visible) else {
+            return;
+        };
+
+        let next = if step < 0 {
+            position.checked_sub(1).unwrap_or(len - 1)
         } else {
-            self.selected_control = (self.selected_control + 1) % len;
+            (position + 1) % len
+        };
+        self.select_control(visible[next]);
+    }
+
+    /// Moves `step` listed rows, stopping at the first and last.
+    fn jump_control_selection(&mut self, step: isize) {
+        let visible = self.visible_controls();
+        let Some(position) = self.selected_position(&visible) else {
+            return;
+        };
+
+        let next = position.saturating_add_signed(step).min(visible.len() - 1);
+        self.select_control(visible[next]);
+    }
+
+    fn select_control(&mut self, index: usize) {
+        if index == self.selected_control {
+            return;
+        }
+
+        let from_fan = self.selected_control_is_fan();
+        self.selected_control = index;
+        // Both fan rows are previewed together and written by one Enter.
+        if !(from_fan && self.selected_control_is_fan()) {
+            self.clear_pending_controls();
+        }
+    }
+
+    /// Indices into `controls` of the rows the filter lets through.
+    pub(crate) fn visible_controls(&self) -> Vec<usize> {
+        visible_control_indices(&self.controls, &self.control_filter)
+    }
+
+    fn selected_position(&self, visible: &[usize]) -> Option<usize> {
+        visible
+            .iter()
+            .position(|&index| index == self.selected_control)
+    }
+
+    fn selection_visible(&self) -> bool {
+        self.visible_controls().contains(&self.selected_control)
+    }
+
+    fn on_filter_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.filter_editing = false;
+                self.set_control_filter(String::new());
+                self.set_message(MessageLevel::Info, "Filter cleared");
+            }
+            KeyCode::Enter => self.filter_editing = false,
+            KeyCode::Up => self.move_control_selection(-1),
+            KeyCode::Down => self.move_control_selection(1),
+            KeyCode::Backspace => {
+                let mut filter = self.control_filter.clone();
+                filter.pop();
+                self.set_control_filter(filter);
+            }
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                let filter = format!("{}{c}", self.control_filter);
+                self.set_control_filter(filter);
+            }
+            _ => {}
+        }
+    }
+
+    fn set_control_filter(&mut self, filter: String) {
+        self.control_filter = filter;
+        self.keep_selection_visible();
+    }
+
+    /// Moves the selection to the first listed row if the filter hid it.
+    fn keep_selection_visible(&mut self) {
+        if let Some(&first) = self.visible_controls().first() {
+            if !self.selection_visible() {
+                self.select_control(first);
+            }
+        }
+    }
+
+    fn selected_control_is_fan(&self) -> bool {
+        self.controls
+            .get(self.selected_control)
+            .is_some_and(|item| item.id.is_fan())
+    }
+
+    /// The fan row's previewed percentage, or its current one.
+    fn fan_percent(&self, id: ControlId) -> u8 {
+        self.controls
+            .iter()
+            .find(|item| item.id == id)
+            .and_then(|item| {
+                item.pending_choice()
+                    .map_or(item.raw.as_str(), |choice| choice.value.as_str())
+                    .parse()
+                    .ok()
+            })
+            .unwrap_or(0)
+    }
+
+    fn pending_fan_speeds(&self) -> (String, bool) {
+        compose_fan_speeds(
+            self.fan_percent(ControlId::CpuFan),
+            self.fan_percent(ControlId::GpuFan),
+            self.config.per_fan_auto,
+        )
+    }
+
+    fn fan_preview_message(&self) -> String {
+        let fan = self.config.fan_control();
+        let label = |percent: u8| match percent {
+            0 => "Auto".to_string(),
+            percent => fan.label(percent),
+        };
+        let (value, forced_auto) = self.pending_fan_speeds();
+        let mut message = format!(
+            "Preview fans: CPU {} / GPU {}",
+            label(self.fan_percent(ControlId::CpuFan)),
+            label(self.fan_percent(ControlId::GpuFan))
+        );
+        if forced_auto {
+            message.push_str(&format!(
+                " (writes {value}: this module only returns both fans to Auto together)"
+            ));
         }
+        message
     }
 
     fn cycle_control(&mut self, step: i8) {
@@ -426,6 +1718,12 @@ impl App {
             return;
         };
 
+        if self.selected_control_is_fan() && message.0 == MessageLevel::Info {
+            let preview = self.fan_preview_message();
+            self.set_message(MessageLevel::Info, preview);
+            return;
+        }
+
         self.set_message(message.0, message.1);
     }
 
@@ -447,6 +1745,17 @@ impl App {
                 let value = if item.raw == "1" { "0" } else { "1" };
                 Some((item.id, value.to_string()))
             }
+            ControlKind::Choice(_) if item.id.is_fan() => {
+                if !self
+                    .controls
+                    .iter()
+                    .any(|fan| fan.id.is_fan() && fan.pending.is_some())
+                {
+                    self.cycle_control(1);
+                    return;
+                }
+                Some((item.id, self.pending_fan_speeds().0))
+            }
             ControlKind::Choice(choices) => {
                 let Some(index) = item.pending else {
                     self.cycle_control(1);
@@ -463,27 +1772,150 @@ impl App {
             return;
         };
 
+        if id == ControlId::BatteryCalibration
+            && value == "1"
+            && self.power.ac_online == Some(false)
+        {
+            self.set_message(
+                MessageLevel::Warning,
+                "Connect the AC adapter before starting battery calibration",
+            );
+            return;
+        }
+
+        if let Some(consequence) = id.confirmation(&value) {
+            self.confirm = Some(PendingConfirm {
+                id,
+                value,
+                consequence,
+            });
+            return;
+        }
+
+        self.send_control(id, value);
+    }
+
+    fn send_control(&mut self, id: ControlId, value: String) {
         match self
             .hardware
             .send(HardwareRequest::ApplyControl { id, value })
         {
             Ok(()) => {
                 self.control_pending = Some(id);
+                self.thermal_reverted &= id != ControlId::ThermalProfile;
                 self.set_message(MessageLevel::Info, format!("Applying {}", id.label()));
             }
             Err(error) => self.set_message(MessageLevel::Error, error.to_string()),
         }
     }
 
+    /// The firmware's own `backlight_timeout` is on; it takes precedence
+    /// over `rgb_idle_minutes`.
+    pub(crate) fn hardware_backlight_timeout(&self) -> bool {
+        self.controls
+            .iter()
+            .any(|item| item.id == ControlId::BacklightTimeout && item.raw.trim() != "0")
+    }
+
+    /// The first snapshot has said whether a keyboard is there.
+    pub(crate) fn keyboard_scanned(&self) -> bool {
+        self.keyboard_hotplug.scanned
+    }
+
+    pub(crate) fn calibration_active(&self) -> bool {
+        self.controls
+            .iter()
+            .any(|item| item.id == ControlId::BatteryCalibration && item.raw == "1")
+    }
+
     fn adjust_rgb(&mut self, step: i8) {
         let field = RgbField::ALL[self.selected_rgb_field];
-        self.rgb.adjust(field, step);
+        match field {
+            RgbField::Zone if self.rgb.is_zones() => {
+                self.selected_zone = if step < 0 {
+                    (self.selected_zone + ZONE_COUNT - 1) % ZONE_COUNT
+                } else {
+                    (self.selected_zone + 1) % ZONE_COUNT
+                };
+                return;
+            }
+            RgbField::Zone => {
+                self.set_message(MessageLevel::Info, "Press z to edit zones");
+                return;
+            }
+            RgbField::Color if self.rgb.is_zones() => {
+                self.rgb.step_zone_color(self.selected_zone, step);
+            }
+            RgbField::Effect => {
+                let quirks = quirks::detected().quirks();
+                let offered = |index| quirks.offers_effect(index);
+                self.rgb.step_effect(step, offered);
+            }
+            _ => self.rgb.adjust(field, step),
+        }
+        self.rgb_changed(format!("{} changed", field.label()));
+    }
+
+    fn move_rgb_slider(&mut self, field: RgbField, movement: SliderMove) {
+        // Home and End count as navigation, so read-only mode gets here.
+        if self.read_only {
+            self.set_message(MessageLevel::Warning, READ_ONLY_HINT);
+            return;
+        }
+        if let Some(value) = self.rgb.move_slider(field, movement) {
+            self.rgb_changed(format!("{} {value}%", field.label()));
+        }
+    }
+
+    /// Steps the direction from any RGB row. Effects without one leave it
+    /// alone and say so instead of failing.
+    fn cycle_direction(&mut self) {
+        let effect = self.rgb.effect();
+        if !effect.has_direction {
+            let note = format!("{} has no direction; ignored", effect.name);
+            self.set_message(MessageLevel::Info, note);
+            return;
+        }
+        self.rgb.adjust(RgbField::Direction, 1);
+        self.rgb_changed(format!("Direction: {}", self.rgb.direction_name()));
+    }
+
+    /// Marks the panel dirty and, with auto-apply on, (re)starts the debounce
+    /// window so a held arrow key sends one write once it is released.
+    fn rgb_changed(&mut self, what: String) {
         self.rgb_dirty = true;
         self.focus_pulse = 1.0;
-        self.set_message(
-            MessageLevel::Info,
-            format!("{} changed; Enter applies lighting", field.label()),
-        );
+        if self.config.auto_apply {
+            self.pending_apply_at = Some(Instant::now() + RGB_APPLY_DEBOUNCE);
+            self.set_message(MessageLevel::Info, what);
+        } else {
+            self.set_message(
+                MessageLevel::Info,
+                format!("{what}; Enter applies lighting"),
+            );
+        }
+    }
+
+    pub(crate) fn auto_apply(&self) -> bool {
+        self.config.auto_apply
+    }
+
+    fn toggle_auto_apply(&mut self) {
+        self.config.auto_apply = !self.config.auto_apply;
+        let state = if self.config.auto_apply { "on" } else { "off" };
+        if self.config.auto_apply && self.rgb_dirty {
+            self.pending_apply_at = Some(Instant::now() + RGB_APPLY_DEBOUNCE);
+        } else if !self.config.auto_apply {
+            self.pending_apply_at = None;
+        }
+
+        match self.config.save() {
+            Ok(()) => self.set_message(MessageLevel::Info, format!("RGB auto-apply {state}")),
+            Err(error) => self.set_message(
+                MessageLevel::Error,
+                format!("RGB auto-apply {state}; config save failed: {error}"),
+            ),
+        }
     }
 
     fn apply_rgb(&mut self) {
@@ -498,37 +1930,121 @@ impl App {
         {
             Ok(()) => {
                 self.rgb_pending = true;
+                self.pending_apply_at = None;
                 self.set_message(MessageLevel::Info, "Applying keyboard lighting");
             }
             Err(error) => self.set_message(MessageLevel::Error, error.to_string()),
         }
     }
 
-    fn replace_controls(&mut self, mut controls: Vec<ControlItem>, preserve_pending: bool) {
+    /// Re-sends the last saved lighting, leaving unsaved edits in the panel
+    /// alone. Idle blanks and the low-battery pulse send a variation of it.
+    /// Callers wait for a pending RGB write; one reply only clears one.
+    fn restore_rgb(&mut self, reason: RgbRestore) {
+        if self.rgb_pending {
+            return;
+        }
+        let saved = RgbSettings::from_config(&self.config.rgb);
+        let settings = reason.settings(saved, self.idle.blanked());
+        self.restore_reason = reason.label();
+
+        match self.hardware.send(HardwareRequest::RestoreRgb(settings)) {
+            Ok(()) => {
+                self.rgb_pending = true;
+                self.set_message(MessageLevel::Info, reason.progress());
+            }
+            Err(error) => self.set_message(MessageLevel::Error, error.to_string()),
+        }
+    }
+
+    /// Merges re-read controls in place; only `complete` reads may drop rows.
+    /// Rows that appear after the first scan are highlighted for a while.
+    /// Returns the rows whose value moved, with the value shown before;
+    /// none on the first scan.
+    fn update_controls(
+        &mut self,
+        incoming: Vec<ControlItem>,
+        complete: bool,
+    ) -> Vec<(ControlId, String)> {
         let selected_id = self.controls.get(self.selected_control).map(|item| item.id);
+        let first_scan = self.controls.is_empty();
 
-        if preserve_pending {
-            for incoming in &mut controls {
-                if let Some(existing) = self.controls.iter().find(|item| item.id == incoming.id) {
-                    incoming.pending = existing.pending;
-                }
+        let changes = merge_controls(&mut self.controls, incoming, complete);
+        let now = Instant::now();
+        self.appeared
+            .retain(|(_, at)| now.saturating_duration_since(*at) < NEW_CONTROL_HIGHLIGHT);
+        if !first_scan {
+            self.appeared
+                .extend(changes.appeared.into_iter().map(|id| (id, now)));
+        }
+
+        match selected_id.and_then(|id| self.controls.iter().position(|item| item.id == id)) {
+            Some(index) => self.selected_control = index,
+            None if self.selected_control >= self.controls.len() => {
+                self.selected_control = self.controls.len().saturating_sub(1);
             }
+            None => {}
+        }
+        self.keep_selection_visible();
+
+        if first_scan {
+            Vec::new()
+        } else {
+            changes.changed
         }
+    }
 
-        self.controls = controls;
+    pub(crate) fn recently_appeared(&self, id: ControlId) -> bool {
+        self.appeared
+            .iter()
+            .any(|(appeared, at)| *appeared == id && at.elapsed() < NEW_CONTROL_HIGHLIGHT)
+    }
 
-        if let Some(id) = selected_id {
-            if let Some(index) = self.controls.iter().position(|item| item.id == id) {
-                self.selected_control = index;
-                return;
+    /// Marks and logs values a periodic read found changed. Our own writes
+    /// land through `ControlApplied` before the next read sees them, so
+    /// only a write still in flight and the worker's `self_written` ones
+    /// need skipping here.
+    fn note_external_changes(
+        &mut self,
+        changed: Vec<(ControlId, String)>,
+        self_written: &[ControlId],
+        now: Instant,
+    ) {
+        self.changed_externally
+            .retain(|(_, at)| now.saturating_duration_since(*at) < EXTERNAL_CHANGE_HIGHLIGHT);
+
+        let mut lines = Vec::new();
+        for (id, before) in changed {
+            if self_written.contains(&id) || self.control_pending == Some(id) {
+                continue;
             }
+            let Some(item) = self.controls.iter().find(|item| item.id == id) else {
+                continue;
+            };
+            lines.push(format!("{} {before} → {}", id.label(), item.display));
+            self.changed_externally
+                .retain(|(changed, _)| *changed != id);
+            self.changed_externally.push((id, now));
         }
 
-        if self.selected_control >= self.controls.len() {
-            self.selected_control = self.controls.len().saturating_sub(1);
+        if !lines.is_empty() {
+            self.set_message(
+                MessageLevel::Info,
+                format!("Changed outside arch-sense: {}", lines.join(", ")),
+            );
         }
     }
 
+    /// How long ago a read found `id` changed outside arch-sense, while it
+    /// is still marked.
+    pub(crate) fn external_change_age(&self, id: ControlId) -> Option<Duration> {
+        self.changed_externally
+            .iter()
+            .find(|(changed, _)| *changed == id)
+            .map(|(_, at)| at.elapsed())
+            .filter(|age| *age < EXTERNAL_CHANGE_HIGHLIGHT)
+    }
+
     fn mark_control_error(&mut self, id: ControlId, error: String) {
         if let Some(item) = self.controls.iter_mut().find(|item| item.id == id) {
             item.last_error = Some(error);
@@ -542,19 +2058,363 @@ impl App {
     }
 
     fn set_message(&mut self, level: MessageLevel, text: impl Into<String>) {
-        self.message = StatusMessage {
-            level,
-            text: text.into(),
-        };
+        let text = text.into();
+        self.log.push(level, &text, SystemTime::now());
+        self.message = StatusMessage { level, text };
     }
 
+    pub(crate) fn profiles(&self) -> &[Profile] {
+        &self.config.profiles
+    }
+
+    /// The selected row, unless the filter hides it.
     pub(crate) fn selected_control(&self) -> Option<&ControlItem> {
-        self.controls.get(self.selected_control)
+        self.selection_visible()
+            .then(|| self.controls.get(self.selected_control))
+            .flatten()
+    }
+}
+
+/// Builds the `cpu,gpu` value for fan_speed, where 0 means auto. Returns
+/// whether Auto on one fan had to be widened to both (`per_fan_auto` off).
+fn compose_fan_speeds(cpu: u8, gpu: u8, per_fan_auto: bool) -> (String, bool) {
+    if !per_fan_auto && (cpu == 0) != (gpu == 0) {
+        return ("0,0".to_string(), true);
     }
+
+    (format!("{cpu},{gpu}"), false)
+}
+
+fn visible_control_indices(controls: &[ControlItem], filter: &str) -> Vec<usize> {
+    controls
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.id.matches(filter))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn profile_names(profiles: &[Profile]) -> Vec<String> {
+    profiles
+        .iter()
+        .map(|profile| profile.name.clone())
+        .collect()
 }
 
 impl Drop for App {
     fn drop(&mut self) {
-        let _ = self.hardware.send(HardwareRequest::Shutdown);
+        self.hardware.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ControlChoice;
+
+    #[test]
+    fn unavailable_samples_are_gaps_and_history_is_bounded() {
+        let mut history = VecDeque::new();
+        SensorsState::push_history(&mut history, Some(61.4), 105.0);
+        SensorsState::push_history(&mut history, None, 105.0);
+        SensorsState::push_history(&mut history, Some(140.0), 105.0);
+        assert_eq!(history, [Some(61), None, Some(105)]);
+
+        for _ in 0..HISTORY_LIMIT {
+            SensorsState::push_history(&mut history, Some(50.0), 105.0);
+        }
+        assert_eq!(history.len(), HISTORY_LIMIT);
+    }
+
+    #[test]
+    fn filter_matches_label_or_description_ignoring_case() {
+        let controls = vec![
+            control(ControlId::ThermalProfile, "quiet", &["quiet"]),
+            control(ControlId::CpuFan, "0", &["0", "50"]),
+            control(ControlId::GpuFan, "0", &["0", "50"]),
+            control(ControlId::UsbCharging, "0", &["0", "10"]),
+        ];
+
+        assert_eq!(visible_control_indices(&controls, ""), [0, 1, 2, 3]);
+        assert_eq!(visible_control_indices(&controls, "FAN"), [1, 2]);
+        assert_eq!(visible_control_indices(&controls, "laptop is off"), [3]);
+        assert!(visible_control_indices(&controls, "xyz").is_empty());
+    }
+
+    #[test]
+    fn status_log_is_bounded_collapses_repeats_and_clamps_scrolling() {
+        let now = SystemTime::now();
+        let mut log = StatusLog::default();
+        let newest = |log: &StatusLog| log.visible().next().unwrap().text.clone();
+        for index in 0..LOG_LIMIT + 5 {
+            log.push(MessageLevel::Info, &format!("message {index}"), now);
+        }
+        log.push(MessageLevel::Info, "message 204", now);
+        assert_eq!(log.len(), LOG_LIMIT);
+        assert_eq!(newest(&log), "message 204");
+
+        log.scroll_by(-3);
+        assert_eq!(log.scroll, 0);
+        log.scroll_by(LOG_PAGE as isize);
+        log.push(MessageLevel::Error, "write failed", now);
+        assert_eq!(newest(&log), "message 194");
+
+        log.scroll_by(isize::MAX);
+        assert_eq!(log.scroll, LOG_LIMIT - 1);
+    }
+
+    #[test]
+    fn shift_and_home_end_move_sliders_finely_or_to_the_ends() {
+        let mut rgb = RgbSettings::from_config(&crate::config::RgbConfig::default());
+        let mut press = |action, modifiers| {
+            let movement = slider_move(action, modifiers).unwrap();
+            rgb.move_slider(RgbField::Brightness, movement)
+        };
+
+        assert_eq!(press(Action::Right, KeyModifiers::NONE), Some(40));
+        assert_eq!(press(Action::Right, KeyModifiers::SHIFT), Some(41));
+        assert_eq!(press(Action::Left, KeyModifiers::SHIFT), Some(40));
+        assert_eq!(press(Action::Bottom, KeyModifiers::NONE), Some(100));
+        assert_eq!(press(Action::Right, KeyModifiers::SHIFT), Some(100));
+        assert_eq!(press(Action::Top, KeyModifiers::NONE), Some(0));
+        assert_eq!(press(Action::Left, KeyModifiers::SHIFT), Some(0));
+
+        assert_eq!(slider_move(Action::Apply, KeyModifiers::SHIFT), None);
+        assert_eq!(rgb.move_slider(RgbField::Color, SliderMove::Max), None);
+    }
+
+    #[test]
+    fn delta_spans_the_window_and_respects_gaps() {
+        let second = Duration::from_secs(1);
+        let mut history: VecDeque<Option<u64>> = (40..=100).map(Some).collect();
+        assert_eq!(SensorsState::delta(&history, second), Some(60));
+        // Two samples a second: the same window is twice as many samples.
+        assert_eq!(SensorsState::delta(&history, second / 2), None);
+
+        history.pop_front();
+        assert_eq!(SensorsState::delta(&history, second), None);
+
+        history.push_back(None);
+        assert_eq!(SensorsState::delta(&history, second), None);
+    }
+
+    #[test]
+    fn fan_auto_is_per_fan_only_when_supported() {
+        assert_eq!(
+            compose_fan_speeds(40, 70, false),
+            ("40,70".to_string(), false)
+        );
+        assert_eq!(compose_fan_speeds(0, 0, false), ("0,0".to_string(), false));
+        assert_eq!(compose_fan_speeds(0, 70, false), ("0,0".to_string(), true));
+        assert_eq!(compose_fan_speeds(0, 70, true), ("0,70".to_string(), false));
+    }
+
+    #[test]
+    fn confirm_popup_accepts_only_yes_or_no() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        assert_eq!(confirm_answer(&key(KeyCode::Char('y'))), Some(true));
+        assert_eq!(confirm_answer(&key(KeyCode::Enter)), Some(true));
+        assert_eq!(confirm_answer(&key(KeyCode::Char('N'))), Some(false));
+        assert_eq!(confirm_answer(&key(KeyCode::Esc)), Some(false));
+        for code in [KeyCode::Char('q'), KeyCode::Tab, KeyCode::Down] {
+            assert_eq!(confirm_answer(&key(code)), None);
+        }
+        assert!(ControlId::BatteryCalibration.confirmation("1").is_some());
+        assert!(ControlId::BatteryCalibration.confirmation("0").is_none());
+        assert!(ControlId::GpuFan.confirmation("100,100").is_some());
+    }
+
+    #[test]
+    fn auto_apply_waits_for_quiet_period_and_idle_keyboard() {
+        let start = Instant::now();
+        let mut pending = None;
+        assert!(!take_due_apply(&mut pending, false, start));
+
+        // Each change pushes the deadline out again.
+        pending = Some(start + RGB_APPLY_DEBOUNCE);
+        assert!(!take_due_apply(
+            &mut pending,
+            false,
+            start + Duration::from_millis(100)
+        ));
+        pending = Some(start + Duration::from_millis(100) + RGB_APPLY_DEBOUNCE);
+        assert!(!take_due_apply(
+            &mut pending,
+            false,
+            start + RGB_APPLY_DEBOUNCE
+        ));
+
+        let due = start + Duration::from_millis(500);
+        assert!(!take_due_apply(&mut pending, true, due));
+        assert!(take_due_apply(&mut pending, false, due));
+        assert_eq!(pending, None);
+    }
+
+    fn control(id: ControlId, raw: &str, choices: &[&str]) -> ControlItem {
+        ControlItem {
+            id,
+            raw: raw.to_string(),
+            display: raw.to_string(),
+            kind: ControlKind::Choice(
+                choices
+                    .iter()
+                    .map(|&value| ControlChoice::new(value, value))
+                    .collect(),
+            ),
+            pending: None,
+            last_error: None,
+            writable: true,
+        }
+    }
+
+    #[test]
+    fn merge_updates_values_in_place_and_keeps_previews() {
+        let mut current = vec![
+            control(ControlId::ThermalProfile, "quiet", &["quiet", "balanced"]),
+            control(ControlId::UsbCharging, "0", &["0", "10"]),
+        ];
+        current[0].pending = Some(1);
+        current[1].pending = Some(1);
+
+        let changes = merge_controls(
+            &mut current,
+            vec![
+                control(ControlId::UsbCharging, "10", &["0", "10", "20"]),
+                control(
+                    ControlId::ThermalProfile,
+                    "balanced",
+                    &["quiet", "balanced"],
+                ),
+            ],
+            true,
+        );
+
+        assert!(changes.appeared.is_empty());
+        assert_eq!(current[0].id, ControlId::ThermalProfile);
+        assert_eq!(current[0].raw, "balanced");
+        assert_eq!(current[0].pending, Some(1));
+        // New choices invalidate the preview index.
+        assert_eq!(current[1].raw, "10");
+        assert_eq!(current[1].pending, None);
+    }
+
+    #[test]
+    fn merge_adds_rows_in_order_and_only_complete_reads_remove() {
+        let mut current = vec![
+            control(ControlId::ThermalProfile, "quiet", &["quiet"]),
+            control(ControlId::UsbCharging, "0", &["0"]),
+        ];
+
+        // A single-control refresh after a write leaves the others alone.
+        let changes = merge_controls(
+            &mut current,
+            vec![control(ControlId::UsbCharging, "0", &["0"])],
+            false,
+        );
+        assert_eq!(changes, ControlChanges::default());
+        assert_eq!(current.len(), 2);
+
+        let changes = merge_controls(
+            &mut current,
+            vec![
+                control(ControlId::ThermalProfile, "quiet", &["quiet"]),
+                control(ControlId::CpuFan, "0", &["0"]),
+            ],
+            true,
+        );
+        assert_eq!(changes.appeared, vec![ControlId::CpuFan]);
+        assert_eq!(
+            current.iter().map(|item| item.id).collect::<Vec<_>>(),
+            vec![ControlId::ThermalProfile, ControlId::CpuFan]
+        );
+    }
+
+    #[test]
+    fn merge_reports_values_that_moved_between_readable_reads() {
+        let profiles = &["balanced", "performance"];
+        let mut current = vec![
+            control(ControlId::ThermalProfile, "balanced", profiles),
+            control(ControlId::BootAnimation, "0", &["0", "1"]),
+            control(ControlId::UsbCharging, "0", &["0", "10"]),
+        ];
+        current[1].last_error = Some("permission denied".to_string());
+
+        // The turbo button switched the profile; Boot Animation only became
+        // readable, which is not a change.
+        let changes = merge_controls(
+            &mut current,
+            vec![
+                control(ControlId::ThermalProfile, "performance", profiles),
+                control(ControlId::BootAnimation, "1", &["0", "1"]),
+                control(ControlId::UsbCharging, "0", &["0", "10"]),
+            ],
+            true,
+        );
+
+        assert_eq!(
+            changes.changed,
+            vec![(ControlId::ThermalProfile, "balanced".to_string())]
+        );
+        assert!(changes.appeared.is_empty());
+    }
+
+    #[test]
+    fn low_battery_fires_once_per_discharge_below_threshold() {
+        let mut watch = LowBatteryWatch::default();
+        let power = |percent: u8, ac: bool| PowerStatus {
+            ac_online: Some(ac),
+            battery_percent: Some(percent),
+            battery_status: Some(if ac { "Charging" } else { "Discharging" }.to_string()),
+            battery_watts: None,
+        };
+
+        assert!(!watch.observe(&power(40, false), 15));
+        assert!(!watch.observe(&power(14, true), 15));
+        assert!(watch.observe(&power(14, false), 15));
+        assert!(!watch.observe(&power(12, false), 15));
+        // Charging back into the hysteresis band does not re-arm it.
+        assert!(!watch.observe(&power(18, true), 15));
+        assert!(!watch.observe(&power(14, false), 15));
+        assert!(!watch.observe(&power(20, true), 15));
+        assert!(watch.observe(&power(14, false), 15));
+        assert!(!watch.observe(&PowerStatus::default(), 15));
+    }
+
+    #[test]
+    fn warning_pulse_returns_to_the_saved_or_blanked_lighting() {
+        let saved = RgbSettings::from_config(&AppConfig::default().rgb);
+
+        let pulse = RgbRestore::LowBattery.settings(saved, false);
+        assert_eq!(pulse.effect_idx, BREATHING_EFFECT_INDEX);
+        assert_eq!(pulse.color_rgb(), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(pulse.zones, saved.zones);
+        assert_eq!(RgbRestore::LowBattery.settings(saved, true), pulse);
+
+        assert_eq!(RgbRestore::PulseDone.settings(saved, false), saved);
+        let dark = RgbRestore::PulseDone.settings(saved, true);
+        assert_eq!(dark.effect_idx, OFF_EFFECT_INDEX);
+        assert_eq!(RgbRestore::Reconnect.settings(saved, true), dark);
+        assert_eq!(RgbRestore::IdleWake.settings(saved, true), saved);
+    }
+
+    #[test]
+    fn keyboard_restore_waits_for_reconnect_to_settle() {
+        let start = Instant::now();
+        let mut hotplug = KeyboardHotplug::default();
+
+        hotplug.observe(true, start);
+        assert!(!hotplug.take_due(start + KEYBOARD_SETTLE));
+
+        hotplug.observe(false, start + Duration::from_secs(1));
+        hotplug.observe(true, start + Duration::from_secs(2));
+        assert!(!hotplug.take_due(start + Duration::from_secs(3)));
+
+        // Re-enumerating again during resume restarts the settle window.
+        hotplug.observe(false, start + Duration::from_secs(3));
+        hotplug.observe(true, start + Duration::from_secs(4));
+        assert!(!hotplug.take_due(start + Duration::from_secs(5)));
+        assert!(hotplug.take_due(start + Duration::from_secs(6)));
+        assert!(!hotplug.take_due(start + Duration::from_secs(9)));
     }
 }
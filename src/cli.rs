@@ -1,4 +1,7 @@
-use clap::Parser;
+use clap::{ArgGroup, CommandFactory, Parser};
+use clap_complete::Shell;
+
+use crate::models::{ApplyTarget, RgbBackend};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -6,20 +9,56 @@ use clap::Parser;
     author,
     version,
     about = "Acer Predator hardware control center",
-    long_about = "A modern TUI and CLI tool for managing Acer Predator hardware on Arch Linux, including keyboard RGB, thermal profiles, fan speeds, and battery health settings."
+    long_about = "A modern TUI and CLI tool for managing Acer Predator hardware on Arch Linux, including keyboard RGB, thermal profiles, fan speeds, and battery health settings.",
+    group(ArgGroup::new("report").args(["doctor", "status"]))
 )]
 pub struct Cli {
-    /// Check hardware permissions and system status
+    /// Check the kernel module, permissions, sensors and keyboard
     #[arg(long)]
     pub doctor: bool,
 
+    /// Print sensors, settings, lighting and the keyboard once and exit
+    #[arg(long, long_help = STATUS_HELP)]
+    pub status: bool,
+
+    /// Print the --doctor or --status report as JSON
+    #[arg(long, requires = "report")]
+    pub json: bool,
+
     /// One-time setup for running without sudo
     #[arg(long)]
     pub install_permissions: bool,
 
-    /// Apply saved RGB settings without launching the TUI
+    /// Apply saved settings without launching the TUI (default: all)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all"
+    )]
+    pub apply: Option<ApplyTarget>,
+
+    /// Log hardware writes instead of performing them; reads still work
     #[arg(long)]
-    pub apply: bool,
+    pub dry_run: bool,
+
+    /// Force the keyboard RGB transport instead of the saved config value
+    #[arg(long, value_enum, value_name = "BACKEND")]
+    pub rgb_backend: Option<RgbBackend>,
+
+    /// Milliseconds between sensor reads (200-5000) instead of the config value
+    #[arg(long, value_name = "MS")]
+    pub refresh: Option<u64>,
+
+    /// Colour preset for this run: default, predator, mono or light
+    #[arg(long, value_name = "NAME")]
+    pub theme: Option<String>,
+
+    /// Print a shell completion script and exit
+    #[arg(long, value_enum, value_name = "SHELL")]
+    pub completions: Option<Shell>,
 
     /// Internal: Run permission installation as root (triggered via pkexec)
     #[arg(long, hide = true)]
@@ -28,4 +67,96 @@ pub struct Cli {
     /// Internal: Directly apply permissions to sysfs and config directories
     #[arg(long, hide = true)]
     pub apply_permissions: bool,
+
+    /// Internal: Panic after the first frame to check terminal restoration
+    #[cfg(debug_assertions)]
+    #[arg(long, hide = true)]
+    pub debug_panic: bool,
+}
+
+const STATUS_HELP: &str = "\
+Print sensors, settings, lighting and the keyboard once and exit, without
+the TUI. Works unprivileged; anything that cannot be read is null (N/A in
+the table). With --json the document looks like this:
+
+  {
+    \"schema\": 1,                      bumped on incompatible changes
+    \"model\": \"Predator PH16-71\",
+    \"module_loaded\": true,
+    \"sensors\": {
+      \"cpu_temp_c\": 61.0, \"gpu_temp_c\": null,
+      \"cpu_fan_rpm\": 2400.0, \"gpu_fan_rpm\": 2300.0,
+      \"cpu_fan_mode\": \"auto\", \"gpu_fan_mode\": \"auto\"   auto or max
+    },
+    \"power\": {
+      \"ac_online\": true, \"battery_percent\": 80,
+      \"battery_status\": \"Charging\", \"battery_watts\": 12.3
+    },
+    \"settings\": {                     every control, keyed as in config.json
+      \"thermal_profile\": { \"value\": \"balanced\", \"label\": \"Balanced\" },
+      \"usb_charging\": null,           missing or unreadable
+      ...
+    },
+    \"rgb\": {                          the saved lighting
+      \"effect\": \"Wave\", \"color\": null, \"brightness\": 100, \"speed\": 50,
+      \"direction\": \"Right\", \"last_effect\": \"Static\"
+    },
+    \"keyboard\": {
+      \"state\": \"accessible\",          or permission_denied, not_found, error
+      \"name\": \"Predator PH16-71\", \"id\": \"04F2:0117\"
+    },
+    \"conflicts\": [\"power-profiles-daemon\"]   also managing platform_profile
+  }
+
+color is #RRGGBB, random, or null when the effect takes no colour;
+direction is null when the effect has none.";
+
+/// Renders the completion script for `shell` from the clap definition above.
+pub fn completion_script(shell: Shell) -> Vec<u8> {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), "arch-sense", &mut script);
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_scripts_cover_the_public_flags() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = String::from_utf8(completion_script(shell)).unwrap();
+            assert!(script.contains("arch-sense"), "{shell}");
+            for flag in [
+                "doctor",
+                "status",
+                "apply",
+                "dry-run",
+                "rgb-backend",
+                "completions",
+            ] {
+                assert!(script.contains(flag), "{shell} is missing {flag}");
+            }
+            assert!(
+                script.contains("hidraw"),
+                "{shell} is missing backend values"
+            );
+        }
+    }
+
+    #[test]
+    fn apply_defaults_to_all_targets() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).map(|cli| cli.apply);
+
+        assert_eq!(parse(&["arch-sense"]).unwrap(), None);
+        assert_eq!(
+            parse(&["arch-sense", "--apply"]).unwrap(),
+            Some(ApplyTarget::All)
+        );
+        assert_eq!(
+            parse(&["arch-sense", "--apply=rgb"]).unwrap(),
+            Some(ApplyTarget::Rgb)
+        );
+        assert!(parse(&["arch-sense", "--apply=fans"]).is_err());
+    }
 }
@@ -0,0 +1,149 @@
+//! Inline editors for an exact keyboard colour: hex entry or an HSV picker.
+//!
+//! Like the profiles popup, key handling only reports an outcome; the App
+//! decides what to do with the chosen colour.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::rgb_protocol::Rgb;
+
+const HUE_STEP: u16 = 10;
+const PERCENT_STEP: u8 = 5;
+/// `#RRGGBB`.
+const HEX_MAX_LEN: usize = 7;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ColorInput {
+    Hex(String),
+    Hsv { hue: u16, saturation: u8, value: u8 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ColorOutcome {
+    Chosen(Rgb),
+    /// Enter on something that is not a colour; the editor stays open.
+    Invalid,
+    Cancelled,
+}
+
+impl ColorInput {
+    pub(crate) fn hsv(start: Rgb) -> Self {
+        let (hue, saturation, value) = start.to_hsv();
+        Self::Hsv {
+            hue,
+            saturation,
+            value,
+        }
+    }
+
+    /// The colour as currently entered, if it is a complete one.
+    pub(crate) fn preview(&self) -> Option<Rgb> {
+        match self {
+            Self::Hex(buffer) => Rgb::from_hex(buffer),
+            Self::Hsv {
+                hue,
+                saturation,
+                value,
+            } => Some(Rgb::from_hsv(*hue, *saturation, *value)),
+        }
+    }
+
+    pub(crate) fn on_key(&mut self, key: KeyEvent) -> Option<ColorOutcome> {
+        match key.code {
+            KeyCode::Esc => return Some(ColorOutcome::Cancelled),
+            KeyCode::Enter => {
+                return Some(
+                    self.preview()
+                        .map_or(ColorOutcome::Invalid, ColorOutcome::Chosen),
+                );
+            }
+            _ => {}
+        }
+
+        match self {
+            Self::Hex(buffer) => match key.code {
+                KeyCode::Char(c)
+                    if (c.is_ascii_hexdigit() || c == '#') && buffer.len() < HEX_MAX_LEN =>
+                {
+                    buffer.push(c.to_ascii_uppercase());
+                }
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                _ => {}
+            },
+            Self::Hsv {
+                hue,
+                saturation,
+                value,
+            } => {
+                let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+                match key.code {
+                    KeyCode::Left if shift => *saturation = saturation.saturating_sub(PERCENT_STEP),
+                    KeyCode::Right if shift => *saturation = (*saturation + PERCENT_STEP).min(100),
+                    KeyCode::Left => *hue = (*hue + 360 - HUE_STEP) % 360,
+                    KeyCode::Right => *hue = (*hue + HUE_STEP) % 360,
+                    KeyCode::Down => *value = value.saturating_sub(PERCENT_STEP),
+                    KeyCode::Up => *value = (*value + PERCENT_STEP).min(100),
+                    _ => {}
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn hex_input_accepts_only_complete_colors() {
+        let mut input = ColorInput::Hex(String::new());
+        for c in "#2eb8bZ8".chars() {
+            input.on_key(key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        assert_eq!(input, ColorInput::Hex("#2EB8B8".to_string()));
+
+        input.on_key(key(KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(
+            input.on_key(key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(ColorOutcome::Invalid)
+        );
+        input.on_key(key(KeyCode::Char('8'), KeyModifiers::NONE));
+        assert_eq!(
+            input.on_key(key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(ColorOutcome::Chosen(Rgb {
+                r: 0x2E,
+                g: 0xB8,
+                b: 0xB8
+            }))
+        );
+    }
+
+    #[test]
+    fn hsv_picker_wraps_hue_and_clamps_percentages() {
+        let mut input = ColorInput::hsv(Rgb { r: 255, g: 0, b: 0 });
+        input.on_key(key(KeyCode::Left, KeyModifiers::NONE));
+        input.on_key(key(KeyCode::Up, KeyModifiers::NONE));
+        input.on_key(key(KeyCode::Left, KeyModifiers::SHIFT));
+
+        assert_eq!(
+            input,
+            ColorInput::Hsv {
+                hue: 350,
+                saturation: 95,
+                value: 100
+            }
+        );
+        assert_eq!(
+            input.on_key(key(KeyCode::Esc, KeyModifiers::NONE)),
+            Some(ColorOutcome::Cancelled)
+        );
+    }
+}
@@ -1,11 +1,21 @@
-use anyhow::Result;
-use crate::config::AppConfig;
-use crate::models::RgbSettings;
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use crate::cli::{self, Cli};
+use crate::config::{control_writes, AppConfig};
+use crate::models::{ApplyTarget, RgbSettings};
+use crate::doctor;
 use crate::permissions;
 use crate::hardware;
+use crate::rgb_protocol::probed_label;
+use crate::status;
+
+pub fn doctor(json: bool) -> Result<()> {
+    doctor::run(json)
+}
 
-pub fn print_permission_report() -> Result<()> {
-    permissions::print_permission_report()
+pub fn status(json: bool) -> Result<()> {
+    status::run(json)
 }
 
 pub fn install_permissions() -> Result<()> {
@@ -20,16 +30,84 @@ pub fn apply_permissions() -> Result<()> {
     permissions::apply_permissions_as_root()
 }
 
-pub fn apply_saved_config() -> Result<()> {
+pub fn print_completions(shell: clap_complete::Shell) -> Result<()> {
+    std::io::stdout().write_all(&cli::completion_script(shell))?;
+    Ok(())
+}
+
+/// Restores the saved hardware settings and/or lighting, e.g. at boot.
+/// Every setting is attempted; the exit status reports whether any failed.
+pub fn apply_saved_config(cli: &Cli, target: ApplyTarget) -> Result<()> {
     let config = AppConfig::load();
-    let rgb = RgbSettings::from_config(&config.rgb);
+    let hardware_failed = target.includes_hardware() && !apply_saved_hardware(&config, cli.dry_run);
+    let rgb_result = if target.includes_rgb() {
+        apply_saved_rgb(&config, cli)
+    } else {
+        Ok(())
+    };
+
+    match (rgb_result, hardware_failed) {
+        (Err(error), true) => {
+            bail!("some hardware settings could not be restored, and neither could the lighting: {error:#}")
+        }
+        (Err(error), false) => Err(error),
+        (Ok(()), true) => bail!("some hardware settings could not be restored"),
+        (Ok(()), false) => Ok(()),
+    }
+}
+
+/// Returns false if any write failed; missing attributes are only reported.
+fn apply_saved_hardware(config: &AppConfig, dry_run: bool) -> bool {
+    let writes = control_writes(&config.hardware);
+    if writes.is_empty() {
+        eprintln!("arch-sense: no saved hardware settings");
+        return true;
+    }
+
+    let capabilities =
+        hardware::Capabilities::probe(config.fan_control(), config.manages_thermal_profile());
+    let summary = hardware::apply_controls(&capabilities, &writes, dry_run);
+    let prefix = if dry_run {
+        hardware::DRY_RUN_PREFIX
+    } else {
+        "hardware:"
+    };
+    eprintln!("arch-sense: {prefix} {}", summary.describe());
+    summary.failed.is_empty()
+}
+
+fn apply_saved_rgb(config: &AppConfig, cli: &Cli) -> Result<()> {
+    let mut rgb = RgbSettings::from_config(&config.rgb);
+    if rgb.is_thermal() {
+        // Tinted once from the current reading; the TUI keeps it updated.
+        let sensors = hardware::SensorSetup::from_config(config);
+        rgb = rgb.tinted(hardware::read_hottest_temp(&sensors));
+    }
+    let (keyboard, keyboard_warning) = hardware::KeyboardSetup::resolve(config, cli.rgb_backend);
+
+    if let Some(warning) = keyboard_warning {
+        eprintln!("arch-sense: {warning}");
+    }
+
+    if cli.dry_run {
+        eprintln!(
+            "arch-sense: {}",
+            hardware::preview_rgb_settings(&rgb, &keyboard)
+        );
+        return Ok(());
+    }
 
-    if !hardware::is_keyboard_present() {
-        eprintln!("arch-sense: keyboard not found (VID:04F2 PID:0117)");
+    if !hardware::is_keyboard_present(&keyboard.models) {
+        eprintln!(
+            "arch-sense: keyboard not found (probed {})",
+            probed_label(&keyboard.models)
+        );
         return Ok(());
     }
 
-    match hardware::apply_rgb_settings(&rgb) {
+    // Dropping the handle at the end releases the interface for usbhid again.
+    let mut usb = hardware::UsbKeyboard::default();
+    match hardware::apply_rgb_settings(&rgb, &keyboard, &mut usb) {
         Ok(message) => {
             eprintln!("arch-sense: {message}");
             Ok(())
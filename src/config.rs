@@ -1,14 +1,30 @@
-use std::fs;
-use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::ops::RangeInclusive;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::permissions::setup_hint;
+use crate::models::{ControlId, GpuSource, RgbBackend};
+use crate::permissions::{is_root, setup_hint};
+use crate::quirks::{self, FanControl};
+use crate::rgb_protocol::{KeyboardModel, SUPPORTED_KEYBOARDS, ZONE_COUNT};
+use crate::user_dirs::{create_dir_all, sudo_user, xdg_dir, XdgDir};
 
 const CONFIG_DIR: &str = "/var/lib/arch-sense";
 const CONFIG_FILE: &str = "config.json";
+/// Per-user lighting under `$XDG_CONFIG_HOME`, used with `per_user_rgb`.
+const USER_RGB_FILE: &str = "arch-sense/rgb.json";
+const USER_RGB_MODE: u32 = 0o644;
+/// Group-writable so members of the arch-sense group can save without sudo.
+pub(crate) const CONFIG_FILE_MODE: u32 = 0o664;
+/// Sensor refresh bounds; faster polling mostly wakes the dGPU more often.
+const REFRESH_MS_RANGE: RangeInclusive<u64> = 200..=5000;
+const DEFAULT_REFRESH_MS: u64 = 1000;
 
 pub(crate) fn config_dir() -> PathBuf {
     PathBuf::from(CONFIG_DIR)
@@ -18,6 +34,17 @@ pub fn config_path() -> PathBuf {
     config_dir().join(CONFIG_FILE)
 }
 
+/// This user's lighting file, or under `sudo` the invoking user's. Root
+/// itself has none: it keeps the shared `rgb` section, which is what
+/// `--apply` restores at boot.
+pub(crate) fn user_rgb_path() -> Option<PathBuf> {
+    let sudo_user = sudo_user();
+    if is_root() && sudo_user.is_none() {
+        return None;
+    }
+    Some(xdg_dir(XdgDir::Config, sudo_user.as_ref())?.join(USER_RGB_FILE))
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct RgbConfig {
     pub(crate) effect: usize,
@@ -25,6 +52,38 @@ pub(crate) struct RgbConfig {
     pub(crate) brightness: u8,
     pub(crate) speed: u8,
     pub(crate) direction: usize,
+    /// Exact colour that replaces the palette entry at `color`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) custom_rgb: Option<(u8, u8, u8)>,
+    /// Colours of the Zones effect, left to right.
+    #[serde(default = "default_zones")]
+    pub(crate) zones: [(u8, u8, u8); ZONE_COUNT],
+    /// Thermal effect colour at 60 °C and below.
+    #[serde(default = "default_thermal_low")]
+    pub(crate) thermal_low: (u8, u8, u8),
+    /// Thermal effect colour at 80 °C and above.
+    #[serde(default = "default_thermal_high")]
+    pub(crate) thermal_high: (u8, u8, u8),
+    /// Effect that switching the lighting back on returns to while
+    /// `effect` is Off.
+    #[serde(default = "default_last_effect")]
+    pub(crate) last_effect: usize,
+}
+
+fn default_zones() -> [(u8, u8, u8); ZONE_COUNT] {
+    [(255, 255, 255); ZONE_COUNT]
+}
+
+fn default_thermal_low() -> (u8, u8, u8) {
+    (0, 255, 0)
+}
+
+fn default_thermal_high() -> (u8, u8, u8) {
+    (255, 0, 0)
+}
+
+fn default_last_effect() -> usize {
+    1 // Static
 }
 
 impl Default for RgbConfig {
@@ -35,13 +94,329 @@ impl Default for RgbConfig {
             brightness: 30,
             speed: 50,
             direction: 0, // Right
+            custom_rgb: None,
+            zones: default_zones(),
+            thermal_low: default_thermal_low(),
+            thermal_high: default_thermal_high(),
+            last_effect: default_last_effect(),
+        }
+    }
+}
+
+/// Desktop notifications for hot sensors and a vanished keyboard.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct NotificationsConfig {
+    pub(crate) enabled: bool,
+    /// °C at which a CPU warning is sent.
+    pub(crate) cpu_threshold: u64,
+    /// °C at which a GPU warning is sent.
+    pub(crate) gpu_threshold: u64,
+    /// Minimum time between two notifications of the same kind.
+    pub(crate) cooldown_secs: u64,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cpu_threshold: 90,
+            gpu_threshold: 85,
+            cooldown_secs: 300,
+        }
+    }
+}
+
+/// A short red pulse on the keyboard when the battery runs low.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct LowBatteryConfig {
+    pub(crate) enabled: bool,
+    /// Percentage below which the keyboard pulses while discharging.
+    pub(crate) threshold: u8,
+}
+
+impl Default for LowBatteryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 15,
+        }
+    }
+}
+
+/// Fade between solid keyboard colours instead of switching instantly.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct TransitionConfig {
+    pub(crate) enabled: bool,
+    pub(crate) duration_ms: u64,
+}
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration_ms: 400,
+        }
+    }
+}
+
+impl TransitionConfig {
+    pub(crate) fn duration(&self) -> Option<Duration> {
+        (self.enabled && self.duration_ms > 0).then(|| Duration::from_millis(self.duration_ms))
+    }
+}
+
+/// Sensor refresh interval from `--refresh`, then `refresh_ms`, then the
+/// default. Out-of-range values are skipped and reported.
+pub(crate) fn refresh_interval(
+    flag: Option<u64>,
+    config: Option<u64>,
+) -> (Duration, Option<String>) {
+    let mut ignored = Vec::new();
+    for (source, ms) in [("--refresh", flag), ("refresh_ms", config)] {
+        let Some(ms) = ms else { continue };
+        if REFRESH_MS_RANGE.contains(&ms) {
+            return (Duration::from_millis(ms), refresh_warning(&ignored));
+        }
+        ignored.push(format!("{source} {ms}"));
+    }
+    (
+        Duration::from_millis(DEFAULT_REFRESH_MS),
+        refresh_warning(&ignored),
+    )
+}
+
+fn refresh_warning(ignored: &[String]) -> Option<String> {
+    (!ignored.is_empty()).then(|| {
+        format!(
+            "Ignoring {}: refresh must be {}-{} ms",
+            ignored.join(" and "),
+            REFRESH_MS_RANGE.start(),
+            REFRESH_MS_RANGE.end()
+        )
+    })
+}
+
+/// Keys read from the input devices while the TUI runs, whichever window
+/// has focus. Nothing is opened while `keys` is empty.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct HotkeysConfig {
+    /// Substrings of the input device names to listen on.
+    pub(crate) devices: Vec<String>,
+    /// Key name such as `KEY_PROG3`, or its code, to an action name.
+    pub(crate) keys: BTreeMap<String, String>,
+}
+
+impl Default for HotkeysConfig {
+    fn default() -> Self {
+        Self {
+            devices: vec![
+                "Acer WMI hotkeys".to_string(),
+                "AT Translated Set 2 keyboard".to_string(),
+            ],
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl HotkeysConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// TUI colours: a preset name plus optional hex overrides such as `"#5FB6FF"`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct ThemeConfig {
+    /// `default`, `predator`, `mono` or `light`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) preset: Option<String>,
+    /// Highlights, focused borders and values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) accent: Option<String>,
+    /// Disabled text and idle borders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) dim: Option<String>,
+    /// Background of the selected row.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) highlight: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cool: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) warm: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) hot: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+impl ThemeConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Extra keyboard entry probed before `SUPPORTED_KEYBOARDS`.
+///
+/// IDs are hex strings as printed by `lsusb`, e.g. `"04f2"` or `"0x04F2"`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct KeyboardOverride {
+    pub(crate) vid: String,
+    pub(crate) pid: String,
+    pub(crate) interface: u8,
+    pub(crate) endpoint: String,
+}
+
+impl KeyboardOverride {
+    pub(crate) fn to_model(&self) -> Result<KeyboardModel, String> {
+        let endpoint = parse_hex(&self.endpoint, "endpoint")?;
+        Ok(KeyboardModel {
+            vid: parse_hex(&self.vid, "vid")?,
+            pid: parse_hex(&self.pid, "pid")?,
+            interface: self.interface,
+            endpoint: u8::try_from(endpoint)
+                .map_err(|_| format!("endpoint '{}' does not fit in a byte", self.endpoint))?,
+            name: "Custom keyboard",
+        })
+    }
+}
+
+fn parse_hex(value: &str, field: &str) -> Result<u16, String> {
+    let digits = value.trim();
+    let digits = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+        .unwrap_or(digits);
+
+    u16::from_str_radix(digits, 16)
+        .map_err(|_| format!("{field} '{value}' is not a 16-bit hex value"))
+}
+
+/// A named snapshot of every persistent control plus the keyboard lighting.
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct Profile {
+    pub(crate) name: String,
+    /// Raw sysfs values keyed by `ControlId::key`.
+    #[serde(default)]
+    pub(crate) controls: BTreeMap<String, String>,
+    #[serde(default)]
+    pub(crate) rgb: RgbConfig,
+    /// Fields from newer versions, written back unchanged.
+    #[serde(flatten)]
+    pub(crate) extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Applies the profile named `profile` every time local time reaches `at`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Schedule {
+    /// `HH:MM`, 24-hour.
+    pub(crate) at: String,
+    /// `mon` to `sun`, `weekdays` or `weekend`; empty means every day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) days: Vec<String>,
+    pub(crate) profile: String,
+}
+
+/// Turns saved control values into writes, in `ControlId::ALL` order.
+///
+/// Unknown keys are skipped, and the two fan rows become a single `cpu,gpu`
+/// write so they land together.
+pub(crate) fn control_writes(controls: &BTreeMap<String, String>) -> Vec<(ControlId, String)> {
+    let value = |id: ControlId| controls.get(id.key()).map(String::as_str);
+    let mut writes = Vec::new();
+
+    for id in ControlId::ALL {
+        match id {
+            ControlId::GpuFan => {}
+            ControlId::CpuFan => {
+                if let (None, None) = (value(ControlId::CpuFan), value(ControlId::GpuFan)) {
+                    continue;
+                }
+                let cpu = value(ControlId::CpuFan).unwrap_or("0");
+                let gpu = value(ControlId::GpuFan).unwrap_or("0");
+                writes.push((id, format!("{cpu},{gpu}")));
+            }
+            _ if id.is_persistent() => {
+                if let Some(raw) = value(id) {
+                    writes.push((id, raw.to_string()));
+                }
+            }
+            _ => {}
         }
     }
+
+    writes
 }
 
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct AppConfig {
     pub(crate) rgb: RgbConfig,
+    #[serde(default)]
+    pub(crate) rgb_backend: RgbBackend,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) keyboard_override: Option<KeyboardOverride>,
+    #[serde(default)]
+    pub(crate) gpu_source: GpuSource,
+    /// Explicit CPU temperature file, tried before thermal zone discovery.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cpu_temp_path: Option<PathBuf>,
+    /// Action name to key descriptors, e.g. `"quit": ["x", "ctrl+q"]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) keybindings: BTreeMap<String, Vec<String>>,
+    /// The module accepts `0` for one fan while the other stays manual.
+    /// Otherwise choosing Auto for either fan returns both to auto.
+    #[serde(default)]
+    pub(crate) per_fan_auto: bool,
+    /// Fixed speeds `fan_speed` takes instead of percentages, for firmware
+    /// missing from the model table; 0 forces percentages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) fan_levels: Option<u8>,
+    /// `false` leaves `platform_profile` to power-profiles-daemon or TLP:
+    /// the thermal profile row is hidden and never written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) manage_thermal_profile: Option<bool>,
+    /// Send RGB changes shortly after they are made instead of on Enter.
+    #[serde(default)]
+    pub(crate) auto_apply: bool,
+    #[serde(default)]
+    pub(crate) transition: TransitionConfig,
+    /// Minutes without input before keyboard lighting is switched off by
+    /// the TUI; restored on the next key press.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) rgb_idle_minutes: Option<u16>,
+    /// Milliseconds between sensor reads; `--refresh` overrides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) refresh_ms: Option<u64>,
+    #[serde(default)]
+    pub(crate) notifications: NotificationsConfig,
+    #[serde(default)]
+    pub(crate) low_battery: LowBatteryConfig,
+    #[serde(default, skip_serializing_if = "ThemeConfig::is_default")]
+    pub(crate) theme: ThemeConfig,
+    #[serde(default, skip_serializing_if = "HotkeysConfig::is_default")]
+    pub(crate) hotkeys: HotkeysConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) profiles: Vec<Profile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) schedules: Vec<Schedule>,
+    /// Each user keeps their own lighting in `~/.config/arch-sense/rgb.json`
+    /// instead of the shared `rgb` section.
+    #[serde(default)]
+    pub(crate) per_user_rgb: bool,
+    /// Set while `rgb` holds this user's lighting.
+    #[serde(skip)]
+    user_rgb: Option<UserRgb>,
+    /// Last value each control was confirmed at, restored by `--apply`.
+    /// Keyed like `Profile::controls`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) hardware: BTreeMap<String, String>,
+    /// Fields from newer versions, written back unchanged.
+    #[serde(flatten)]
+    pub(crate) extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl AppConfig {
@@ -50,37 +425,199 @@ impl AppConfig {
     }
 
     pub(crate) fn load_with_warning() -> (Self, Option<String>) {
-        match fs::read_to_string(config_path()) {
+        let (mut config, warning) = Self::load_from(&config_path());
+        let user_warning = match user_rgb_path() {
+            Some(path) if config.per_user_rgb => config.use_user_rgb(path),
+            _ => None,
+        };
+        (config, warning.or(user_warning))
+    }
+
+    /// Swaps in the lighting saved at `path`; until that file exists, the
+    /// shared lighting is the starting point. Saving writes `rgb` there and
+    /// leaves the shared section alone.
+    pub(crate) fn use_user_rgb(&mut self, path: PathBuf) -> Option<String> {
+        let shared = self.rgb.clone();
+        let warning = match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(rgb) => {
+                    self.rgb = rgb;
+                    None
+                }
+                Err(error) => Some(format!(
+                    "Lighting file {} is invalid ({error}); using the shared lighting",
+                    path.display()
+                )),
+            },
+            Err(error) if error.kind() == ErrorKind::NotFound => None,
+            Err(error) => Some(format!(
+                "Lighting file {} could not be read ({error}); using the shared lighting",
+                path.display()
+            )),
+        };
+        self.user_rgb = Some(UserRgb { path, shared });
+        warning
+    }
+
+    /// Loads `path`, copying a corrupt file to `<path>.bak` before falling back to defaults.
+    pub(crate) fn load_from(path: &Path) -> (Self, Option<String>) {
+        match fs::read_to_string(path) {
             Ok(content) => match serde_json::from_str(&content) {
                 Ok(config) => (config, None),
-                Err(error) => (
-                    Self::default(),
-                    Some(format!(
-                        "Config parse failed at {}: {error}; using defaults",
-                        config_path().display()
-                    )),
-                ),
+                Err(error) => {
+                    let backup = backup_path(path);
+                    let kept = match fs::copy(path, &backup) {
+                        Ok(_) => format!("copy kept at {}", backup.display()),
+                        Err(copy_error) => {
+                            format!("backup to {} failed: {copy_error}", backup.display())
+                        }
+                    };
+                    (
+                        Self::default(),
+                        Some(format!(
+                            "Config parse failed at {}: {error}; {kept}; using defaults",
+                            path.display()
+                        )),
+                    )
+                }
             },
             Err(error) if error.kind() == ErrorKind::NotFound => (Self::default(), None),
             Err(error) => (
                 Self::default(),
                 Some(format!(
                     "Config read failed at {}: {error}; using defaults",
-                    config_path().display()
+                    path.display()
                 )),
             ),
         }
     }
 
+    /// How `fan_speed` is read and written: `fan_levels` if set, otherwise
+    /// the detected model's flavour.
+    pub(crate) fn fan_control(&self) -> FanControl {
+        self.fan_levels.map_or_else(
+            || quirks::detected().quirks().fan_control,
+            FanControl::from_levels,
+        )
+    }
+
+    pub(crate) fn manages_thermal_profile(&self) -> bool {
+        self.manage_thermal_profile.unwrap_or(true)
+    }
+
+    /// Keyboards to probe, override first, then the detected model's, plus a
+    /// warning if the override is invalid.
+    pub(crate) fn keyboards(&self) -> (Vec<KeyboardModel>, Option<String>) {
+        let mut models = Vec::with_capacity(SUPPORTED_KEYBOARDS.len() + 1);
+        let mut warning = None;
+
+        match self
+            .keyboard_override
+            .as_ref()
+            .map(KeyboardOverride::to_model)
+        {
+            Some(Ok(model)) => models.push(model),
+            Some(Err(error)) => {
+                warning = Some(format!("Ignoring keyboard_override in config: {error}"));
+            }
+            None => {}
+        }
+
+        for model in quirks::detected()
+            .quirks()
+            .keyboards
+            .iter()
+            .chain(SUPPORTED_KEYBOARDS)
+        {
+            if !models.contains(model) {
+                models.push(*model);
+            }
+        }
+        (models, warning)
+    }
+
     pub(crate) fn save(&self) -> Result<()> {
-        fs::create_dir_all(config_dir())
-            .map_err(|e| config_error(e, "creating config directory"))?;
-        let json = serde_json::to_string_pretty(self)?;
-        fs::write(config_path(), json).map_err(|e| config_error(e, "writing config file"))?;
-        Ok(())
+        self.save_to(&config_path())
+    }
+
+    /// Replaces the config at `path` atomically. With `per_user_rgb` in use,
+    /// the lighting goes to the user's file and `path` keeps the shared one.
+    pub(crate) fn save_to(&self, path: &Path) -> Result<()> {
+        let json = match &self.user_rgb {
+            Some(user) => {
+                let rgb = serde_json::to_string_pretty(&self.rgb)?;
+                let sudo_user = sudo_user();
+                user.path
+                    .parent()
+                    .map_or(Ok(()), |dir| create_dir_all(dir, sudo_user.as_ref()))
+                    .and_then(|()| replace_file(&user.path, &rgb, USER_RGB_MODE))
+                    .with_context(|| format!("writing {}", user.path.display()))?;
+                if let Some(sudo_user) = sudo_user {
+                    sudo_user.hand_back(&user.path);
+                }
+                let mut shared = serde_json::to_value(self)?;
+                shared["rgb"] = serde_json::to_value(&user.shared)?;
+                serde_json::to_string_pretty(&shared)?
+            }
+            None => serde_json::to_string_pretty(self)?,
+        };
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir).map_err(|e| config_error(e, "creating config directory"))?;
+        replace_file(path, &json, CONFIG_FILE_MODE)
+            .map_err(|error| config_error(error, "writing config file"))
     }
 }
 
+/// The lighting a user sees with `per_user_rgb`, and the shared lighting it
+/// stands in for.
+#[derive(Clone)]
+struct UserRgb {
+    path: PathBuf,
+    shared: RgbConfig,
+}
+
+/// Writes a temp file next to `path`, fsyncs it and renames it into place,
+/// so a crash mid-write never leaves a truncated file behind.
+fn replace_file(path: &Path, contents: &str, mode: u32) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dir.join(format!(".{name}.tmp-{}", std::process::id()));
+
+    let written = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(mode)
+            .open(&tmp)?;
+        // The umask may have masked the group write bit off.
+        file.set_permissions(fs::Permissions::from_mode(mode))?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+
+    if let Err(error) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(error);
+    }
+
+    // Persist the rename itself; not every filesystem supports syncing a directory.
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_os_string();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
 fn config_error(err: std::io::Error, action: &str) -> anyhow::Error {
     if err.kind() == ErrorKind::PermissionDenied {
         anyhow::anyhow!("{action} failed: {err}; {}", setup_hint())
@@ -88,3 +625,193 @@ fn config_error(err: std::io::Error, action: &str) -> anyhow::Error {
         anyhow::anyhow!("{action} failed: {err}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_override(vid: &str, pid: &str, endpoint: &str) -> AppConfig {
+        AppConfig {
+            keyboard_override: Some(KeyboardOverride {
+                vid: vid.to_string(),
+                pid: pid.to_string(),
+                interface: 2,
+                endpoint: endpoint.to_string(),
+            }),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn keyboard_override_is_probed_before_builtin_table() {
+        let (models, warning) = config_with_override("0x04F2", "1234", "04").keyboards();
+
+        assert_eq!(warning, None);
+        assert_eq!(models.len(), SUPPORTED_KEYBOARDS.len() + 1);
+        assert_eq!((models[0].vid, models[0].pid), (0x04F2, 0x1234));
+        assert_eq!((models[0].interface, models[0].endpoint), (2, 0x04));
+        assert_eq!(&models[1..], SUPPORTED_KEYBOARDS);
+    }
+
+    #[test]
+    fn invalid_keyboard_override_is_ignored_with_warning() {
+        for config in [
+            config_with_override("zz", "0117", "04"),
+            config_with_override("04f2", "10000", "04"),
+            config_with_override("04f2", "0117", "0x184"),
+        ] {
+            let (models, warning) = config.keyboards();
+
+            assert_eq!(models, SUPPORTED_KEYBOARDS);
+            assert!(warning.unwrap().contains("keyboard_override"));
+        }
+    }
+
+    #[test]
+    fn refresh_prefers_the_flag_then_the_file_and_skips_bad_values() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(refresh_interval(None, None), (ms(1000), None));
+        assert_eq!(refresh_interval(None, Some(600)), (ms(600), None));
+        assert_eq!(refresh_interval(Some(250), Some(600)), (ms(250), None));
+
+        let (interval, warning) = refresh_interval(Some(50), Some(600));
+        assert_eq!(interval, ms(600));
+        assert!(warning.unwrap().contains("--refresh 50"));
+
+        let (interval, warning) = refresh_interval(Some(0), Some(9000));
+        assert_eq!(interval, ms(1000));
+        let warning = warning.unwrap();
+        assert!(warning.contains("--refresh 0") && warning.contains("refresh_ms 9000"));
+    }
+
+    fn temp_config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("arch-sense-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn save_replaces_config_atomically_with_group_write_mode() {
+        let dir = temp_config_dir("config-save");
+        let path = dir.join(CONFIG_FILE);
+        let mut config = AppConfig::default();
+        config.rgb.brightness = 70;
+
+        config.save_to(&path).unwrap();
+        config.rgb.brightness = 80;
+        config.save_to(&path).unwrap();
+
+        let (loaded, warning) = AppConfig::load_from(&path);
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        let entries = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(warning, None);
+        assert_eq!(loaded.rgb.brightness, 80);
+        assert_eq!(mode, CONFIG_FILE_MODE);
+        assert_eq!(entries, 1, "temp file left behind");
+    }
+
+    #[test]
+    fn per_user_lighting_is_saved_apart_from_the_shared_config() {
+        let dir = temp_config_dir("config-user-rgb");
+        let path = dir.join(CONFIG_FILE);
+        let user_path = dir.join("home").join(USER_RGB_FILE);
+        let mut config = AppConfig {
+            per_user_rgb: true,
+            ..AppConfig::default()
+        };
+        config.rgb.brightness = 30;
+
+        // No file yet: the user starts from the shared lighting.
+        assert_eq!(config.use_user_rgb(user_path.clone()), None);
+        assert_eq!(config.rgb.brightness, 30);
+        config.rgb.brightness = 90;
+        config.save_to(&path).unwrap();
+
+        let (mut loaded, _) = AppConfig::load_from(&path);
+        let shared_brightness = loaded.rgb.brightness;
+        loaded.use_user_rgb(user_path.clone());
+        let user_brightness = loaded.rgb.brightness;
+
+        fs::write(&user_path, "{ not json").unwrap();
+        let (mut broken, _) = AppConfig::load_from(&path);
+        let warning = broken.use_user_rgb(user_path);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(shared_brightness, 30);
+        assert_eq!(user_brightness, 90);
+        assert!(warning.unwrap().contains("using the shared lighting"));
+        assert_eq!(broken.rgb.brightness, 30);
+    }
+
+    #[test]
+    fn corrupt_config_is_backed_up_and_defaults_are_used() {
+        let dir = temp_config_dir("config-corrupt");
+        let path = dir.join(CONFIG_FILE);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "{\"rgb\": {").unwrap();
+
+        let (loaded, warning) = AppConfig::load_from(&path);
+        let backup = fs::read_to_string(backup_path(&path));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.rgb.brightness, RgbConfig::default().brightness);
+        assert!(warning.unwrap().contains("config.json.bak"));
+        assert_eq!(backup.unwrap(), "{\"rgb\": {");
+    }
+
+    #[test]
+    fn configs_without_newer_keys_still_load() {
+        let config: AppConfig = serde_json::from_str(
+            r#"{"rgb":{"effect":1,"color":9,"brightness":30,"speed":50,"direction":0}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.rgb_backend, RgbBackend::Auto);
+        assert!(config.keyboard_override.is_none());
+    }
+
+    #[test]
+    fn profiles_and_unknown_fields_survive_round_trips() {
+        let json = r#"{
+            "rgb": {"effect":1,"color":9,"brightness":30,"speed":50,"direction":0},
+            "future_setting": [1, 2],
+            "profiles": [{
+                "name": "Library",
+                "controls": {"thermal_profile": "quiet", "cpu_fan": "30"},
+                "rgb": {"effect":0,"color":0,"brightness":0,"speed":0,"direction":0},
+                "future_profile_field": "kept"
+            }]
+        }"#;
+        let config: AppConfig = serde_json::from_str(json).unwrap();
+        let saved = serde_json::to_value(&config).unwrap();
+
+        assert_eq!(saved["future_setting"], serde_json::json!([1, 2]));
+        assert_eq!(saved["profiles"][0]["name"], "Library");
+        assert_eq!(saved["profiles"][0]["controls"]["cpu_fan"], "30");
+        assert_eq!(saved["profiles"][0]["future_profile_field"], "kept");
+    }
+
+    #[test]
+    fn control_writes_pair_fans_and_skip_calibration() {
+        let controls = BTreeMap::from(
+            [
+                ("gpu_fan", "70"),
+                ("thermal_profile", "quiet"),
+                ("battery_calibration", "1"),
+                ("turbo_button", "1"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+
+        assert_eq!(
+            control_writes(&controls),
+            [
+                (ControlId::ThermalProfile, "quiet".to_string()),
+                (ControlId::CpuFan, "0,70".to_string()),
+            ]
+        );
+    }
+}
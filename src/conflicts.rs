@@ -0,0 +1,113 @@
+//! Other power managers that also write `platform_profile`.
+//!
+//! power-profiles-daemon and TLP both set the ACPI platform profile, so a
+//! thermal profile chosen here can be switched back seconds later. They are
+//! detected for `--doctor` and `--status`, and the worker watches for a
+//! profile it wrote being changed back shortly afterwards.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::constants::sysfs;
+
+/// A change this soon after our write counts as another tool reverting it.
+pub(crate) const REVERT_WINDOW: Duration = Duration::from_secs(30);
+
+const PROC_DIR: &str = "/proc";
+/// Created by `tlp init start` and kept while TLP is in charge.
+const TLP_RUN_DIR: &str = "/run/tlp";
+/// The kernel keeps the first 15 bytes of a process name in `comm`.
+const COMM_LEN: usize = 15;
+
+/// Names of the running tools that manage `platform_profile` too.
+pub(crate) fn detect() -> Vec<String> {
+    let mut found = Vec::new();
+    if process_running("power-profiles-daemon") {
+        found.push("power-profiles-daemon".to_string());
+    }
+    if Path::new(&sysfs(TLP_RUN_DIR)).is_dir() {
+        found.push("TLP".to_string());
+    }
+    found
+}
+
+fn process_running(name: &str) -> bool {
+    let comm = &name[..name.len().min(COMM_LEN)];
+    let Ok(entries) = fs::read_dir(sysfs(PROC_DIR)) else {
+        return false;
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .filter_map(|entry| fs::read_to_string(entry.path().join("comm")).ok())
+        .any(|running| running.trim_end() == comm)
+}
+
+/// Who to blame in a revert warning.
+pub(crate) fn describe(conflicts: &[String]) -> String {
+    if conflicts.is_empty() {
+        "another program".to_string()
+    } else {
+        conflicts.join(" or ")
+    }
+}
+
+/// The last thermal profile the worker wrote, until `REVERT_WINDOW` has
+/// passed or a read shows it replaced.
+#[derive(Debug, Default)]
+pub(crate) struct RevertWatch {
+    written: Option<(String, Instant)>,
+}
+
+impl RevertWatch {
+    pub(crate) fn wrote(&mut self, value: &str, now: Instant) {
+        self.written = Some((value.to_string(), now));
+    }
+
+    /// Compares a read of the profile with the last write. Returns how long
+    /// after the write it was found changed; each write is reported once.
+    pub(crate) fn observe(&mut self, raw: &str, now: Instant) -> Option<Duration> {
+        let (value, at) = self.written.as_ref()?;
+        let after = now.saturating_duration_since(*at);
+        if after > REVERT_WINDOW {
+            self.written = None;
+            return None;
+        }
+        if raw == value {
+            return None;
+        }
+        self.written = None;
+        Some(after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_profile_switched_back_soon_after_the_write_is_reported_once() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut watch = RevertWatch::default();
+
+        // Nothing written yet: an outside change is not a revert.
+        assert_eq!(watch.observe("balanced", start), None);
+
+        watch.wrote("performance", start);
+        assert_eq!(watch.observe("performance", start + second), None);
+        assert_eq!(watch.observe("performance", start + 2 * second), None);
+        assert_eq!(
+            watch.observe("balanced", start + 4 * second),
+            Some(4 * second)
+        );
+        assert_eq!(watch.observe("balanced", start + 5 * second), None);
+
+        // A change after the window is just a change.
+        watch.wrote("quiet", start);
+        assert_eq!(watch.observe("quiet", start + 10 * second), None);
+        assert_eq!(watch.observe("balanced", start + 40 * second), None);
+        assert_eq!(watch.observe("performance", start + 41 * second), None);
+    }
+}
@@ -1,24 +1,76 @@
+use std::env;
+use std::path::Path;
 use std::time::Duration;
 
+use crate::quirks;
+
 pub(crate) const PS_BASE: &str =
     "/sys/module/linuwu_sense/drivers/platform:acer-wmi/acer-wmi/predator_sense";
+/// Nitro models get `nitro_sense` instead: the same attribute names, minus
+/// the Predator-only ones such as `boot_animation_sound`.
+pub(crate) const NITRO_BASE: &str =
+    "/sys/module/linuwu_sense/drivers/platform:acer-wmi/acer-wmi/nitro_sense";
+/// Known locations of the sense attribute directory, in probe order.
+pub(crate) const PS_BASE_CANDIDATES: [&str; 4] = [
+    PS_BASE,
+    "/sys/devices/platform/acer-wmi/predator_sense",
+    NITRO_BASE,
+    "/sys/devices/platform/acer-wmi/nitro_sense",
+];
+/// Names module versions give the same attribute, preferred first.
+pub(crate) const BOOT_ANIMATION_ATTRS: [&str; 2] = ["boot_animation_sound", "boot_animation"];
+pub(crate) const LCD_OVERRIDE_ATTRS: [&str; 2] = ["lcd_override", "lcd_overdrive"];
 pub(crate) const PLATFORM_PROFILE: &str = "/sys/firmware/acpi/platform_profile";
 pub(crate) const PROFILE_CHOICES: &str = "/sys/firmware/acpi/platform_profile_choices";
-pub(crate) const CPU_TEMP_PATH: &str = "/sys/class/thermal/thermal_zone0/temp";
+/// Re-roots every sysfs path, so the app can be smoke-tested against a fake tree.
+pub(crate) const SYSFS_ROOT_ENV: &str = "ARCH_SENSE_SYSFS_ROOT";
 
-// USB keyboard (Acer Predator PH16-71)
-pub(crate) const KB_VID: u16 = 0x04F2;
-pub(crate) const KB_PID: u16 = 0x0117;
-pub(crate) const KB_IFACE: u8 = 3;
-pub(crate) const KB_EP: u8 = 0x04;
+// USB keyboard; device IDs live in `rgb_protocol::SUPPORTED_KEYBOARDS`
 pub(crate) const USB_TIMEOUT: Duration = Duration::from_millis(1000);
 
-// RGB protocol limits
-pub(crate) const BRIGHT_HW_MAX: u8 = 50; // 0x32
-pub(crate) const SPEED_HW_FAST: u8 = 1;
-pub(crate) const SPEED_HW_SLOW: u8 = 9;
-pub(crate) const PREAMBLE: [u8; 8] = [0xB1, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x4E];
+#[cfg(test)]
+thread_local! {
+    /// A test's fake sysfs tree. Set per thread rather than through the
+    /// environment, so tests running in parallel never see each other's.
+    pub(crate) static TEST_SYSFS_ROOT: std::cell::RefCell<Option<String>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Prefixes an absolute sysfs path with `$ARCH_SENSE_SYSFS_ROOT` when it is set.
+pub(crate) fn sysfs(path: &str) -> String {
+    match sysfs_root() {
+        Some(root) => format!("{}{path}", root.trim_end_matches('/')),
+        None => path.to_string(),
+    }
+}
+
+fn sysfs_root() -> Option<String> {
+    #[cfg(test)]
+    if let Some(root) = TEST_SYSFS_ROOT.with(|root| root.borrow().clone()) {
+        return Some(root);
+    }
+    env::var(SYSFS_ROOT_ENV)
+        .ok()
+        .filter(|root| !root.is_empty())
+}
+
+/// The first sense directory that exists, if any. The detected model's
+/// preferred directory is tried first.
+pub(crate) fn find_ps_base() -> Option<String> {
+    quirks::detected()
+        .quirks()
+        .sense_base
+        .into_iter()
+        .chain(PS_BASE_CANDIDATES)
+        .map(sysfs)
+        .find(|base| Path::new(base).is_dir())
+}
 
-pub(crate) fn ps(name: &str) -> String {
-    format!("{PS_BASE}/{name}")
+/// `name` in the sense directory `base`, or in the default one while the
+/// module is not loaded. Callers probe `base` once with `find_ps_base`.
+pub(crate) fn ps(base: Option<&str>, name: &str) -> String {
+    match base {
+        Some(base) => format!("{base}/{name}"),
+        None => format!("{}/{name}", sysfs(PS_BASE)),
+    }
 }
@@ -0,0 +1,413 @@
+//! `arch-sense --doctor`: a checklist of what the app needs from the system.
+//!
+//! Each check reuses the probes the TUI runs at startup, so the report says
+//! exactly what the app would see. Failed checks carry the command or
+//! setting that fixes them; `--json` prints the same list for bug reports.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::config::{config_path, AppConfig};
+use crate::conflicts;
+use crate::constants::{sysfs, PROFILE_CHOICES};
+use crate::hardware::{self, AttrCapability, Capabilities, SensorSetup};
+use crate::models::{ControlId, SensorMetric};
+use crate::permissions::{
+    group_exists, keyboard_udev_rules, setup_hint, PermissionReport, UsbAccess, HARDWARE_GROUP,
+    UDEV_RULE_PATH,
+};
+use crate::quirks;
+use crate::rgb_protocol::{probed_label, KeyboardModel};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CheckStatus {
+    Pass,
+    /// Something is limited, but the app still starts and works around it.
+    Warn,
+    /// The app cannot do its job until this is fixed.
+    Fail,
+}
+
+impl CheckStatus {
+    fn symbol(self) -> &'static str {
+        match self {
+            Self::Pass => "✓",
+            Self::Warn => "!",
+            Self::Fail => "✗",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct Check {
+    pub(crate) name: String,
+    pub(crate) status: CheckStatus,
+    pub(crate) detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) fix: Vec<String>,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            fix: Vec::new(),
+        }
+    }
+
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Pass, detail)
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Warn, detail)
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Fail, detail)
+    }
+
+    fn fix(mut self, line: impl Into<String>) -> Self {
+        self.fix.push(line.into());
+        self
+    }
+}
+
+/// Runs every check and prints the result; fails if any check failed.
+pub fn run(json: bool) -> Result<()> {
+    let checks = collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&checks)?);
+    } else {
+        print!("{}", render(&checks));
+    }
+
+    let failed = failed_count(&checks);
+    if failed > 0 {
+        bail!("{failed} critical check(s) failed");
+    }
+    Ok(())
+}
+
+fn collect() -> Vec<Check> {
+    // Parsed before `AppConfig::load`, which replaces a corrupt file.
+    let mut checks = vec![model_check(), config_check(&config_path())];
+
+    let config = AppConfig::load();
+    let (keyboards, keyboard_warning) = config.keyboards();
+    let report = PermissionReport::collect(&keyboards);
+    let capabilities = Capabilities::probe(config.fan_control(), config.manages_thermal_profile());
+
+    checks.push(user_check(&report));
+    checks.push(module_check(&capabilities));
+    checks.extend(control_checks(&capabilities, report.is_root));
+    checks.push(power_manager_check(
+        &conflicts::detect(),
+        capabilities.manage_thermal_profile,
+    ));
+    checks.extend(sensor_checks(&SensorSetup::from_config(&config)));
+    checks.push(keyboard_check(&report.usb, &probed_label(&keyboards)));
+    if let Some(warning) = keyboard_warning {
+        checks.push(Check::warn("Keyboard override", warning));
+    }
+    checks.push(udev_check(&keyboards));
+    checks
+}
+
+fn model_check() -> Check {
+    let model = quirks::detected();
+    let detail = format!("{} ({})", model.label(), model.dmi.label());
+
+    match model.unknown_warning() {
+        None => Check::pass("Laptop model", detail),
+        Some(_) => Check::warn("Laptop model", format!("{detail}; not in the quirks table"))
+            .fix("PH16-71 defaults are used; please report these DMI strings"),
+    }
+}
+
+fn config_check(path: &Path) -> Check {
+    let name = "Config file";
+    match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str::<AppConfig>(&content) {
+            Ok(_) => Check::pass(name, path.display().to_string()),
+            Err(error) => Check::fail(name, format!("{}: {error}", path.display()))
+                .fix("fix the JSON by hand, or delete the file to start from defaults"),
+        },
+        Err(error) if error.kind() == ErrorKind::NotFound => Check::pass(
+            name,
+            format!("{} not created yet; defaults apply", path.display()),
+        ),
+        Err(error) => Check::fail(name, format!("{}: {error}", path.display()))
+            .fix(format!("Fix: {}", setup_hint())),
+    }
+}
+
+fn user_check(report: &PermissionReport) -> Check {
+    if report.is_root {
+        return Check::pass("User", "running as root");
+    }
+    if group_exists(HARDWARE_GROUP) {
+        Check::pass("User", format!("group {HARDWARE_GROUP} exists"))
+    } else {
+        Check::warn("User", format!("group {HARDWARE_GROUP} is missing"))
+            .fix(format!("Fix: {}", setup_hint()))
+    }
+}
+
+fn module_check(capabilities: &Capabilities) -> Check {
+    match &capabilities.base_path {
+        Some(base) => Check::pass("Kernel module", format!("linuwu_sense loaded ({base})")),
+        None => Check::fail("Kernel module", "linuwu_sense is not loaded")
+            .fix("install it (see the README prerequisites), then `sudo modprobe linuwu_sense`")
+            .fix("after a kernel update, check that DKMS rebuilt it: `dkms status`"),
+    }
+}
+
+/// One line per control attribute. Sense attributes are skipped while the
+/// module is missing; the module check already says why.
+fn control_checks(capabilities: &Capabilities, is_root: bool) -> Vec<Check> {
+    capabilities
+        .attrs
+        .iter()
+        .filter(|(id, _)| capabilities.module_loaded() || *id == ControlId::ThermalProfile)
+        .map(|(id, capability)| control_check(*id, capability, is_root, capabilities))
+        .collect()
+}
+
+fn control_check(
+    id: ControlId,
+    capability: &AttrCapability,
+    is_root: bool,
+    capabilities: &Capabilities,
+) -> Check {
+    let name = id.label();
+    let check = match (capability.present, capability.readable, capability.writable) {
+        (false, _, _) if id == ControlId::ThermalProfile => {
+            return Check::warn(name, "platform_profile is missing").fix(
+                "the firmware offers no ACPI platform profiles; thermal profiles stay hidden",
+            );
+        }
+        (false, _, _) => {
+            return Check::warn(name, "missing; this model may not expose it");
+        }
+        (true, _, true) => Check::pass(name, "read/write"),
+        (true, true, false) => Check::fail(name, "read-only"),
+        (true, false, false) => Check::fail(name, "present but not accessible"),
+    };
+
+    let check = match check.status {
+        CheckStatus::Fail if !is_root => check.fix(format!("Fix: {}", setup_hint())),
+        _ => check,
+    };
+    if id == ControlId::ThermalProfile && check.status == CheckStatus::Pass {
+        let choices = fs::read_to_string(sysfs(PROFILE_CHOICES)).unwrap_or_default();
+        let detail = format!("read/write; choices: {}", choices.trim());
+        return Check { detail, ..check };
+    }
+    if id.is_fan() && check.status == CheckStatus::Pass {
+        let fan = capabilities.fan_control.describe();
+        let detail = format!("read/write; fan_speed takes {fan}");
+        return Check { detail, ..check };
+    }
+    if id == ControlId::UsbCharging && check.status == CheckStatus::Pass {
+        let thresholds: Vec<String> = capabilities
+            .usb_thresholds
+            .iter()
+            .map(u8::to_string)
+            .collect();
+        let detail = format!("read/write; thresholds: {}", thresholds.join(", "));
+        return Check { detail, ..check };
+    }
+    check
+}
+
+/// power-profiles-daemon and TLP switch platform_profile back behind our
+/// back unless one side leaves it alone.
+fn power_manager_check(conflicts: &[String], managed: bool) -> Check {
+    let name = "Power managers";
+    if !managed {
+        return Check::pass(
+            name,
+            "platform_profile left to other tools (manage_thermal_profile off)",
+        );
+    }
+    if conflicts.is_empty() {
+        return Check::pass(name, "no power-profiles-daemon or TLP running");
+    }
+    Check::warn(
+        name,
+        format!(
+            "{} also manages platform_profile",
+            conflicts::describe(conflicts)
+        ),
+    )
+    .fix("thermal profiles set here may be switched back within seconds")
+    .fix("stop one of them, or set \"manage_thermal_profile\": false in config.json")
+}
+
+fn sensor_checks(setup: &SensorSetup) -> Vec<Check> {
+    let snapshot = hardware::read_sensors_once(setup);
+    vec![
+        sensor_check(
+            "CPU temperature",
+            &snapshot.cpu_temp,
+            "set cpu_temp_path in config.json to a working temp*_input file",
+        ),
+        sensor_check(
+            "GPU temperature",
+            &snapshot.gpu_temp,
+            "install nvidia-smi for the proprietary driver, or set gpu_source to none",
+        ),
+    ]
+}
+
+/// Sensors only ever warn: the rest of the app works without them.
+fn sensor_check(name: &str, metric: &SensorMetric, fix: &str) -> Check {
+    match metric.value {
+        Some(value) => Check::pass(name, format!("{value:.0} °C")),
+        None => {
+            let error = metric.error.as_deref().unwrap_or("no reading");
+            Check::warn(name, error).fix(fix)
+        }
+    }
+}
+
+fn keyboard_check(access: &UsbAccess, probed: &str) -> Check {
+    let name = "USB keyboard";
+    match access {
+        UsbAccess::Accessible(model) => {
+            Check::pass(name, format!("{} ({}) opens", model.name, model.id_label()))
+        }
+        UsbAccess::PermissionDenied(model) => {
+            let detail = format!("{} ({}) found, access denied", model.name, model.id_label());
+            let check = Check::fail(name, detail)
+                .fix(format!("Fix: {}", setup_hint()))
+                .fix(format!("or add these lines to {UDEV_RULE_PATH}:"));
+            keyboard_udev_rules(model).lines().fold(check, Check::fix)
+        }
+        UsbAccess::NotFound => Check::warn(name, format!("not found (probed {probed})"))
+            .fix("if the keyboard uses another ID with the same protocol, set keyboard_override"),
+        UsbAccess::Error(error) => Check::fail(name, format!("found but not usable: {error}")),
+    }
+}
+
+fn udev_check(keyboards: &[KeyboardModel]) -> Check {
+    let name = "udev rules";
+    let Ok(installed) = fs::read_to_string(UDEV_RULE_PATH) else {
+        return Check::warn(name, format!("{UDEV_RULE_PATH} is missing"))
+            .fix(format!("Fix: {}", setup_hint()));
+    };
+
+    let missing: Vec<String> = keyboards
+        .iter()
+        .filter(|model| !installed.contains(&keyboard_udev_rules(model)))
+        .map(|model| model.id_label())
+        .collect();
+    if missing.is_empty() {
+        Check::pass(name, UDEV_RULE_PATH)
+    } else {
+        Check::warn(name, format!("no rule for {}", missing.join(", ")))
+            .fix("re-run `arch-sense --install-permissions` to cover every probed keyboard")
+    }
+}
+
+fn failed_count(checks: &[Check]) -> usize {
+    checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count()
+}
+
+fn render(checks: &[Check]) -> String {
+    let mut out = String::from("Arch-Sense doctor\n");
+    for check in checks {
+        out += &format!(
+            "  {} {}: {}\n",
+            check.status.symbol(),
+            check.name,
+            check.detail
+        );
+        for line in &check.fix {
+            out += &format!("      {line}\n");
+        }
+    }
+
+    let failed = failed_count(checks);
+    let warned = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Warn)
+        .count();
+    out += &match (failed, warned) {
+        (0, 0) => "\nEverything looks fine.\n".to_string(),
+        _ => format!("\n{failed} failed, {warned} warning(s).\n"),
+    };
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb_protocol::SUPPORTED_KEYBOARDS;
+
+    #[test]
+    fn config_check_fails_only_on_a_broken_file() {
+        let dir = std::env::temp_dir().join(format!("arch-sense-doctor-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+
+        assert_eq!(config_check(&path).status, CheckStatus::Pass);
+        let valid = serde_json::to_string(&AppConfig::default()).unwrap();
+        fs::write(&path, valid).unwrap();
+        assert_eq!(config_check(&path).status, CheckStatus::Pass);
+        fs::write(&path, "{ not json").unwrap();
+        let broken = config_check(&path);
+        assert_eq!(broken.status, CheckStatus::Fail);
+        assert!(!broken.fix.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn denied_keyboard_lists_its_udev_rules() {
+        let model = SUPPORTED_KEYBOARDS[0];
+        let check = keyboard_check(&UsbAccess::PermissionDenied(model), "04F2:0117");
+
+        assert_eq!(check.status, CheckStatus::Fail);
+        for rule in keyboard_udev_rules(&model).lines() {
+            assert!(check.fix.iter().any(|line| line == rule));
+        }
+        assert_eq!(
+            keyboard_check(&UsbAccess::NotFound, "04F2:0117").status,
+            CheckStatus::Warn
+        );
+    }
+
+    #[test]
+    fn report_counts_failures_and_serializes_for_bug_reports() {
+        let checks = vec![
+            Check::pass("Kernel module", "linuwu_sense loaded"),
+            Check::warn("GPU temperature", "nvidia-smi is not installed"),
+            Check::fail("Fan Speed", "read-only").fix("Fix: run it"),
+        ];
+
+        assert_eq!(failed_count(&checks), 1);
+        let text = render(&checks);
+        assert!(text.contains("  ✓ Kernel module: linuwu_sense loaded\n"));
+        assert!(text.contains("  ✗ Fan Speed: read-only\n      Fix: run it\n"));
+        assert!(text.ends_with("1 failed, 1 warning(s).\n"));
+
+        let json = serde_json::to_value(&checks).unwrap();
+        assert_eq!(json[1]["status"], "warn");
+        assert!(json[0].get("fix").is_none());
+        assert_eq!(json[2]["fix"][0], "Fix: run it");
+    }
+}
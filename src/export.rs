@@ -0,0 +1,154 @@
+//! Sensor history export for graphing a session later.
+//!
+//! The TUI keeps the last 600 samples. `E` writes them to a CSV file under
+//! the user's data directory; gaps from failed reads stay empty.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::app::SensorsState;
+use crate::user_dirs::{create_dir_all, sudo_user, xdg_dir, XdgDir};
+
+const CSV_HEADER: [&str; 5] = [
+    "timestamp",
+    "cpu_temp_c",
+    "gpu_temp_c",
+    "cpu_fan_rpm",
+    "gpu_fan_rpm",
+];
+
+/// Writes the history to `history-<time>.csv` and returns its path.
+pub(crate) fn export_history(sensors: &SensorsState) -> Result<PathBuf> {
+    if sensors.sample_times.is_empty() {
+        return Err(anyhow!("no samples yet"));
+    }
+
+    let sudo_user = sudo_user();
+    let dir = xdg_dir(XdgDir::Data, sudo_user.as_ref())
+        .ok_or_else(|| anyhow!("HOME is not set"))?
+        .join("arch-sense");
+    create_dir_all(&dir, sudo_user.as_ref())
+        .with_context(|| format!("creating {}", dir.display()))?;
+
+    let stamp = rfc3339(SystemTime::now()).replace([':', '-'], "");
+    let path = dir.join(format!("history-{stamp}.csv"));
+    fs::write(&path, history_csv(sensors))
+        .with_context(|| format!("writing {}", path.display()))?;
+
+    if let Some(user) = sudo_user {
+        user.hand_back(&path);
+    }
+
+    Ok(path)
+}
+
+pub(crate) fn history_csv(sensors: &SensorsState) -> String {
+    let number = |sample: Option<&Option<u64>>| {
+        sample
+            .copied()
+            .flatten()
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    };
+
+    let mut csv = String::new();
+    push_row(&mut csv, &CSV_HEADER);
+    for (index, at) in sensors.sample_times.iter().enumerate() {
+        let row = [
+            rfc3339(*at),
+            number(sensors.cpu_temp_history.get(index)),
+            number(sensors.gpu_temp_history.get(index)),
+            number(sensors.cpu_fan_history.get(index)),
+            number(sensors.gpu_fan_history.get(index)),
+        ];
+        push_row(&mut csv, &row);
+    }
+    csv
+}
+
+fn push_row<S: AsRef<str>>(csv: &mut String, fields: &[S]) {
+    let fields: Vec<Cow<str>> = fields
+        .iter()
+        .map(|field| csv_field(field.as_ref()))
+        .collect();
+    csv.push_str(&fields.join(","));
+    csv.push_str("\r\n");
+}
+
+/// Quotes fields containing separators, quotes or line breaks (RFC 4180).
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// UTC timestamp such as `2026-10-17T08:05:09Z`.
+pub(crate) fn rfc3339(at: SystemTime) -> String {
+    let secs = at
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3_600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn fields_with_separators_or_quotes_are_escaped() {
+        let mut csv = String::new();
+        push_row(&mut csv, &["plain", "a,b", "say \"hi\"", "two\nlines", ""]);
+        assert_eq!(csv, "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\r\n");
+    }
+
+    #[test]
+    fn gaps_are_empty_fields_with_rfc3339_timestamps() {
+        let mut sensors = SensorsState::new();
+        let start = UNIX_EPOCH + Duration::from_secs(1_792_224_309);
+        sensors
+            .sample_times
+            .extend([start, start + Duration::from_secs(1)]);
+        sensors.cpu_temp_history.extend([Some(61), None]);
+        sensors.gpu_temp_history.extend([None, Some(55)]);
+        sensors.cpu_fan_history.extend([Some(2400), Some(2500)]);
+        sensors.gpu_fan_history.extend([Some(0), None]);
+
+        assert_eq!(
+            history_csv(&sensors),
+            "timestamp,cpu_temp_c,gpu_temp_c,cpu_fan_rpm,gpu_fan_rpm\r\n\
+             2026-10-17T08:05:09Z,61,,2400,0\r\n\
+             2026-10-17T08:05:10Z,,55,2500,\r\n"
+        );
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            rfc3339(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+}
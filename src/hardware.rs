@@ -1,23 +1,64 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
 use std::fs;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, Output, Stdio};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
+use rusb::{DeviceHandle, GlobalContext};
 
+use crate::config::AppConfig;
+use crate::conflicts::{self, RevertWatch};
 use crate::constants::{
-    ps, BRIGHT_HW_MAX, CPU_TEMP_PATH, KB_EP, KB_IFACE, PLATFORM_PROFILE, PREAMBLE, PROFILE_CHOICES,
-    PS_BASE, SPEED_HW_FAST, SPEED_HW_SLOW, USB_TIMEOUT,
+    find_ps_base, ps, sysfs, BOOT_ANIMATION_ATTRS, LCD_OVERRIDE_ATTRS, PLATFORM_PROFILE,
+    PROFILE_CHOICES, PS_BASE_CANDIDATES, USB_TIMEOUT,
 };
 use crate::models::{
-    ControlChoice, ControlId, ControlItem, ControlKind, FanMode, Rgb, RgbSettings, SensorMetric,
-    SensorSnapshot, OFF_EFFECT_INDEX, RANDOM_COLOR_INDEX,
+    ControlChoice, ControlId, ControlItem, ControlKind, FanMode, GpuSource, RgbBackend,
+    RgbSettings, SensorMetric, SensorSnapshot,
 };
-use crate::permissions::{keyboard_access, keyboard_present, open_keyboard, setup_hint, UsbAccess};
+use crate::permissions::{
+    keyboard_access, keyboard_present, open_keyboard, path_write_access, setup_hint, PathAccess,
+    UsbAccess,
+};
+use crate::quirks::{self, FanControl};
+use crate::rgb_protocol::{probed_label, KeyboardModel, Transfer};
+use crate::{hidraw, rgb_protocol};
+
+/// Prefix for messages about writes that `--dry-run` skipped.
+pub(crate) const DRY_RUN_PREFIX: &str = "[dry-run]";
 
 const HWMON_BASE: &str = "/sys/class/hwmon";
+const THERMAL_BASE: &str = "/sys/class/thermal";
+const DRM_BASE: &str = "/sys/class/drm";
+const POWER_SUPPLY_BASE: &str = "/sys/class/power_supply";
+const CHARGE_LIMIT_CHOICES: [&str; 3] = ["60", "80", "100"];
+const CHARGE_LIMIT_RANGE: std::ops::RangeInclusive<u8> = 50..=100;
+/// Manual fan percentages are offered in steps of this size.
+const FAN_STEP: u8 = 10;
+const PCI_VENDOR_AMD: &str = "0x1002";
+/// PCI base class 0x03: display controller.
+const PCI_CLASS_DISPLAY_PREFIX: &str = "0x03";
+/// hwmon driver names that report a discrete GPU's own temperature.
+const GPU_HWMON_DRIVERS: [&str; 4] = ["nvidia", "amdgpu", "nouveau", "radeon"];
+const NVIDIA_HWMON_DRIVERS: [&str; 2] = ["nvidia", "nouveau"];
+/// Back-off before each retry of a transient USB error.
+const USB_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(50),
+    Duration::from_millis(150),
+    Duration::from_millis(400),
+];
+/// nvidia-smi can block for hundreds of milliseconds waking a sleeping dGPU.
+const NVIDIA_SMI_INTERVAL: Duration = Duration::from_secs(10);
+/// nvidia-smi is killed after this long so one read never stalls for more.
+const NVIDIA_SMI_TIMEOUT: Duration = Duration::from_secs(1);
+/// Per-channel change below which a Thermal re-tint is not worth a USB
+/// write; about two degrees on the default green-to-red fade.
+const TINT_STEP: u8 = 32;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum SensorRole {
@@ -28,6 +69,8 @@ enum SensorRole {
 #[derive(Clone, Debug)]
 struct HwmonFanSample {
     hwmon_name: String,
+    /// N in fanN_input; Acer's EC exposes the CPU fan as fan1 and the GPU fan as fan2.
+    index: usize,
     label: Option<String>,
     rpm: u64,
     pwm: Option<u64>,
@@ -41,27 +84,164 @@ struct HwmonTempSample {
     celsius: f64,
 }
 
+/// Probe result for a single sysfs attribute backing a control.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct AttrCapability {
+    pub(crate) present: bool,
+    pub(crate) readable: bool,
+    pub(crate) writable: bool,
+}
+
+/// Which control attributes exist on this machine, probed once and cached by
+/// the worker until the kernel module appears or disappears.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Capabilities {
+    pub(crate) base_path: Option<String>,
+    pub(crate) attrs: Vec<(ControlId, AttrCapability)>,
+    /// Whether `fan_speed` takes percentages or fixed levels.
+    pub(crate) fan_control: FanControl,
+    /// `platform_profile` is ours to write; see `manage_thermal_profile`.
+    pub(crate) manage_thermal_profile: bool,
+    /// See `charge_threshold_path`.
+    pub(crate) charge_threshold: Option<PathBuf>,
+    /// The percentages `usb_charging` takes, in ascending order.
+    pub(crate) usb_thresholds: Vec<u8>,
+}
+
+impl Capabilities {
+    pub(crate) fn probe(fan_control: FanControl, manage_thermal_profile: bool) -> Self {
+        let quirks = quirks::detected().quirks();
+        let base_path = find_ps_base();
+        let mut capabilities = Self {
+            usb_thresholds: usb_charging_thresholds(base_path.as_deref()),
+            base_path,
+            attrs: Vec::new(),
+            fan_control,
+            manage_thermal_profile,
+            charge_threshold: charge_threshold_path(),
+        };
+        // Attributes the model is known to lack stay unprobed, so they are hidden.
+        let attrs = ControlId::ALL
+            .iter()
+            .filter(|&&id| quirks.offers(id))
+            .filter(|&&id| id != ControlId::ThermalProfile || manage_thermal_profile)
+            .map(|&id| (id, probe_attr(&capabilities.control_path(id))))
+            .collect();
+        capabilities.attrs = attrs;
+        capabilities
+    }
+
+    /// An attribute in the sense directory found by the probe.
+    pub(crate) fn ps(&self, name: &str) -> String {
+        ps(self.base_path.as_deref(), name)
+    }
+
+    pub(crate) fn module_loaded(&self) -> bool {
+        self.base_path.is_some()
+    }
+
+    pub(crate) fn get(&self, id: ControlId) -> Option<AttrCapability> {
+        self.attrs
+            .iter()
+            .find(|(attr_id, _)| *attr_id == id)
+            .map(|(_, capability)| *capability)
+    }
+
+    /// Controls worth showing: everything while the module is offline (so the
+    /// user sees why they fail), otherwise only attributes that exist.
+    pub(crate) fn is_offered(&self, id: ControlId) -> bool {
+        if id == ControlId::ThermalProfile && !self.manage_thermal_profile {
+            return false;
+        }
+        !self.module_loaded() || self.get(id).is_some_and(|capability| capability.present)
+    }
+}
+
+fn probe_attr(path: &str) -> AttrCapability {
+    let path = Path::new(path);
+    let writable = path_write_access(path);
+
+    AttrCapability {
+        present: !matches!(writable, PathAccess::Missing),
+        readable: fs::File::open(path).is_ok(),
+        writable: matches!(writable, PathAccess::Writable),
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum HardwareRequest {
     Snapshot,
-    ApplyControl { id: ControlId, value: String },
+    ApplyControl {
+        id: ControlId,
+        value: String,
+    },
     ApplyRgb(RgbSettings),
+    RestoreRgb(RgbSettings),
+    ApplyProfile {
+        name: String,
+        controls: Vec<(ControlId, String)>,
+        rgb: RgbSettings,
+    },
     Shutdown,
 }
 
+impl HardwareRequest {
+    /// Requests that replace the lighting, cutting a running fade short.
+    fn changes_rgb(&self) -> bool {
+        matches!(
+            self,
+            Self::ApplyRgb(_) | Self::RestoreRgb(_) | Self::ApplyProfile { .. } | Self::Shutdown
+        )
+    }
+}
+
+/// The worker's request queue. A fade peeks at it between frames, so
+/// requests read early are kept here until their turn.
+struct Requests {
+    rx: Receiver<HardwareRequest>,
+    queued: VecDeque<HardwareRequest>,
+}
+
+impl Requests {
+    fn next(&mut self) -> Option<HardwareRequest> {
+        self.queued.pop_front().or_else(|| self.rx.recv().ok())
+    }
+
+    /// Whether a newer lighting change is waiting.
+    fn rgb_waiting(&mut self) -> bool {
+        self.queued.extend(self.rx.try_iter());
+        self.queued.iter().any(HardwareRequest::changes_rgb)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum HardwareEvent {
     Snapshot(Box<HardwareSnapshot>),
+    /// `controls` holds only the re-read controls the write touched.
     ControlApplied {
-        id: ControlId,
         controls: Vec<ControlItem>,
+        message: String,
+        /// The value read back is the one written.
+        verified: bool,
     },
     ControlFailed {
         id: ControlId,
         error: String,
     },
     RgbApplied(String),
+    RgbRestored(String),
     RgbFailed(String),
+    /// A background `RestoreRgb` failed; user settings are left alone.
+    RgbRestoreFailed(String),
+    ProfileApplied {
+        controls: Vec<ControlItem>,
+        message: String,
+        /// The keyboard accepted the profile's lighting.
+        rgb_applied: bool,
+        /// At least one control or the lighting failed, or a control did
+        /// not change.
+        failed: bool,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -70,12 +250,121 @@ pub(crate) struct HardwareSnapshot {
     pub(crate) keyboard: UsbAccess,
     pub(crate) sensors: SensorSnapshot,
     pub(crate) controls: Vec<ControlItem>,
+    pub(crate) power: PowerStatus,
     pub(crate) note: Option<String>,
+    /// One-off event the UI should surface, e.g. an automatic calibration stop.
+    pub(crate) warning: Option<String>,
+    /// Controls the worker wrote itself while collecting; their new values
+    /// are not external changes.
+    pub(crate) self_written: Vec<ControlId>,
+    /// Another program switched back a thermal profile written here.
+    pub(crate) reverted: Option<String>,
+}
+
+/// AC adapter and battery state from the power_supply class.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct PowerStatus {
+    pub(crate) ac_online: Option<bool>,
+    pub(crate) battery_percent: Option<u8>,
+    /// `Charging`, `Discharging`, `Full`, ... as reported by the kernel.
+    pub(crate) battery_status: Option<String>,
+    /// Charge or discharge rate in watts.
+    pub(crate) battery_watts: Option<f64>,
+}
+
+/// Last nvidia-smi result, reused until `NVIDIA_SMI_INTERVAL` has passed.
+#[derive(Debug, Default)]
+struct NvidiaSmiCache {
+    last: Option<(Instant, Result<f64, String>)>,
+}
+
+impl NvidiaSmiCache {
+    fn read(&mut self, now: Instant, query: impl FnOnce() -> Result<f64>) -> Result<f64, String> {
+        if let Some((queried_at, result)) = &self.last {
+            if now.saturating_duration_since(*queried_at) < NVIDIA_SMI_INTERVAL {
+                return result.clone();
+            }
+        }
+
+        let result = query().map_err(|error| error.to_string());
+        self.last = Some((now, result.clone()));
+        result
+    }
+}
+
+/// Sensor state the worker keeps between snapshots.
+#[derive(Debug, Default)]
+struct SensorCache {
+    nvidia_smi: NvidiaSmiCache,
+    /// Resolved CPU temperature files, best first; rebuilt when none can be read.
+    cpu_temp_paths: Vec<PathBuf>,
+}
+
+/// Readings from an AMD GPU's hwmon node under its DRM device.
+#[derive(Clone, Debug, PartialEq)]
+struct AmdGpuSensors {
+    edge: Option<f64>,
+    junction: Option<f64>,
+    fan_rpm: Option<u64>,
+}
+
+impl AmdGpuSensors {
+    /// Edge temperature, or junction when the edge sensor is missing.
+    fn temperature(&self) -> Option<f64> {
+        self.edge.or(self.junction)
+    }
+}
+
+/// Sensor sources resolved from the config file.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SensorSetup {
+    pub(crate) gpu_source: GpuSource,
+    pub(crate) cpu_temp_path: Option<PathBuf>,
+    /// Also decides how fan_speed is written, so the worker probes with it.
+    pub(crate) fan_control: FanControl,
+    /// Likewise whether the worker may write platform_profile.
+    pub(crate) manage_thermal_profile: bool,
+}
+
+impl SensorSetup {
+    pub(crate) fn from_config(config: &AppConfig) -> Self {
+        Self {
+            gpu_source: config.gpu_source,
+            cpu_temp_path: config.cpu_temp_path.clone(),
+            fan_control: config.fan_control(),
+            manage_thermal_profile: config.manages_thermal_profile(),
+        }
+    }
+}
+
+/// Keyboard RGB transport and the keyboards to probe, resolved from config and CLI.
+#[derive(Clone, Debug)]
+pub(crate) struct KeyboardSetup {
+    pub(crate) backend: RgbBackend,
+    pub(crate) models: Vec<KeyboardModel>,
+    /// Fade length between solid colours; `None` switches instantly.
+    pub(crate) transition: Option<Duration>,
+}
+
+impl KeyboardSetup {
+    pub(crate) fn resolve(
+        config: &AppConfig,
+        backend: Option<RgbBackend>,
+    ) -> (Self, Option<String>) {
+        let (models, warning) = config.keyboards();
+        let setup = Self {
+            backend: backend.unwrap_or(config.rgb_backend),
+            models,
+            transition: config.transition.duration(),
+        };
+        (setup, warning)
+    }
 }
 
 pub(crate) struct HardwareHandle {
     tx: Sender<HardwareRequest>,
     rx: Receiver<HardwareEvent>,
+    worker: Option<thread::JoinHandle<()>>,
 }
 
 impl HardwareHandle {
@@ -88,41 +377,158 @@ impl HardwareHandle {
     pub(crate) fn drain(&self) -> Vec<HardwareEvent> {
         self.rx.try_iter().collect()
     }
+
+    /// Stops the worker and waits for it, so the keyboard interface is released
+    /// and its kernel driver reattached before the process exits.
+    pub(crate) fn shutdown(&mut self) {
+        let _ = self.tx.send(HardwareRequest::Shutdown);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
 }
 
-pub(crate) fn spawn_worker() -> Result<HardwareHandle> {
+pub(crate) fn spawn_worker(
+    keyboard: KeyboardSetup,
+    sensors: SensorSetup,
+    dry_run: bool,
+) -> Result<HardwareHandle> {
     let (request_tx, request_rx) = mpsc::channel();
     let (event_tx, event_rx) = mpsc::channel();
 
-    thread::Builder::new()
+    let worker = thread::Builder::new()
         .name("arch-sense-hardware".into())
-        .spawn(move || worker_loop(request_rx, event_tx))
+        .spawn(move || worker_loop(request_rx, event_tx, keyboard, sensors, dry_run))
         .context("starting hardware worker")?;
 
     Ok(HardwareHandle {
         tx: request_tx,
         rx: event_rx,
+        worker: Some(worker),
     })
 }
 
-fn worker_loop(rx: Receiver<HardwareRequest>, tx: Sender<HardwareEvent>) {
-    for request in rx {
+fn worker_loop(
+    rx: Receiver<HardwareRequest>,
+    tx: Sender<HardwareEvent>,
+    keyboard: KeyboardSetup,
+    sensors: SensorSetup,
+    dry_run: bool,
+) {
+    let mut capabilities = Capabilities::probe(sensors.fan_control, sensors.manage_thermal_profile);
+    let mut sensor_cache = SensorCache::default();
+    let mut usb = UsbKeyboard::default();
+    let mut rgb_cache = RgbCache::default();
+    let mut thermal_watch = RevertWatch::default();
+    let mut requests = Requests {
+        rx,
+        queued: VecDeque::new(),
+    };
+
+    while let Some(request) = requests.next() {
         let event = match request {
-            HardwareRequest::Snapshot => HardwareEvent::Snapshot(Box::new(collect_snapshot())),
-            HardwareRequest::ApplyControl { id, value } => match write_control(id, &value) {
-                Ok(()) => HardwareEvent::ControlApplied {
-                    id,
-                    controls: load_controls(),
-                },
-                Err(error) => HardwareEvent::ControlFailed {
-                    id,
-                    error: error.to_string(),
-                },
-            },
-            HardwareRequest::ApplyRgb(settings) => match apply_rgb_settings(&settings) {
-                Ok(message) => HardwareEvent::RgbApplied(message),
-                Err(error) => HardwareEvent::RgbFailed(error.to_string()),
-            },
+            HardwareRequest::Snapshot => {
+                if find_ps_base() != capabilities.base_path {
+                    capabilities =
+                        Capabilities::probe(sensors.fan_control, sensors.manage_thermal_profile);
+                }
+                let mut snapshot = collect_snapshot(
+                    &capabilities,
+                    &keyboard.models,
+                    &sensors,
+                    &mut sensor_cache,
+                    dry_run,
+                );
+                snapshot.reverted = check_thermal_revert(&mut thermal_watch, &snapshot.controls);
+                if matches!(snapshot.keyboard, UsbAccess::NotFound) {
+                    usb.release();
+                    rgb_cache.invalidate();
+                }
+                rgb_cache.hottest = snapshot.sensors.hottest_temp();
+                if let Some(tint) = rgb_cache.due_tint().filter(|_| !dry_run) {
+                    let write = || apply_rgb_settings(&tint, &keyboard, &mut usb);
+                    if let Err(error) = rgb_cache.apply(&tint, write) {
+                        snapshot
+                            .warning
+                            .get_or_insert(format!("Thermal lighting update failed: {error}"));
+                    }
+                }
+                HardwareEvent::Snapshot(Box::new(snapshot))
+            }
+            HardwareRequest::ApplyControl { id, value } => {
+                let value = sysfs_value(id, &value, capabilities.fan_control);
+                match apply_control(&capabilities, id, &value, dry_run) {
+                    Ok(message) => {
+                        let warning = verify_write(&capabilities, id, &value, dry_run);
+                        if id == ControlId::ThermalProfile && warning.is_none() && !dry_run {
+                            thermal_watch.wrote(&value, Instant::now());
+                        }
+                        HardwareEvent::ControlApplied {
+                            controls: reload_written(&capabilities, id),
+                            verified: warning.is_none(),
+                            message: warning.unwrap_or(message),
+                        }
+                    }
+                    Err(error) => HardwareEvent::ControlFailed {
+                        id,
+                        error: error.to_string(),
+                    },
+                }
+            }
+            HardwareRequest::ApplyRgb(settings) => {
+                if !dry_run {
+                    fade_to(&settings, &keyboard, &mut usb, &rgb_cache, &mut requests);
+                }
+                match apply_rgb(&settings, &keyboard, &mut usb, &mut rgb_cache, dry_run) {
+                    Ok(message) => HardwareEvent::RgbApplied(message),
+                    Err(error) => HardwareEvent::RgbFailed(error.to_string()),
+                }
+            }
+            HardwareRequest::RestoreRgb(settings) => {
+                // A reconnected keyboard has lost whatever was applied before.
+                rgb_cache.invalidate();
+                match apply_rgb(&settings, &keyboard, &mut usb, &mut rgb_cache, dry_run) {
+                    Ok(message) => HardwareEvent::RgbRestored(message),
+                    Err(error) => HardwareEvent::RgbRestoreFailed(error.to_string()),
+                }
+            }
+            HardwareRequest::ApplyProfile {
+                name,
+                controls,
+                rgb,
+            } => {
+                let summary = apply_controls(&capabilities, &controls, dry_run);
+                if summary.applied.contains(&ControlId::ThermalProfile) && !dry_run {
+                    if let Some((_, value)) = controls
+                        .iter()
+                        .find(|(id, _)| *id == ControlId::ThermalProfile)
+                    {
+                        thermal_watch.wrote(value, Instant::now());
+                    }
+                }
+                if !dry_run {
+                    fade_to(&rgb, &keyboard, &mut usb, &rgb_cache, &mut requests);
+                }
+                let rgb_result = apply_rgb(&rgb, &keyboard, &mut usb, &mut rgb_cache, dry_run);
+                let rgb_status = match &rgb_result {
+                    Ok(_) => "RGB applied".to_string(),
+                    Err(error) => format!("RGB failed: {error}"),
+                };
+                let message = format!("Profile '{name}': {}; {rgb_status}", summary.describe());
+
+                HardwareEvent::ProfileApplied {
+                    controls: load_controls(&capabilities),
+                    message: if dry_run {
+                        format!("{DRY_RUN_PREFIX} {message}")
+                    } else {
+                        message
+                    },
+                    rgb_applied: rgb_result.is_ok(),
+                    failed: !summary.failed.is_empty()
+                        || !summary.unchanged.is_empty()
+                        || rgb_result.is_err(),
+                }
+            }
             HardwareRequest::Shutdown => break,
         };
 
@@ -132,11 +538,28 @@ fn worker_loop(rx: Receiver<HardwareRequest>, tx: Sender<HardwareEvent>) {
     }
 }
 
-pub(crate) fn collect_snapshot() -> HardwareSnapshot {
-    let module_loaded = Path::new(PS_BASE).exists();
-    let controls = load_controls();
-    let sensors = read_sensors();
-    let keyboard = keyboard_access();
+fn collect_snapshot(
+    capabilities: &Capabilities,
+    keyboards: &[KeyboardModel],
+    sensor_setup: &SensorSetup,
+    sensor_cache: &mut SensorCache,
+    dry_run: bool,
+) -> HardwareSnapshot {
+    let module_loaded = capabilities.module_loaded();
+    let power = read_power_status(Path::new(&sysfs(POWER_SUPPLY_BASE)));
+    let warning = capabilities
+        .is_offered(ControlId::BatteryCalibration)
+        .then(|| supervise_calibration(capabilities, &power, dry_run))
+        .flatten();
+    // A warning means the calibration stop above was attempted.
+    let self_written = if warning.is_some() {
+        vec![ControlId::BatteryCalibration]
+    } else {
+        Vec::new()
+    };
+    let controls = load_controls(capabilities);
+    let sensors = read_sensors(sensor_setup, sensor_cache, &capabilities.ps("fan_speed"));
+    let keyboard = keyboard_access(keyboards);
     let note = hardware_note(module_loaded, &sensors);
 
     HardwareSnapshot {
@@ -144,13 +567,104 @@ pub(crate) fn collect_snapshot() -> HardwareSnapshot {
         keyboard,
         sensors,
         controls,
+        power,
         note,
+        warning,
+        self_written,
+        reverted: None,
     }
 }
 
+/// Warns when the thermal profile read now is not the one written here a
+/// moment ago, which means another power manager owns platform_profile.
+fn check_thermal_revert(watch: &mut RevertWatch, controls: &[ControlItem]) -> Option<String> {
+    let item = controls
+        .iter()
+        .find(|item| item.id == ControlId::ThermalProfile && item.last_error.is_none())?;
+    let after = watch.observe(&item.raw, Instant::now())?;
+    Some(format!(
+        "Thermal profile switched back to {} {}s after arch-sense set it; {} is also managing \
+         platform_profile. Set \"manage_thermal_profile\": false in config.json to leave it alone",
+        item.display,
+        after.as_secs().max(1),
+        conflicts::describe(&conflicts::detect()),
+    ))
+}
+
+/// Stops a running battery calibration once the AC adapter is unplugged, since
+/// the cycle would otherwise drain the battery flat.
+fn supervise_calibration(
+    capabilities: &Capabilities,
+    power: &PowerStatus,
+    dry_run: bool,
+) -> Option<String> {
+    if power.ac_online != Some(false)
+        || read_control_raw(capabilities, ControlId::BatteryCalibration).ok()? != "1"
+    {
+        return None;
+    }
+
+    Some(
+        match apply_control(capabilities, ControlId::BatteryCalibration, "0", dry_run) {
+            Ok(_) if dry_run => {
+                format!("{DRY_RUN_PREFIX} would stop battery calibration: AC adapter unplugged")
+            }
+            Ok(_) => "Battery calibration stopped: AC adapter unplugged".to_string(),
+            Err(error) => format!(
+                "AC adapter unplugged during battery calibration; stopping it failed: {error}"
+            ),
+        },
+    )
+}
+
+/// Reads the first system battery (BAT0 or BAT1) and whether any mains supply
+/// is online. Missing attributes stay `None`.
+fn read_power_status(root: &Path) -> PowerStatus {
+    let mut status = PowerStatus::default();
+    let mut battery_seen = false;
+
+    for supply in sorted_entries(root, "") {
+        match read_optional_string(&supply.join("type")).as_deref() {
+            Some("Mains") => {
+                let online = read_optional_string(&supply.join("online")).as_deref() == Some("1");
+                status.ac_online = Some(status.ac_online.unwrap_or(false) || online);
+            }
+            // Wireless mice and headsets report a "Device" scope battery.
+            Some("Battery")
+                if !battery_seen
+                    && read_optional_string(&supply.join("scope")).as_deref() != Some("Device") =>
+            {
+                battery_seen = true;
+                status.battery_percent = read_optional_u64(&supply.join("capacity"))
+                    .map(|capacity| capacity.min(100) as u8);
+                status.battery_status = read_optional_string(&supply.join("status"));
+                status.battery_watts = read_battery_watts(&supply);
+            }
+            _ => {}
+        }
+    }
+
+    status
+}
+
+/// `power_now`, or `current_now * voltage_now` on batteries that only report those.
+fn read_battery_watts(battery: &Path) -> Option<f64> {
+    let read_micro = |name: &str| {
+        read_optional_string(&battery.join(name))
+            .and_then(|value| value.parse::<i64>().ok())
+            .map(|value| value.unsigned_abs() as f64 / 1_000_000.0)
+    };
+
+    read_micro("power_now")
+        .or_else(|| Some(read_micro("current_now")? * read_micro("voltage_now")?))
+}
+
 fn hardware_note(module_loaded: bool, sensors: &SensorSnapshot) -> Option<String> {
     if !module_loaded {
-        return Some(format!("linuwu_sense module offline: missing {PS_BASE}"));
+        return Some(format!(
+            "linuwu_sense module offline: missing {}",
+            PS_BASE_CANDIDATES.join(" and ")
+        ));
     }
 
     [
@@ -163,73 +677,259 @@ fn hardware_note(module_loaded: bool, sensors: &SensorSnapshot) -> Option<String
     .find_map(|metric| metric.error.clone())
 }
 
-fn read_sensors() -> SensorSnapshot {
-    let (cpu_fan, gpu_fan, cpu_fan_mode, gpu_fan_mode) = read_fan_telemetry();
+/// One reading outside the worker, for `--apply`, `--doctor` and `--status`.
+pub(crate) fn read_sensors_once(setup: &SensorSetup) -> SensorSnapshot {
+    let fan_speed = ps(find_ps_base().as_deref(), "fan_speed");
+    read_sensors(setup, &mut SensorCache::default(), &fan_speed)
+}
+
+/// AC and battery state outside the worker, for `--status`.
+pub(crate) fn read_power_once() -> PowerStatus {
+    read_power_status(Path::new(&sysfs(POWER_SUPPLY_BASE)))
+}
+
+/// Tints the Thermal effect on `--apply`.
+pub(crate) fn read_hottest_temp(setup: &SensorSetup) -> Option<u64> {
+    read_sensors_once(setup).hottest_temp()
+}
+
+/// `fan_speed` is the predator_sense attribute's path.
+fn read_sensors(setup: &SensorSetup, cache: &mut SensorCache, fan_speed: &str) -> SensorSnapshot {
+    let fan_speeds = read_linuwu_fan_speeds(fan_speed, setup.fan_control);
+    let (cpu_fan, mut gpu_fan, cpu_fan_mode, gpu_fan_mode) = read_fan_telemetry(fan_speeds);
+    let amd_gpu = matches!(setup.gpu_source, GpuSource::Auto | GpuSource::Amd)
+        .then(|| read_amd_dgpu(Path::new(&sysfs(DRM_BASE))));
+
+    if let Some(rpm) = amd_gpu
+        .as_ref()
+        .and_then(|gpu| gpu.as_ref().ok())
+        .and_then(|gpu| gpu.fan_rpm)
+    {
+        gpu_fan = SensorMetric::available(rpm as f64);
+    }
 
     SensorSnapshot {
-        cpu_temp: read_cpu_temp(),
-        gpu_temp: read_gpu_temp(),
+        cpu_temp: read_cpu_temp(setup.cpu_temp_path.as_deref(), &mut cache.cpu_temp_paths),
+        gpu_temp: read_gpu_temp(setup.gpu_source, amd_gpu.as_ref(), &mut cache.nvidia_smi),
         cpu_fan,
         gpu_fan,
         cpu_fan_mode,
         gpu_fan_mode,
+        cpu_fan_percent: fan_speeds.and_then(|(cpu, _)| manual_fan_percent(cpu)),
+        gpu_fan_percent: fan_speeds.and_then(|(_, gpu)| manual_fan_percent(gpu)),
     }
 }
 
-fn read_cpu_temp() -> SensorMetric {
-    let hwmon = read_hwmon_temperature(SensorRole::Cpu);
-    if let Ok(value) = hwmon {
+/// Reads the first working CPU sensor, re-resolving the candidates when the cached ones fail.
+fn read_cpu_temp(override_path: Option<&Path>, cached_paths: &mut Vec<PathBuf>) -> SensorMetric {
+    if let Some(value) = first_readable_temp(cached_paths) {
+        return SensorMetric::available(value);
+    }
+
+    *cached_paths = cpu_temp_candidates(
+        override_path,
+        Path::new(&sysfs(THERMAL_BASE)),
+        Path::new(&sysfs(HWMON_BASE)),
+    );
+    if let Some(value) = first_readable_temp(cached_paths) {
         return SensorMetric::available(value);
     }
 
-    let hwmon_error = hwmon.err().map(|error| error.to_string());
+    let override_note = override_path
+        .map(|path| format!("cpu_temp_path {} is unreadable; ", path.display()))
+        .unwrap_or_default();
 
-    match read_sysfs(CPU_TEMP_PATH).and_then(|raw| {
-        raw.parse::<f64>()
-            .map(|value| value / 1000.0)
-            .with_context(|| format!("parsing CPU temperature from {CPU_TEMP_PATH}: {raw}"))
-    }) {
+    match read_hwmon_temperature(SensorRole::Cpu) {
         Ok(value) => SensorMetric::available(value),
-        Err(error) => {
-            let detail = match hwmon_error {
-                Some(hwmon_error) => {
-                    format!("hwmon: {hwmon_error}; thermal zone: {error}")
-                }
-                None => error.to_string(),
-            };
-            SensorMetric::unavailable(format!("CPU temperature unavailable: {detail}"))
+        Err(error) => SensorMetric::unavailable(format!(
+            "CPU temperature unavailable: {override_note}no x86_pkg_temp zone or package sensor; hwmon: {error}"
+        )),
+    }
+}
+
+/// CPU temperature files in priority order: the configured override, the
+/// x86_pkg_temp thermal zone, then the coretemp/k10temp package sensor.
+fn cpu_temp_candidates(
+    override_path: Option<&Path>,
+    thermal_root: &Path,
+    hwmon_root: &Path,
+) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = override_path.map(Path::to_path_buf).into_iter().collect();
+
+    for zone in sorted_entries(thermal_root, "thermal_zone") {
+        if read_optional_string(&zone.join("type")).as_deref() == Some("x86_pkg_temp") {
+            candidates.push(zone.join("temp"));
+        }
+    }
+
+    for (driver, labels) in [
+        ("coretemp", &["Package id 0"][..]),
+        ("k10temp", &["Tdie", "Tctl"][..]),
+    ] {
+        for hwmon in sorted_entries(hwmon_root, "hwmon") {
+            if read_optional_string(&hwmon.join("name")).as_deref() != Some(driver) {
+                continue;
+            }
+
+            for label in labels {
+                candidates.extend(hwmon_input_with_label(&hwmon, label));
+            }
         }
     }
+
+    candidates
 }
 
-fn read_gpu_temp() -> SensorMetric {
-    let hwmon = read_hwmon_temperature(SensorRole::Gpu);
-    if let Ok(value) = hwmon {
-        return SensorMetric::available(value);
+fn hwmon_input_with_label(hwmon: &Path, label: &str) -> Option<PathBuf> {
+    fs::read_dir(hwmon).ok()?.flatten().find_map(|entry| {
+        let name = entry.file_name();
+        let index = parse_indexed_attr(&name.to_string_lossy(), "temp", "_label")?;
+        (read_optional_string(&entry.path()).as_deref() == Some(label))
+            .then(|| hwmon.join(format!("temp{index}_input")))
+    })
+}
+
+fn sorted_entries(root: &Path, prefix: &str) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+fn first_readable_temp(paths: &[PathBuf]) -> Option<f64> {
+    paths.iter().find_map(|path| {
+        let millidegrees = read_optional_string(path)?.parse::<f64>().ok()?;
+        Some(millidegrees / 1000.0).filter(|celsius| (-40.0..=130.0).contains(celsius))
+    })
+}
+
+/// Tries the AMD dGPU, then GPU driver hwmon nodes; nvidia-smi is a cached last resort.
+fn read_gpu_temp(
+    source: GpuSource,
+    amd_gpu: Option<&Result<AmdGpuSensors>>,
+    nvidia_smi: &mut NvidiaSmiCache,
+) -> SensorMetric {
+    if source == GpuSource::None {
+        return SensorMetric::unavailable("GPU temperature disabled (gpu_source is none)");
     }
 
-    let hwmon_error = hwmon.err().map(|error| error.to_string());
+    let mut errors = Vec::new();
 
-    match read_gpu_temp_from_nvidia_smi() {
-        Ok(value) => SensorMetric::available(value),
-        Err(error) => {
-            let detail = match hwmon_error {
-                Some(hwmon_error) => format!("hwmon: {hwmon_error}; nvidia-smi: {error}"),
-                None => error.to_string(),
-            };
-            SensorMetric::unavailable(format!("GPU temperature unavailable: {detail}"))
+    match amd_gpu {
+        Some(Ok(gpu)) => match gpu.temperature() {
+            Some(value) => return SensorMetric::available(value),
+            None => errors.push("amdgpu: hwmon has no temp1_input or temp2_input".to_string()),
+        },
+        Some(Err(error)) => errors.push(format!("amdgpu: {error}")),
+        None => {}
+    }
+
+    if matches!(source, GpuSource::Auto | GpuSource::Nvidia) {
+        let drivers: &[&str] = if source == GpuSource::Nvidia {
+            &NVIDIA_HWMON_DRIVERS
+        } else {
+            &GPU_HWMON_DRIVERS
+        };
+
+        match read_gpu_hwmon_temperature(drivers) {
+            Ok(value) => return SensorMetric::available(value),
+            Err(error) => errors.push(format!("hwmon: {error}")),
+        }
+
+        match nvidia_smi.read(Instant::now(), read_gpu_temp_from_nvidia_smi) {
+            Ok(value) => return SensorMetric::available(value),
+            Err(error) => errors.push(format!("nvidia-smi: {error}")),
+        }
+    }
+
+    SensorMetric::unavailable(format!(
+        "GPU temperature unavailable: {}",
+        errors.join("; ")
+    ))
+}
+
+/// Reads the AMD display device under `drm_root`, preferring one that is not
+/// the boot VGA device so a Radeon dGPU wins over an AMD iGPU.
+fn read_amd_dgpu(drm_root: &Path) -> Result<AmdGpuSensors> {
+    let hwmon = find_amd_dgpu_hwmon(drm_root)?;
+    let read_celsius = |name: &str| {
+        read_optional_string(&hwmon.join(name))
+            .and_then(|raw| raw.parse::<f64>().ok())
+            .map(|millidegrees| millidegrees / 1000.0)
+    };
+
+    Ok(AmdGpuSensors {
+        edge: read_celsius("temp1_input"),
+        junction: read_celsius("temp2_input"),
+        fan_rpm: read_optional_u64(&hwmon.join("fan1_input")).filter(|rpm| *rpm > 0),
+    })
+}
+
+fn find_amd_dgpu_hwmon(drm_root: &Path) -> Result<PathBuf> {
+    let mut candidates = Vec::new();
+
+    for entry in fs::read_dir(drm_root)
+        .with_context(|| format!("reading {}", drm_root.display()))?
+        .flatten()
+    {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Skip connector entries such as card1-eDP-1.
+        let is_card = name
+            .strip_prefix("card")
+            .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()));
+        if !is_card {
+            continue;
         }
+
+        let device = entry.path().join("device");
+        let is_amd_display = read_optional_string(&device.join("vendor")).as_deref()
+            == Some(PCI_VENDOR_AMD)
+            && read_optional_string(&device.join("class"))
+                .is_some_and(|class| class.starts_with(PCI_CLASS_DISPLAY_PREFIX));
+        if !is_amd_display {
+            continue;
+        }
+
+        let Some(hwmon) = fs::read_dir(device.join("hwmon")).ok().and_then(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .min()
+        }) else {
+            continue;
+        };
+
+        let boot_vga = read_optional_string(&device.join("boot_vga")).as_deref() == Some("1");
+        candidates.push((boot_vga, name, hwmon));
     }
+
+    candidates.sort();
+    candidates
+        .into_iter()
+        .next()
+        .map(|(_, _, hwmon)| hwmon)
+        .with_context(|| format!("no AMD GPU with hwmon under {}", drm_root.display()))
 }
 
 fn read_gpu_temp_from_nvidia_smi() -> Result<f64> {
-    match Command::new("nvidia-smi")
+    let spawned = Command::new("nvidia-smi")
         .args([
             "--query-gpu=temperature.gpu",
             "--format=csv,noheader,nounits",
         ])
-        .output()
-    {
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    match spawned.and_then(|child| wait_with_timeout(child, NVIDIA_SMI_TIMEOUT)) {
         Ok(output) if output.status.success() => {
             let raw = String::from_utf8_lossy(&output.stdout).trim().to_string();
             raw.parse::<f64>()
@@ -247,13 +947,35 @@ fn read_gpu_temp_from_nvidia_smi() -> Result<f64> {
         Err(error) if error.kind() == ErrorKind::NotFound => {
             bail!("nvidia-smi is not installed")
         }
+        Err(error) if error.kind() == ErrorKind::TimedOut => {
+            bail!("nvidia-smi did not answer within {NVIDIA_SMI_TIMEOUT:?}")
+        }
         Err(error) => bail!("starting nvidia-smi failed: {error}"),
     }
 }
 
-fn read_fan_telemetry() -> (SensorMetric, SensorMetric, FanMode, FanMode) {
-    let linuwu_modes = read_linuwu_fan_modes();
-    let samples = match collect_hwmon_fan_samples() {
+/// Collects `child`'s output, killing it once `timeout` has passed. Its
+/// output is small enough to fit the pipe buffers while it runs.
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> std::io::Result<Output> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if child.try_wait()?.is_some() {
+            return child.wait_with_output();
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::from(ErrorKind::TimedOut));
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn read_fan_telemetry(
+    linuwu_speeds: Option<(u8, u8)>,
+) -> (SensorMetric, SensorMetric, FanMode, FanMode) {
+    let linuwu_modes = linuwu_speeds.map(|(cpu, gpu)| (fan_mode(cpu), fan_mode(gpu)));
+    let samples = match collect_hwmon_fan_samples(Path::new(&sysfs(HWMON_BASE))) {
         Ok(samples) => samples,
         Err(error) => {
             let message = format!("hwmon fan discovery failed: {error}");
@@ -307,20 +1029,37 @@ fn read_fan_telemetry() -> (SensorMetric, SensorMetric, FanMode, FanMode) {
     (cpu_fan, gpu_fan, cpu_mode, gpu_mode)
 }
 
-fn read_linuwu_fan_modes() -> Option<(FanMode, FanMode)> {
-    let raw = read_sysfs(&ps("fan_speed")).ok()?;
-    let parts: Vec<&str> = raw.split(',').collect();
+/// Reads the `cpu,gpu` pair from predator_sense's fan_speed as percentages;
+/// 0 means auto.
+fn read_linuwu_fan_speeds(path: &str, fan: FanControl) -> Option<(u8, u8)> {
+    let (cpu, gpu) = parse_fan_speeds(&read_sysfs(path).ok()?)?;
+    Some((fan.to_percent(cpu), fan.to_percent(gpu)))
+}
 
-    let parse_mode = |index: usize| -> Option<FanMode> {
-        let value = parts.get(index)?.trim().parse::<f64>().ok()?;
-        Some(if value >= 100.0 {
-            FanMode::Max
-        } else {
-            FanMode::Auto
-        })
+fn parse_fan_speeds(raw: &str) -> Option<(u8, u8)> {
+    let (cpu, gpu) = raw.trim().split_once(',')?;
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<u8>()
+            .ok()
+            .filter(|value| *value <= 100)
     };
 
-    Some((parse_mode(0)?, parse_mode(1)?))
+    Some((parse(cpu)?, parse(gpu)?))
+}
+
+fn fan_mode(percent: u8) -> FanMode {
+    if percent >= 100 {
+        FanMode::Max
+    } else {
+        FanMode::Auto
+    }
+}
+
+/// Manual fan percentage, or `None` while the EC is in auto mode.
+fn manual_fan_percent(percent: u8) -> Option<u8> {
+    (percent > 0).then_some(percent)
 }
 
 fn select_fan_sample_indices(samples: &[HwmonFanSample]) -> (Option<usize>, Option<usize>) {
@@ -362,6 +1101,17 @@ fn fan_score(sample: &HwmonFanSample, role: SensorRole) -> i32 {
         score += 6;
     }
 
+    if contains_any(&sample.hwmon_name.to_ascii_lowercase(), &["acer", "linuwu"]) {
+        score += 2;
+        let role_index = match role {
+            SensorRole::Cpu => 1,
+            SensorRole::Gpu => 2,
+        };
+        if sample.label.is_none() && sample.index == role_index {
+            score += 3;
+        }
+    }
+
     match role {
         SensorRole::Cpu => {
             if contains_any(&haystack, &["package", "tctl", "tdie", "coretemp", "cpu"]) {
@@ -395,10 +1145,10 @@ fn mode_from_hwmon_sample(sample: &HwmonFanSample) -> FanMode {
     FanMode::Auto
 }
 
-fn collect_hwmon_fan_samples() -> Result<Vec<HwmonFanSample>> {
+fn collect_hwmon_fan_samples(hwmon_root: &Path) -> Result<Vec<HwmonFanSample>> {
     let mut samples = Vec::new();
 
-    for hwmon_dir in list_hwmon_dirs()? {
+    for hwmon_dir in list_hwmon_dirs(hwmon_root)? {
         let hwmon_name =
             read_optional_string(&hwmon_dir.join("name")).unwrap_or_else(|| "unknown".to_string());
         let entries = match fs::read_dir(&hwmon_dir) {
@@ -425,6 +1175,7 @@ fn collect_hwmon_fan_samples() -> Result<Vec<HwmonFanSample>> {
 
             samples.push(HwmonFanSample {
                 hwmon_name: hwmon_name.clone(),
+                index,
                 label,
                 rpm,
                 pwm,
@@ -451,10 +1202,36 @@ fn read_hwmon_temperature(role: SensorRole) -> Result<f64> {
     Ok(samples[index].celsius)
 }
 
+fn read_gpu_hwmon_temperature(drivers: &[&str]) -> Result<f64> {
+    let samples = collect_hwmon_temp_samples()?;
+    let Some(index) = gpu_temp_index(&samples, drivers) else {
+        bail!("no GPU sensor ({}) in {HWMON_BASE}", drivers.join(", "));
+    };
+
+    Ok(samples[index].celsius)
+}
+
+/// Picks a sensor from a GPU driver, falling back to GPU-labelled sensors elsewhere.
+/// Ties keep the first sensor, which is the edge/GPU core reading on amdgpu.
+fn gpu_temp_index(samples: &[HwmonTempSample], drivers: &[&str]) -> Option<usize> {
+    let best = |driver_only: bool| {
+        samples
+            .iter()
+            .enumerate()
+            .filter(|(_, sample)| drivers.contains(&sample.hwmon_name.as_str()) == driver_only)
+            .map(|(index, sample)| (index, temperature_score(sample, SensorRole::Gpu)))
+            .filter(|(_, score)| driver_only || *score > 0)
+            .max_by_key(|(index, score)| (*score, Reverse(*index)))
+            .map(|(index, _)| index)
+    };
+
+    best(true).or_else(|| best(false))
+}
+
 fn collect_hwmon_temp_samples() -> Result<Vec<HwmonTempSample>> {
     let mut samples = Vec::new();
 
-    for hwmon_dir in list_hwmon_dirs()? {
+    for hwmon_dir in list_hwmon_dirs(Path::new(&sysfs(HWMON_BASE)))? {
         let hwmon_name =
             read_optional_string(&hwmon_dir.join("name")).unwrap_or_else(|| "unknown".to_string());
         let entries = match fs::read_dir(&hwmon_dir) {
@@ -574,11 +1351,11 @@ fn parse_indexed_attr(name: &str, prefix: &str, suffix: &str) -> Option<usize> {
     name[start..end].parse::<usize>().ok()
 }
 
-fn list_hwmon_dirs() -> Result<Vec<PathBuf>> {
+fn list_hwmon_dirs(root: &Path) -> Result<Vec<PathBuf>> {
     let mut dirs = Vec::new();
 
-    for entry in fs::read_dir(HWMON_BASE).with_context(|| format!("reading {HWMON_BASE}"))? {
-        let entry = entry.with_context(|| format!("reading entries in {HWMON_BASE}"))?;
+    for entry in fs::read_dir(root).with_context(|| format!("reading {}", root.display()))? {
+        let entry = entry.with_context(|| format!("reading entries in {}", root.display()))?;
         let path = entry.path();
         if path.is_dir() {
             dirs.push(path);
@@ -589,19 +1366,56 @@ fn list_hwmon_dirs() -> Result<Vec<PathBuf>> {
     Ok(dirs)
 }
 
-pub(crate) fn load_controls() -> Vec<ControlItem> {
+pub(crate) fn load_controls(capabilities: &Capabilities) -> Vec<ControlItem> {
+    load_controls_where(capabilities, |_| true)
+}
+
+/// Re-reads only what a write to `written` can change; both fans share
+/// `fan_speed`.
+fn reload_written(capabilities: &Capabilities, written: ControlId) -> Vec<ControlItem> {
+    load_controls_where(capabilities, |id| {
+        id == written || (id.is_fan() && written.is_fan())
+    })
+}
+
+fn load_controls_where(
+    capabilities: &Capabilities,
+    wanted: impl Fn(ControlId) -> bool,
+) -> Vec<ControlItem> {
     let thermal_choices = read_thermal_choices().unwrap_or_default();
 
     ControlId::ALL
         .iter()
         .copied()
-        .map(|id| read_control(id, &thermal_choices))
+        .filter(|&id| wanted(id) && capabilities.is_offered(id))
+        .map(|id| {
+            let mut item = read_control(capabilities, id, &thermal_choices);
+            item.writable = capabilities
+                .get(id)
+                .is_none_or(|capability| capability.writable);
+            item
+        })
         .collect()
 }
 
-fn read_control(id: ControlId, thermal_choices: &[String]) -> ControlItem {
-    let kind = control_kind(id, thermal_choices);
-    let raw_result = read_control_raw(id);
+/// Fan rows are read as percentages whatever `fan` the attribute takes.
+fn read_control(
+    capabilities: &Capabilities,
+    id: ControlId,
+    thermal_choices: &[String],
+) -> ControlItem {
+    let fan = capabilities.fan_control;
+    let kind = control_kind(
+        id,
+        thermal_choices,
+        &capabilities.usb_thresholds,
+        capabilities.charge_threshold.is_some(),
+        fan,
+    );
+    let raw_result = read_control_raw(capabilities, id).map(|raw| match raw.parse::<u8>() {
+        Ok(speed) if id.is_fan() => fan.to_percent(speed).to_string(),
+        _ => raw,
+    });
     let (raw, last_error) = match raw_result {
         Ok(raw) => (raw, None),
         Err(error) => ("N/A".to_string(), Some(error.to_string())),
@@ -609,22 +1423,35 @@ fn read_control(id: ControlId, thermal_choices: &[String]) -> ControlItem {
 
     ControlItem {
         id,
-        display: display_control_value(id, &raw),
+        display: display_control_value(id, &raw, fan),
         raw,
         kind,
         pending: None,
         last_error,
+        writable: true,
     }
 }
 
 fn read_thermal_choices() -> Result<Vec<String>> {
-    Ok(read_sysfs(PROFILE_CHOICES)?
-        .split_whitespace()
+    Ok(parse_thermal_choices(&read_sysfs(&sysfs(PROFILE_CHOICES))?))
+}
+
+/// Splits a choices list, dropping the `[...]` some kernels put around the active entry.
+fn parse_thermal_choices(raw: &str) -> Vec<String> {
+    raw.split_whitespace()
+        .map(|choice| choice.trim_start_matches('[').trim_end_matches(']'))
+        .filter(|choice| !choice.is_empty())
         .map(ToOwned::to_owned)
-        .collect())
+        .collect()
 }
 
-fn control_kind(id: ControlId, thermal_choices: &[String]) -> ControlKind {
+fn control_kind(
+    id: ControlId,
+    thermal_choices: &[String],
+    usb_thresholds: &[u8],
+    charge_threshold: bool,
+    fan: FanControl,
+) -> ControlKind {
     match id {
         ControlId::ThermalProfile => {
             let choices = if thermal_choices.is_empty() {
@@ -637,81 +1464,329 @@ fn control_kind(id: ControlId, thermal_choices: &[String]) -> ControlKind {
             };
             ControlKind::Choice(choices)
         }
-        ControlId::FanSpeed => ControlKind::Choice(vec![
-            ControlChoice::new("0,0", "Auto"),
-            ControlChoice::new("100,100", "Max"),
-        ]),
-        ControlId::UsbCharging => ControlKind::Choice(vec![
-            ControlChoice::new("0", "Off"),
-            ControlChoice::new("10", "Until 10%"),
-            ControlChoice::new("20", "Until 20%"),
-            ControlChoice::new("30", "Until 30%"),
-        ]),
+        ControlId::CpuFan | ControlId::GpuFan => ControlKind::Choice(
+            std::iter::once(ControlChoice::new("0", "Auto"))
+                .chain(
+                    fan.steps(FAN_STEP)
+                        .into_iter()
+                        .map(|percent| ControlChoice::new(percent.to_string(), fan.label(percent))),
+                )
+                .collect(),
+        ),
+        ControlId::BatteryLimiter if charge_threshold => ControlKind::Choice(
+            CHARGE_LIMIT_CHOICES
+                .iter()
+                .map(|&limit| ControlChoice::new(limit, charge_limit_label(limit)))
+                .collect(),
+        ),
+        ControlId::UsbCharging => ControlKind::Choice(
+            usb_thresholds
+                .iter()
+                .map(|&percent| {
+                    ControlChoice::new(percent.to_string(), usb_charging_label(percent))
+                })
+                .collect(),
+        ),
         _ => ControlKind::Toggle,
     }
 }
 
-fn read_control_raw(id: ControlId) -> Result<String> {
-    match id {
-        ControlId::ThermalProfile => read_sysfs(PLATFORM_PROFILE),
-        ControlId::BacklightTimeout => read_sysfs(&ps("backlight_timeout")),
-        ControlId::BatteryCalibration => read_sysfs(&ps("battery_calibration")),
-        ControlId::BatteryLimiter => read_sysfs(&ps("battery_limiter")),
-        ControlId::BootAnimation => read_sysfs(&ps("boot_animation_sound")),
-        ControlId::FanSpeed => read_sysfs(&ps("fan_speed")),
-        ControlId::LcdOverride => read_sysfs(&ps("lcd_override")),
-        ControlId::UsbCharging => read_sysfs(&ps("usb_charging")),
+impl Capabilities {
+    fn control_path(&self, id: ControlId) -> String {
+        match id {
+            ControlId::ThermalProfile => sysfs(PLATFORM_PROFILE),
+            ControlId::BacklightTimeout => self.ps("backlight_timeout"),
+            ControlId::BatteryCalibration => self.ps("battery_calibration"),
+            ControlId::BatteryLimiter => self
+                .charge_threshold
+                .as_ref()
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.ps("battery_limiter")),
+            ControlId::BootAnimation => self.sense_attr(&BOOT_ANIMATION_ATTRS),
+            ControlId::CpuFan | ControlId::GpuFan => self.ps("fan_speed"),
+            ControlId::LcdOverride => self.sense_attr(&LCD_OVERRIDE_ATTRS),
+            ControlId::UsbCharging => self.ps("usb_charging"),
+        }
+    }
+
+    /// The first of `names` present in the sense directory, or the first name
+    /// when none is, so errors mention the usual one. Looked up on each use,
+    /// so a module reloaded with other names is followed.
+    fn sense_attr(&self, names: &[&str]) -> String {
+        names
+            .iter()
+            .map(|name| self.ps(name))
+            .find(|path| Path::new(path).exists())
+            .unwrap_or_else(|| self.ps(names[0]))
     }
 }
 
-fn write_control(id: ControlId, value: &str) -> Result<()> {
-    if value == "N/A" {
-        bail!(
-            "{} is unavailable because the hardware did not report choices",
-            id.label()
-        );
+/// Fan rows read their own half of the `cpu,gpu` pair.
+fn read_control_raw(capabilities: &Capabilities, id: ControlId) -> Result<String> {
+    let raw = read_sysfs(&capabilities.control_path(id))?;
+    if !id.is_fan() {
+        return Ok(raw);
     }
 
-    match id {
-        ControlId::ThermalProfile => write_sysfs(PLATFORM_PROFILE, value),
-        ControlId::BacklightTimeout => write_sysfs(&ps("backlight_timeout"), value),
-        ControlId::BatteryCalibration => write_sysfs(&ps("battery_calibration"), value),
-        ControlId::BatteryLimiter => write_sysfs(&ps("battery_limiter"), value),
-        ControlId::BootAnimation => write_sysfs(&ps("boot_animation_sound"), value),
-        ControlId::FanSpeed => write_sysfs(&ps("fan_speed"), value),
-        ControlId::LcdOverride => write_sysfs(&ps("lcd_override"), value),
-        ControlId::UsbCharging => write_sysfs(&ps("usb_charging"), value),
+    let (cpu, gpu) =
+        parse_fan_speeds(&raw).with_context(|| format!("unexpected fan_speed value '{raw}'"))?;
+    Ok(if id == ControlId::CpuFan { cpu } else { gpu }.to_string())
+}
+
+/// Turns the app's `cpu,gpu` fan percentages into what fan_speed takes;
+/// every other value is written as is.
+fn sysfs_value(id: ControlId, value: &str, fan: FanControl) -> String {
+    match parse_fan_speeds(value) {
+        Some((cpu, gpu)) if id.is_fan() => format!("{},{}", fan.to_raw(cpu), fan.to_raw(gpu)),
+        _ => value.to_string(),
     }
 }
 
-fn display_control_value(id: ControlId, raw: &str) -> String {
-    match id {
-        ControlId::ThermalProfile => thermal_label(raw).to_string(),
-        ControlId::BacklightTimeout | ControlId::BootAnimation | ControlId::LcdOverride => {
-            on_off(raw)
-        }
-        ControlId::BatteryCalibration => match raw {
-            "1" => "Running".to_string(),
-            "0" => "Stopped".to_string(),
-            other => other.to_string(),
-        },
-        ControlId::BatteryLimiter => match raw {
-            "1" => "80% Limit".to_string(),
-            "0" => "Disabled".to_string(),
-            other => other.to_string(),
-        },
-        ControlId::FanSpeed => match raw {
-            "0" | "0,0" => "Auto".to_string(),
-            "100" | "100,100" => "Max".to_string(),
-            other => format!("CPU/GPU {other}"),
-        },
-        ControlId::UsbCharging => match raw {
-            "0" => "Disabled".to_string(),
-            "10" => "Until 10%".to_string(),
-            "20" => "Until 20%".to_string(),
-            "30" => "Until 30%".to_string(),
+/// Writes a control, or with `dry_run` only validates it and describes the write.
+fn apply_control(
+    capabilities: &Capabilities,
+    id: ControlId,
+    value: &str,
+    dry_run: bool,
+) -> Result<String> {
+    if id == ControlId::BatteryCalibration
+        && value == "1"
+        && read_power_status(Path::new(&sysfs(POWER_SUPPLY_BASE))).ac_online == Some(false)
+    {
+        bail!("connect the AC adapter before starting battery calibration");
+    }
+
+    if dry_run {
+        check_control_value(capabilities, id, value)?;
+        return Ok(format!(
+            "{DRY_RUN_PREFIX} would write '{value}' to {}",
+            capabilities.control_path(id)
+        ));
+    }
+
+    write_control(capabilities, id, value)?;
+    Ok(format!("{} applied", id.label()))
+}
+
+/// Reads a control back after a write the driver accepted. Some ECs drop
+/// writes silently, e.g. LCD overdrive on 60 Hz panels; the warning says so.
+fn verify_write(
+    capabilities: &Capabilities,
+    id: ControlId,
+    value: &str,
+    dry_run: bool,
+) -> Option<String> {
+    if dry_run {
+        return None;
+    }
+    let current = read_sysfs(&capabilities.control_path(id)).ok()?;
+    let stuck = match (parse_fan_speeds(&current), parse_fan_speeds(value)) {
+        (Some(read), Some(written)) => read == written,
+        _ => current == value.trim(),
+    };
+
+    (!stuck).then(|| {
+        format!(
+            "{}: write accepted but the value did not change (still '{current}')",
+            id.label()
+        )
+    })
+}
+
+/// What happened to each control of a batch write.
+#[derive(Debug, Default)]
+pub(crate) struct WriteSummary {
+    pub(crate) applied: Vec<ControlId>,
+    /// The attribute does not exist on this machine.
+    pub(crate) skipped: Vec<ControlId>,
+    /// Accepted, but read back with the old value.
+    pub(crate) unchanged: Vec<ControlId>,
+    pub(crate) failed: Vec<(ControlId, String)>,
+}
+
+impl WriteSummary {
+    /// "5 applied, 1 skipped (USB Charging), 1 unchanged (LCD Override),
+    /// 1 failed (Fan: ...)".
+    pub(crate) fn describe(&self) -> String {
+        let labels = |ids: &[ControlId]| {
+            ids.iter()
+                .map(|id| id.label())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut parts = vec![format!("{} applied", self.applied.len())];
+        if !self.skipped.is_empty() {
+            parts.push(format!(
+                "{} skipped ({})",
+                self.skipped.len(),
+                labels(&self.skipped)
+            ));
+        }
+        if !self.unchanged.is_empty() {
+            parts.push(format!(
+                "{} unchanged ({})",
+                self.unchanged.len(),
+                labels(&self.unchanged)
+            ));
+        }
+        if !self.failed.is_empty() {
+            let failures = self
+                .failed
+                .iter()
+                .map(|(id, error)| format!("{}: {error}", id.label()))
+                .collect::<Vec<_>>()
+                .join("; ");
+            parts.push(format!("{} failed ({failures})", self.failed.len()));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Writes each control in turn; a missing attribute or a failed write never
+/// stops the rest.
+pub(crate) fn apply_controls(
+    capabilities: &Capabilities,
+    writes: &[(ControlId, String)],
+    dry_run: bool,
+) -> WriteSummary {
+    let mut summary = WriteSummary::default();
+
+    for (id, value) in writes {
+        if !capabilities
+            .get(*id)
+            .is_some_and(|capability| capability.present)
+        {
+            summary.skipped.push(*id);
+            continue;
+        }
+
+        let value = sysfs_value(*id, value, capabilities.fan_control);
+        match apply_control(capabilities, *id, &value, dry_run) {
+            Ok(_) if verify_write(capabilities, *id, &value, dry_run).is_some() => {
+                summary.unchanged.push(*id)
+            }
+            Ok(_) => summary.applied.push(*id),
+            Err(error) => summary.failed.push((*id, error.to_string())),
+        }
+    }
+
+    summary
+}
+
+fn write_control(capabilities: &Capabilities, id: ControlId, value: &str) -> Result<()> {
+    check_control_value(capabilities, id, value)?;
+    write_sysfs(&capabilities.control_path(id), value)
+}
+
+fn check_control_value(capabilities: &Capabilities, id: ControlId, value: &str) -> Result<()> {
+    if value == "N/A" {
+        bail!(
+            "{} is unavailable because the hardware did not report choices",
+            id.label()
+        );
+    }
+    if !is_valid_control_value(capabilities, id, value) {
+        bail!("'{value}' is not a valid {} value", id.label());
+    }
+
+    Ok(())
+}
+
+/// Rejects values the driver would refuse, before anything touches sysfs.
+fn is_valid_control_value(capabilities: &Capabilities, id: ControlId, value: &str) -> bool {
+    match id {
+        ControlId::ThermalProfile => !value.trim().is_empty(),
+        ControlId::CpuFan | ControlId::GpuFan => parse_fan_speeds(value).is_some(),
+        ControlId::BatteryLimiter if capabilities.charge_threshold.is_some() => value
+            .parse::<u8>()
+            .is_ok_and(|limit| CHARGE_LIMIT_RANGE.contains(&limit)),
+        ControlId::UsbCharging => value
+            .parse::<u8>()
+            .is_ok_and(|percent| capabilities.usb_thresholds.contains(&percent)),
+        _ => matches!(value, "0" | "1"),
+    }
+}
+
+fn display_control_value(id: ControlId, raw: &str, fan: FanControl) -> String {
+    match id {
+        ControlId::ThermalProfile => thermal_label(raw).to_string(),
+        ControlId::BacklightTimeout | ControlId::BootAnimation | ControlId::LcdOverride => {
+            on_off(raw)
+        }
+        ControlId::BatteryCalibration => match raw {
+            "1" => "Running".to_string(),
+            "0" => "Stopped".to_string(),
             other => other.to_string(),
         },
+        ControlId::BatteryLimiter => match raw {
+            "1" => "80% Limit".to_string(),
+            "0" => "Disabled".to_string(),
+            other => charge_limit_label(other),
+        },
+        ControlId::CpuFan | ControlId::GpuFan => match raw.parse::<u8>() {
+            Ok(0) => "Auto".to_string(),
+            Ok(percent) => fan.label(percent),
+            Err(_) => raw.to_string(),
+        },
+        ControlId::UsbCharging => match raw.parse::<u8>() {
+            Ok(0) => "Disabled".to_string(),
+            Ok(percent) => usb_charging_label(percent),
+            Err(_) => raw.to_string(),
+        },
+    }
+}
+
+/// The standard `charge_control_end_threshold` of the first battery that has one.
+/// When present it replaces predator_sense's fixed 80% `battery_limiter` switch.
+pub(crate) fn charge_threshold_path() -> Option<PathBuf> {
+    sorted_entries(Path::new(&sysfs(POWER_SUPPLY_BASE)), "BAT")
+        .into_iter()
+        .map(|battery| battery.join("charge_control_end_threshold"))
+        .find(|path| path.is_file())
+}
+
+/// The percentages `usb_charging` takes, in ascending order. Read from the driver's
+/// `usb_charging_choices` when it has one (`0 10 20 30`, optionally with
+/// `%` or the current value in brackets), else the model's quirks.
+fn usb_charging_thresholds(base: Option<&str>) -> Vec<u8> {
+    let listed = fs::read_to_string(ps(base, "usb_charging_choices"))
+        .map(|choices| parse_usb_charging_choices(&choices))
+        .unwrap_or_default();
+    if listed.is_empty() {
+        quirks::detected().quirks().usb_charging_thresholds.to_vec()
+    } else {
+        listed
+    }
+}
+
+fn parse_usb_charging_choices(choices: &str) -> Vec<u8> {
+    let mut thresholds: Vec<u8> = choices
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|choice| {
+            choice
+                .trim_matches(|c| matches!(c, '[' | ']' | '%'))
+                .parse()
+                .ok()
+        })
+        .filter(|&percent| percent <= 100)
+        .collect();
+    thresholds.sort_unstable();
+    thresholds.dedup();
+    thresholds
+}
+
+fn usb_charging_label(percent: u8) -> String {
+    match percent {
+        0 => "Off".to_string(),
+        percent => format!("Until {percent}%"),
+    }
+}
+
+fn charge_limit_label(raw: &str) -> String {
+    match raw {
+        "100" => "Full Charge".to_string(),
+        limit if limit.parse::<u8>().is_ok() => format!("{limit}% Limit"),
+        other => other.to_string(),
     }
 }
 
@@ -760,140 +1835,1109 @@ fn sysfs_error(
     }
 }
 
-pub(crate) fn apply_rgb_settings(settings: &RgbSettings) -> Result<String> {
-    let effect = settings.effect();
+pub(crate) fn apply_rgb_settings(
+    settings: &RgbSettings,
+    keyboard: &KeyboardSetup,
+    usb: &mut UsbKeyboard,
+) -> Result<String> {
+    let transfers = rgb_protocol::transfers(settings);
+    let models = &keyboard.models;
+
+    match keyboard.backend {
+        RgbBackend::Libusb => usb
+            .send(&transfers, models)
+            .map(|model| libusb_applied(&model)),
+        RgbBackend::Hidraw => hidraw::send_transfers(&transfers, models)
+            .map(|(node, model)| hidraw_applied(&node, &model)),
+        RgbBackend::Auto => match usb.send(&transfers, models) {
+            Ok(model) => Ok(libusb_applied(&model)),
+            Err(usb_error) => match hidraw::send_transfers(&transfers, models) {
+                Ok((node, model)) => Ok(hidraw_applied(&node, &model)),
+                Err(hid_error) => Err(anyhow::anyhow!(
+                    "{usb_error:#}; hidraw fallback failed: {hid_error:#}"
+                )),
+            },
+        },
+    }
+}
+
+fn apply_rgb(
+    settings: &RgbSettings,
+    keyboard: &KeyboardSetup,
+    usb: &mut UsbKeyboard,
+    cache: &mut RgbCache,
+    dry_run: bool,
+) -> Result<String> {
+    cache.thermal = settings.is_thermal().then_some(*settings);
+    let sent = settings.tinted(cache.hottest);
+    if dry_run {
+        Ok(preview_rgb_settings(&sent, keyboard))
+    } else {
+        cache.apply(&sent, || apply_rgb_settings(&sent, keyboard, usb))
+    }
+}
+
+/// Fades from the lighting on the keyboard towards `settings` when both
+/// are solid colours. Only done over an already claimed USB handle, which
+/// spares a claim per frame; the caller then writes the target itself.
+fn fade_to(
+    settings: &RgbSettings,
+    keyboard: &KeyboardSetup,
+    usb: &mut UsbKeyboard,
+    cache: &RgbCache,
+    requests: &mut Requests,
+) {
+    let (Some(duration), Some(from)) = (keyboard.transition, cache.applied) else {
+        return;
+    };
+    if usb.claimed.is_none() {
+        return;
+    }
+    let Some(frames) = rgb_protocol::fade_frames(&from, &settings.tinted(cache.hottest)) else {
+        return;
+    };
+
+    let interval = duration / (frames.len() as u32 + 1);
+    play_fade(
+        &frames,
+        interval,
+        |frame| {
+            usb.send(&rgb_protocol::transfers(frame), &keyboard.models)
+                .map(drop)
+        },
+        || requests.rgb_waiting(),
+        thread::sleep,
+    );
+}
+
+/// Sends `frames` one `interval` apart. Stops at the first failed write or
+/// as soon as `interrupted` reports a newer request; returns how many
+/// frames went out.
+fn play_fade(
+    frames: &[RgbSettings],
+    interval: Duration,
+    mut write: impl FnMut(&RgbSettings) -> Result<()>,
+    mut interrupted: impl FnMut() -> bool,
+    mut sleep: impl FnMut(Duration),
+) -> usize {
+    for (sent, frame) in frames.iter().enumerate() {
+        if interrupted() || write(frame).is_err() {
+            return sent;
+        }
+        sleep(interval);
+    }
+    frames.len()
+}
+
+/// The last RGB state the keyboard accepted, so repeated identical requests
+/// (a held brightness key at 100%) skip the USB round trip.
+#[derive(Default)]
+struct RgbCache {
+    applied: Option<RgbSettings>,
+    /// Last requested Thermal lighting, re-tinted as temperatures change.
+    thermal: Option<RgbSettings>,
+    /// Hottest reading of the last snapshot.
+    hottest: Option<u64>,
+}
+
+impl RgbCache {
+    fn apply(
+        &mut self,
+        settings: &RgbSettings,
+        write: impl FnOnce() -> Result<String>,
+    ) -> Result<String> {
+        if self.applied == Some(*settings) {
+            return Ok("Keyboard lighting already applied".to_string());
+        }
 
-    if settings.effect_idx == OFF_EFFECT_INDEX {
-        return send_usb_commands(&[PREAMBLE, [0x08, 0x02, 0x01, 0x00, 0x00, 0x01, 0x01, 0x9B]]);
+        self.applied = None;
+        let message = write()?;
+        self.applied = Some(*settings);
+        Ok(message)
     }
 
-    let mut commands = vec![PREAMBLE];
-    if effect.has_color && settings.color_idx != RANDOM_COLOR_INDEX {
-        commands.push(make_color_packet(settings.color().rgb));
+    fn invalidate(&mut self) {
+        self.applied = None;
     }
-    commands.push(make_effect_packet(settings));
 
-    send_usb_commands(&commands)
+    /// The Thermal lighting to send when its colour has drifted from what
+    /// the keyboard shows. Nothing is due while the keyboard is gone: the
+    /// restore after it returns starts tinting again.
+    fn due_tint(&self) -> Option<RgbSettings> {
+        let thermal = self.thermal?;
+        let shown = self.applied?.custom?;
+        let tint = thermal.thermal_color(self.hottest);
+        let moved = [
+            shown.r.abs_diff(tint.r),
+            shown.g.abs_diff(tint.g),
+            shown.b.abs_diff(tint.b),
+        ]
+        .into_iter()
+        .any(|delta| delta > TINT_STEP);
+        moved.then(|| thermal.tinted(self.hottest))
+    }
 }
 
-pub(crate) fn is_keyboard_present() -> bool {
-    keyboard_present()
+/// Describes the packets `apply_rgb_settings` would send, without opening the keyboard.
+pub(crate) fn preview_rgb_settings(settings: &RgbSettings, keyboard: &KeyboardSetup) -> String {
+    let transfers = rgb_protocol::transfers(settings);
+    let described = transfers
+        .iter()
+        .map(|transfer| match transfer {
+            Transfer::Report(packet) => format!("{packet:02X?}"),
+            Transfer::Frame(frame) => format!("<{}-byte zone frame>", frame.len()),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "{DRY_RUN_PREFIX} would send {} RGB packets to {}: [{described}]",
+        transfers.len(),
+        probed_label(&keyboard.models)
+    )
 }
 
-fn make_color_packet(color: Rgb) -> [u8; 8] {
-    [0x14, 0x00, 0x00, color.r, color.g, color.b, 0x00, 0x00]
+fn libusb_applied(model: &KeyboardModel) -> String {
+    format!("Keyboard lighting applied to {} via libusb", model.name)
 }
 
-fn make_effect_packet(settings: &RgbSettings) -> [u8; 8] {
-    let effect = settings.effect();
-    let hardware_brightness = ((settings.brightness as u16) * BRIGHT_HW_MAX as u16 / 100) as u8;
-    let hardware_speed = if settings.speed >= 100 {
-        SPEED_HW_FAST
-    } else {
-        let range = (SPEED_HW_SLOW - SPEED_HW_FAST) as u16;
-        (SPEED_HW_SLOW - (settings.speed as u16 * range / 100) as u8).max(SPEED_HW_FAST)
-    };
-    let color_preset = if settings.color_idx == RANDOM_COLOR_INDEX {
-        0x08
-    } else {
-        0x01
-    };
-    let direction = if effect.has_direction {
-        settings.direction_idx as u8 + 1
-    } else {
-        0x01
-    };
+fn hidraw_applied(node: &Path, model: &KeyboardModel) -> String {
+    format!(
+        "Keyboard lighting applied to {} via hidraw ({})",
+        model.name,
+        node.display()
+    )
+}
 
-    [
-        0x08,
-        0x02,
-        effect.opcode,
-        hardware_speed,
-        hardware_brightness,
-        color_preset,
-        direction,
-        0x9B,
-    ]
+pub(crate) fn is_keyboard_present(keyboards: &[KeyboardModel]) -> bool {
+    keyboard_present(keyboards)
 }
 
-fn send_usb_commands(commands: &[[u8; 8]]) -> Result<String> {
-    let handle = open_keyboard()?;
-    let was_attached = handle.kernel_driver_active(KB_IFACE).unwrap_or(false);
+/// The keyboard's lighting interface, claimed on first use and kept claimed
+/// until `release` or drop, so repeated RGB writes skip open/detach/claim.
+#[derive(Default)]
+pub(crate) struct UsbKeyboard {
+    claimed: Option<ClaimedKeyboard>,
+}
 
-    if was_attached {
-        handle.detach_kernel_driver(KB_IFACE).with_context(|| {
-            format!(
-                "failed to detach keyboard kernel driver on interface {KB_IFACE}; {}",
-                setup_hint()
-            )
-        })?;
+impl UsbKeyboard {
+    fn send(
+        &mut self,
+        commands: &[Transfer],
+        keyboards: &[KeyboardModel],
+    ) -> Result<KeyboardModel> {
+        let reused = self.claimed.is_some();
+        match self.send_once(commands, keyboards) {
+            // A cached handle may belong to a keyboard that was unplugged since.
+            Err(error) if reused && self.claimed.is_none() => self
+                .send_once(commands, keyboards)
+                .with_context(|| format!("retry after stale USB handle ({error:#})")),
+            result => result,
+        }
     }
 
-    if let Err(error) = handle
-        .claim_interface(KB_IFACE)
-        .with_context(|| format!("failed to claim USB interface {KB_IFACE}; {}", setup_hint()))
-    {
-        if was_attached {
-            let _ = handle.attach_kernel_driver(KB_IFACE);
+    fn send_once(
+        &mut self,
+        commands: &[Transfer],
+        keyboards: &[KeyboardModel],
+    ) -> Result<KeyboardModel> {
+        let keyboard = match &mut self.claimed {
+            Some(keyboard) => keyboard,
+            None => self.claimed.insert(ClaimedKeyboard::open(keyboards)?),
+        };
+        let model = keyboard.model;
+
+        for command in commands {
+            let result = match command {
+                Transfer::Report(packet) => keyboard.write(packet),
+                Transfer::Frame(frame) => keyboard.write_frame(frame),
+            };
+            if let Err((error, attempts)) = result {
+                if invalidates_handle(error) {
+                    self.release();
+                }
+                match command {
+                    Transfer::Report(packet) => bail!(
+                        "USB control transfer failed for packet {packet:02X?} after {attempts} attempt(s): {error}"
+                    ),
+                    Transfer::Frame(_) => bail!(
+                        "USB interrupt transfer failed for the zone frame after {attempts} attempt(s): {error}"
+                    ),
+                }
+            }
         }
-        return Err(error);
+
+        Ok(model)
     }
 
-    let _ = handle.clear_halt(KB_EP);
+    pub(crate) fn release(&mut self) {
+        self.claimed = None;
+    }
+}
 
-    let transfer = (|| -> Result<()> {
-        for command in commands {
-            handle
-                .write_control(0x21, 0x09, 0x0300, KB_IFACE as u16, command, USB_TIMEOUT)
-                .with_context(|| {
-                    format!("USB control transfer failed for packet {command:02X?}")
-                })?;
+/// Runs `transfer`, retrying transient errors once per entry in `delays`;
+/// `before_retry` gets the delay to wait. Failures carry the attempt count.
+fn retry_usb<T>(
+    delays: &[Duration],
+    mut transfer: impl FnMut() -> rusb::Result<T>,
+    mut before_retry: impl FnMut(Duration),
+) -> Result<T, (rusb::Error, usize)> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match transfer() {
+            Ok(value) => return Ok(value),
+            Err(error) if is_transient_usb_error(error) && attempts <= delays.len() => {
+                before_retry(delays[attempts - 1]);
+            }
+            Err(error) => return Err((error, attempts)),
+        }
+    }
+}
+
+/// Errors the keyboard firmware throws while it settles, e.g. right after resume.
+fn is_transient_usb_error(error: rusb::Error) -> bool {
+    matches!(
+        error,
+        rusb::Error::Timeout | rusb::Error::Pipe | rusb::Error::Busy
+    )
+}
+
+/// Errors after which the handle is useless and must be reopened.
+fn invalidates_handle(error: rusb::Error) -> bool {
+    matches!(
+        error,
+        rusb::Error::NoDevice | rusb::Error::Io | rusb::Error::NotFound
+    )
+}
+
+struct ClaimedKeyboard {
+    handle: DeviceHandle<GlobalContext>,
+    model: KeyboardModel,
+    reattach: bool,
+}
+
+impl ClaimedKeyboard {
+    fn open(keyboards: &[KeyboardModel]) -> Result<Self> {
+        let (handle, model) = open_keyboard(keyboards)?;
+        let iface = model.interface;
+        let reattach = handle.kernel_driver_active(iface).unwrap_or(false);
+
+        if reattach {
+            handle.detach_kernel_driver(iface).with_context(|| {
+                format!(
+                    "failed to detach keyboard kernel driver on interface {iface}; {}",
+                    setup_hint()
+                )
+            })?;
+        }
+
+        if let Err(error) = handle
+            .claim_interface(iface)
+            .with_context(|| format!("failed to claim USB interface {iface}; {}", setup_hint()))
+        {
+            if reattach {
+                let _ = handle.attach_kernel_driver(iface);
+            }
+            return Err(error);
         }
-        Ok(())
-    })();
 
-    let release = handle
-        .release_interface(KB_IFACE)
-        .context("failed to release USB keyboard interface");
+        let _ = handle.clear_halt(model.endpoint);
 
-    if was_attached {
-        let _ = handle.attach_kernel_driver(KB_IFACE);
+        Ok(Self {
+            handle,
+            model,
+            reattach,
+        })
     }
 
-    transfer?;
-    release?;
+    fn write(&self, command: &[u8; 8]) -> Result<(), (rusb::Error, usize)> {
+        let iface = self.model.interface;
+        retry_usb(
+            &USB_RETRY_DELAYS,
+            || {
+                self.handle
+                    .write_control(0x21, 0x09, 0x0300, iface as u16, command, USB_TIMEOUT)
+                    .map(|_| ())
+            },
+            |delay| {
+                thread::sleep(delay);
+                let _ = self.handle.clear_halt(self.model.endpoint);
+            },
+        )
+    }
 
-    Ok("Keyboard lighting applied".to_string())
+    fn write_frame(&self, frame: &[u8]) -> Result<(), (rusb::Error, usize)> {
+        retry_usb(
+            &USB_RETRY_DELAYS,
+            || {
+                self.handle
+                    .write_interrupt(self.model.endpoint, frame, USB_TIMEOUT)
+                    .map(|_| ())
+            },
+            |delay| {
+                thread::sleep(delay);
+                let _ = self.handle.clear_halt(self.model.endpoint);
+            },
+        )
+    }
+}
+
+impl Drop for ClaimedKeyboard {
+    fn drop(&mut self) {
+        let _ = self.handle.release_interface(self.model.interface);
+        if self.reattach {
+            let _ = self.handle.attach_kernel_driver(self.model.interface);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::RgbConfig;
-    use crate::models::RgbSettings;
+    use crate::constants::{NITRO_BASE, PS_BASE, TEST_SYSFS_ROOT};
 
     #[test]
-    fn effect_packet_maps_brightness_and_speed_to_hardware_ranges() {
-        let mut settings = RgbSettings::from_config(&RgbConfig::default());
-        settings.brightness = 100;
-        settings.speed = 0;
+    fn display_values_are_human_readable() {
+        assert_eq!(
+            display_control_value(ControlId::ThermalProfile, "balanced", FanControl::Percent),
+            "Balanced"
+        );
+        assert_eq!(
+            display_control_value(ControlId::CpuFan, "0", FanControl::Percent),
+            "Auto"
+        );
+        assert_eq!(
+            display_control_value(ControlId::GpuFan, "70", FanControl::Percent),
+            "70%"
+        );
+        assert_eq!(
+            display_control_value(ControlId::BatteryLimiter, "1", FanControl::Percent),
+            "80% Limit"
+        );
+    }
 
-        let packet = make_effect_packet(&settings);
+    fn temp_sample(hwmon_name: &str, label: Option<&str>, celsius: f64) -> HwmonTempSample {
+        HwmonTempSample {
+            hwmon_name: hwmon_name.to_string(),
+            label: label.map(str::to_string),
+            celsius,
+        }
+    }
 
-        assert_eq!(packet[3], SPEED_HW_SLOW);
-        assert_eq!(packet[4], BRIGHT_HW_MAX);
+    #[test]
+    fn gpu_temperature_prefers_gpu_driver_hwmon() {
+        let samples = [
+            temp_sample("acpitz", None, 45.0),
+            temp_sample("coretemp", Some("Package id 0"), 60.0),
+            temp_sample("amdgpu", Some("edge"), 52.0),
+            temp_sample("amdgpu", Some("junction"), 58.0),
+        ];
+        assert_eq!(gpu_temp_index(&samples, &GPU_HWMON_DRIVERS), Some(2));
+
+        let cpu_only = [
+            temp_sample("acpitz", None, 45.0),
+            temp_sample("coretemp", Some("Package id 0"), 60.0),
+        ];
+        assert_eq!(gpu_temp_index(&cpu_only, &GPU_HWMON_DRIVERS), None);
     }
 
     #[test]
-    fn display_values_are_human_readable() {
+    fn nvidia_smi_is_not_requeried_within_interval() {
+        let start = Instant::now();
+        let mut cache = NvidiaSmiCache::default();
+        let mut queries = 0;
+
+        for offset in [0, 1, 9] {
+            let result = cache.read(start + Duration::from_secs(offset), || {
+                queries += 1;
+                Ok(48.0)
+            });
+            assert_eq!(result, Ok(48.0));
+        }
+        assert_eq!(queries, 1);
+
+        let result = cache.read(start + NVIDIA_SMI_INTERVAL, || bail!("GPU asleep"));
+        assert_eq!(result, Err("GPU asleep".to_string()));
+    }
+
+    fn write_file(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn amd_dgpu_is_preferred_over_boot_vga_igpu() {
+        let root = std::env::temp_dir().join(format!("arch-sense-drm-{}", std::process::id()));
+        let igpu = root.join("card0/device");
+        let dgpu = root.join("card1/device");
+        let nvidia = root.join("card2/device");
+
+        for (device, vendor, boot_vga, edge) in [
+            (&igpu, PCI_VENDOR_AMD, "1", "41000"),
+            (&dgpu, PCI_VENDOR_AMD, "0", "57000"),
+            (&nvidia, "0x10de", "0", "63000"),
+        ] {
+            write_file(&device.join("vendor"), vendor);
+            write_file(&device.join("class"), "0x030000");
+            write_file(&device.join("boot_vga"), boot_vga);
+            write_file(&device.join("hwmon/hwmon3/temp1_input"), edge);
+        }
+        write_file(&dgpu.join("hwmon/hwmon3/temp2_input"), "66000");
+        write_file(&dgpu.join("hwmon/hwmon3/fan1_input"), "2100");
+        fs::create_dir_all(root.join("card1-eDP-1")).unwrap();
+
+        let sensors = read_amd_dgpu(&root);
+        fs::remove_dir_all(&root).unwrap();
+
         assert_eq!(
-            display_control_value(ControlId::ThermalProfile, "balanced"),
-            "Balanced"
+            sensors.unwrap(),
+            AmdGpuSensors {
+                edge: Some(57.0),
+                junction: Some(66.0),
+                fan_rpm: Some(2100),
+            }
         );
-        assert_eq!(display_control_value(ControlId::FanSpeed, "0,0"), "Auto");
+    }
+
+    #[test]
+    fn missing_amd_gpu_is_an_error() {
+        let root = std::env::temp_dir().join(format!("arch-sense-drm-none-{}", std::process::id()));
+        write_file(&root.join("card0/device/vendor"), "0x8086");
+        write_file(&root.join("card0/device/class"), "0x030000");
+
+        let sensors = read_amd_dgpu(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(sensors.is_err());
+    }
+
+    #[test]
+    fn cpu_temp_candidates_follow_priority() {
+        let root = std::env::temp_dir().join(format!("arch-sense-cpu-{}", std::process::id()));
+        let thermal = root.join("thermal");
+        let hwmon = root.join("hwmon");
+        write_file(&thermal.join("thermal_zone0/type"), "acpitz");
+        write_file(&thermal.join("thermal_zone0/temp"), "27800");
+        write_file(&thermal.join("thermal_zone3/type"), "x86_pkg_temp");
+        write_file(&thermal.join("thermal_zone3/temp"), "61000");
+        write_file(&hwmon.join("hwmon1/name"), "acpitz");
+        write_file(&hwmon.join("hwmon4/name"), "coretemp");
+        write_file(&hwmon.join("hwmon4/temp2_label"), "Core 0");
+        write_file(&hwmon.join("hwmon4/temp1_label"), "Package id 0");
+        write_file(&hwmon.join("hwmon4/temp1_input"), "62000");
+        let override_path = root.join("missing/temp");
+
+        let candidates = cpu_temp_candidates(Some(&override_path), &thermal, &hwmon);
+        let value = first_readable_temp(&candidates);
+        fs::remove_dir_all(&root).unwrap();
+
         assert_eq!(
-            display_control_value(ControlId::BatteryLimiter, "1"),
-            "80% Limit"
+            candidates,
+            [
+                override_path,
+                thermal.join("thermal_zone3/temp"),
+                hwmon.join("hwmon4/temp1_input"),
+            ]
+        );
+        // The unreadable override falls through to the package zone.
+        assert_eq!(value, Some(61.0));
+    }
+
+    #[test]
+    fn fan_speed_percentages_are_parsed() {
+        assert_eq!(parse_fan_speeds("0,0\n"), Some((0, 0)));
+        assert_eq!(parse_fan_speeds("45, 100"), Some((45, 100)));
+        assert_eq!(parse_fan_speeds("150,-5"), None);
+        assert_eq!(parse_fan_speeds("auto"), None);
+        assert_eq!(manual_fan_percent(0), None);
+        assert_eq!(manual_fan_percent(45), Some(45));
+        assert_eq!(fan_mode(100), FanMode::Max);
+    }
+
+    #[test]
+    fn acer_hwmon_fans_map_by_index() {
+        let root = std::env::temp_dir().join(format!("arch-sense-fans-{}", std::process::id()));
+        write_file(&root.join("hwmon0/name"), "nvme");
+        write_file(&root.join("hwmon5/name"), "acer");
+        write_file(&root.join("hwmon5/fan1_input"), "3200");
+        write_file(&root.join("hwmon5/fan2_input"), "4100");
+
+        let samples = collect_hwmon_fan_samples(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let samples = samples.unwrap();
+        let (cpu, gpu) = select_fan_sample_indices(&samples);
+        assert_eq!(samples[cpu.unwrap()].rpm, 3200);
+        assert_eq!(samples[gpu.unwrap()].rpm, 4100);
+    }
+
+    /// A throwaway sysfs tree that `sysfs()` resolves into on this test's
+    /// thread while it is alive.
+    struct FakeSysfs {
+        root: PathBuf,
+    }
+
+    impl FakeSysfs {
+        fn new(name: &str) -> Self {
+            let fake = Self::bare(name);
+            fs::create_dir_all(fake.path(PS_BASE)).unwrap();
+            fake
+        }
+
+        /// A tree without a predator_sense directory.
+        fn bare(name: &str) -> Self {
+            let root = std::env::temp_dir()
+                .join(format!("arch-sense-sysfs-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            let path = root.to_string_lossy().into_owned();
+            TEST_SYSFS_ROOT.with(|fake| *fake.borrow_mut() = Some(path));
+
+            Self { root }
+        }
+
+        fn path(&self, path: &str) -> PathBuf {
+            self.root.join(path.trim_start_matches('/'))
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            write_file(&self.path(path), contents);
+        }
+    }
+
+    impl Drop for FakeSysfs {
+        fn drop(&mut self) {
+            TEST_SYSFS_ROOT.with(|fake| *fake.borrow_mut() = None);
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn controls_round_trip_through_fake_sysfs() {
+        let fake = FakeSysfs::new("controls");
+        fake.write(PLATFORM_PROFILE, "balanced\n");
+        fake.write(PROFILE_CHOICES, "quiet [balanced] performance\n");
+        fake.write(&format!("{PS_BASE}/usb_charging"), "10\n");
+        fake.write(&format!("{PS_BASE}/fan_speed"), "30,70\n");
+
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        assert!(capabilities.module_loaded());
+
+        let controls = load_controls(&capabilities);
+        let thermal = controls
+            .iter()
+            .find(|item| item.id == ControlId::ThermalProfile)
+            .unwrap();
+        assert_eq!(thermal.display, "Balanced");
+        let ControlKind::Choice(choices) = &thermal.kind else {
+            panic!("thermal profile should be a choice");
+        };
+        let values: Vec<&str> = choices.iter().map(|choice| choice.value.as_str()).collect();
+        assert_eq!(values, ["quiet", "balanced", "performance"]);
+
+        write_control(&capabilities, ControlId::UsbCharging, "20").unwrap();
+        assert_eq!(
+            read_control_raw(&capabilities, ControlId::UsbCharging).unwrap(),
+            "20"
+        );
+        assert_eq!(
+            read_control_raw(&capabilities, ControlId::CpuFan).unwrap(),
+            "30"
+        );
+        assert_eq!(
+            read_control_raw(&capabilities, ControlId::GpuFan).unwrap(),
+            "70"
+        );
+        write_control(&capabilities, ControlId::GpuFan, "100,100").unwrap();
+        assert_eq!(
+            read_linuwu_fan_speeds(&capabilities.ps("fan_speed"), FanControl::Percent),
+            Some((100, 100))
+        );
+    }
+
+    #[test]
+    fn fan_levels_are_shown_and_written_as_percentages() {
+        let fake = FakeSysfs::new("fan-levels");
+        fake.write(&format!("{PS_BASE}/fan_speed"), "2,4\n");
+        let levels = FanControl::Levels(4);
+
+        let capabilities = Capabilities::probe(levels, true);
+        let controls = load_controls(&capabilities);
+        let fan = |id| controls.iter().find(|item| item.id == id).unwrap();
+        assert_eq!(fan(ControlId::CpuFan).raw, "50");
+        assert_eq!(fan(ControlId::CpuFan).display, "Level 2");
+        assert_eq!(fan(ControlId::GpuFan).display, "Max");
+        let ControlKind::Choice(choices) = &fan(ControlId::CpuFan).kind else {
+            panic!("fans should be a choice");
+        };
+        let values: Vec<&str> = choices.iter().map(|choice| choice.value.as_str()).collect();
+        assert_eq!(values, ["0", "25", "50", "75", "100"]);
+        assert_eq!(
+            read_linuwu_fan_speeds(&capabilities.ps("fan_speed"), levels),
+            Some((50, 100))
+        );
+
+        // A 70% preset from a profile or --apply lands on the nearest level.
+        let writes = [(ControlId::CpuFan, "70,0".to_string())];
+        let summary = apply_controls(&capabilities, &writes, false);
+        assert!(summary.failed.is_empty());
+        assert_eq!(
+            fs::read_to_string(fake.path(&format!("{PS_BASE}/fan_speed"))).unwrap(),
+            "3,0"
+        );
+    }
+
+    #[test]
+    fn an_unmanaged_thermal_profile_is_hidden_and_left_alone() {
+        let fake = FakeSysfs::new("unmanaged");
+        fake.write(PLATFORM_PROFILE, "balanced\n");
+        fake.write(PROFILE_CHOICES, "quiet balanced performance\n");
+
+        let capabilities = Capabilities::probe(FanControl::Percent, false);
+        assert!(!capabilities.is_offered(ControlId::ThermalProfile));
+        assert!(load_controls(&capabilities)
+            .iter()
+            .all(|item| item.id != ControlId::ThermalProfile));
+
+        let writes = [(ControlId::ThermalProfile, "performance".to_string())];
+        let summary = apply_controls(&capabilities, &writes, false);
+        assert_eq!(summary.skipped, [ControlId::ThermalProfile]);
+        assert_eq!(
+            fs::read_to_string(fake.path(PLATFORM_PROFILE)).unwrap(),
+            "balanced\n"
+        );
+    }
+
+    #[test]
+    fn nitro_layout_offers_only_its_own_attributes() {
+        let fake = FakeSysfs::bare("nitro");
+        fake.write(PLATFORM_PROFILE, "balanced\n");
+        fake.write(PROFILE_CHOICES, "quiet balanced performance\n");
+        for (attr, value) in [
+            ("backlight_timeout", "0"),
+            ("battery_calibration", "0"),
+            ("battery_limiter", "1"),
+            ("fan_speed", "0,0"),
+            ("usb_charging", "0"),
+        ] {
+            fake.write(&format!("{NITRO_BASE}/{attr}"), value);
+        }
+
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        assert_eq!(
+            capabilities.base_path.as_deref(),
+            Some(fake.path(NITRO_BASE).to_str().unwrap())
+        );
+
+        let ids: Vec<ControlId> = load_controls(&capabilities)
+            .iter()
+            .map(|item| item.id)
+            .collect();
+        assert!(!ids.contains(&ControlId::BootAnimation));
+        assert!(!ids.contains(&ControlId::LcdOverride));
+        assert!(ids.contains(&ControlId::BatteryLimiter));
+        assert!(ids.contains(&ControlId::CpuFan));
+
+        write_control(&capabilities, ControlId::BatteryLimiter, "0").unwrap();
+        assert_eq!(
+            fs::read_to_string(fake.path(&format!("{NITRO_BASE}/battery_limiter"))).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn usb_charging_thresholds_follow_the_driver_list() {
+        let fake = FakeSysfs::new("usb-choices");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        fake.write(&format!("{PS_BASE}/usb_charging"), "40\n");
+
+        let usb = || {
+            load_controls(&Capabilities::probe(FanControl::Percent, true))
+                .into_iter()
+                .find(|item| item.id == ControlId::UsbCharging)
+                .unwrap()
+        };
+        // Without a list the default set applies, but an unknown reading
+        // is still shown rather than turned into an error.
+        let item = usb();
+        assert_eq!(item.display, "Until 40%");
+        assert!(item.last_error.is_none());
+        assert!(write_control(&capabilities, ControlId::UsbCharging, "40").is_err());
+
+        fake.write(
+            &format!("{PS_BASE}/usb_charging_choices"),
+            "0 20% [40] 60\n",
         );
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        assert_eq!(capabilities.usb_thresholds, [0, 20, 40, 60]);
+        let ControlKind::Choice(choices) = usb().kind else {
+            panic!("usb_charging is a choice");
+        };
+        assert_eq!(choices[3], ControlChoice::new("60", "Until 60%"));
+        write_control(&capabilities, ControlId::UsbCharging, "60").unwrap();
+        assert!(write_control(&capabilities, ControlId::UsbCharging, "10").is_err());
+
+        fake.write(&format!("{PS_BASE}/usb_charging"), "fast");
+        assert_eq!(usb().display, "fast");
+    }
+
+    #[test]
+    fn renamed_attributes_are_found_under_either_name() {
+        let fake = FakeSysfs::new("aliases");
+        fake.write(&format!("{PS_BASE}/lcd_overdrive"), "0\n");
+        fake.write(&format!("{PS_BASE}/boot_animation"), "1\n");
+
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        assert!(capabilities.get(ControlId::LcdOverride).unwrap().present);
+        let controls = load_controls(&capabilities);
+        let boot = controls
+            .iter()
+            .find(|item| item.id == ControlId::BootAnimation)
+            .unwrap();
+        assert_eq!(boot.raw, "1");
+
+        write_control(&capabilities, ControlId::LcdOverride, "1").unwrap();
+        let overdrive = fake.path(&format!("{PS_BASE}/lcd_overdrive"));
+        assert_eq!(fs::read_to_string(&overdrive).unwrap(), "1");
+        assert!(!fake.path(&format!("{PS_BASE}/lcd_override")).exists());
+
+        // The usual name wins when a module exposes both.
+        fake.write(&format!("{PS_BASE}/lcd_override"), "0\n");
+        assert_eq!(
+            read_control_raw(&capabilities, ControlId::LcdOverride).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn writes_the_ec_ignores_are_reported() {
+        let fake = FakeSysfs::new("verify");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        fake.write(&format!("{PS_BASE}/lcd_override"), "1");
+        fake.write(&format!("{PS_BASE}/fan_speed"), "30,70\n");
+
+        assert_eq!(
+            verify_write(&capabilities, ControlId::LcdOverride, "1", false),
+            None
+        );
+        assert_eq!(
+            verify_write(&capabilities, ControlId::CpuFan, "30, 70", false),
+            None
+        );
+        // The EC kept the old value.
+        fake.write(&format!("{PS_BASE}/lcd_override"), "0");
+        let warning = verify_write(&capabilities, ControlId::LcdOverride, "1", false).unwrap();
+        assert!(warning.contains("did not change (still '0')"), "{warning}");
+        // Nothing was written, so there is nothing to check.
+        assert_eq!(
+            verify_write(&capabilities, ControlId::LcdOverride, "1", true),
+            None
+        );
+
+        let summary = WriteSummary {
+            unchanged: vec![ControlId::LcdOverride],
+            ..WriteSummary::default()
+        };
+        assert_eq!(summary.describe(), "0 applied, 1 unchanged (LCD Override)");
+    }
+
+    #[test]
+    fn malformed_fan_speed_is_ignored() {
+        let fake = FakeSysfs::new("fan-speed");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        for raw in ["87", "120,30", ""] {
+            fake.write(&format!("{PS_BASE}/fan_speed"), raw);
+            assert_eq!(
+                read_linuwu_fan_speeds(&capabilities.ps("fan_speed"), FanControl::Percent),
+                None,
+                "{raw:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_rejected_before_writing() {
+        let fake = FakeSysfs::new("validation");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        fake.write(&format!("{PS_BASE}/usb_charging"), "10");
+
+        assert!(write_control(&capabilities, ControlId::UsbCharging, "15").is_err());
+        assert!(write_control(&capabilities, ControlId::CpuFan, "120,30").is_err());
+        assert!(write_control(&capabilities, ControlId::BatteryLimiter, "2").is_err());
+        assert_eq!(
+            read_control_raw(&capabilities, ControlId::UsbCharging).unwrap(),
+            "10"
+        );
+        assert_eq!(
+            parse_thermal_choices("[low-power] balanced  performance"),
+            ["low-power", "balanced", "performance"]
+        );
+    }
+
+    #[test]
+    fn only_fatal_usb_errors_drop_the_claimed_handle() {
+        assert!(invalidates_handle(rusb::Error::NoDevice));
+        assert!(invalidates_handle(rusb::Error::Io));
+        assert!(!invalidates_handle(rusb::Error::Timeout));
+        assert!(!invalidates_handle(rusb::Error::Pipe));
+        assert!(UsbKeyboard::default().claimed.is_none());
+    }
+
+    #[test]
+    fn transient_usb_errors_are_retried_with_backoff() {
+        let mut script = vec![Err(rusb::Error::Timeout), Err(rusb::Error::Pipe), Ok(7)];
+        script.reverse();
+        let mut waits = Vec::new();
+        let result = retry_usb(
+            &USB_RETRY_DELAYS,
+            || script.pop().unwrap(),
+            |d| waits.push(d),
+        );
+        assert_eq!(result, Ok(7));
+        assert_eq!(waits, USB_RETRY_DELAYS[..2]);
+
+        let mut calls = 0;
+        let result: Result<(), _> = retry_usb(
+            &USB_RETRY_DELAYS,
+            || {
+                calls += 1;
+                Err(rusb::Error::Busy)
+            },
+            |_| {},
+        );
+        assert_eq!(result, Err((rusb::Error::Busy, 4)));
+        assert_eq!(calls, 4);
+
+        for fatal in [rusb::Error::NoDevice, rusb::Error::Access] {
+            let result: Result<(), _> = retry_usb(
+                &USB_RETRY_DELAYS,
+                || Err(fatal),
+                |_| panic!("{fatal} must not be retried"),
+            );
+            assert_eq!(result, Err((fatal, 1)));
+        }
+    }
+
+    #[test]
+    fn identical_rgb_requests_write_once() {
+        let settings = RgbSettings::from_config(&AppConfig::default().rgb);
+        let mut cache = RgbCache::default();
+        let mut writes = 0;
+        let mut write = || {
+            writes += 1;
+            Ok("applied".to_string())
+        };
+
+        assert_eq!(cache.apply(&settings, &mut write).unwrap(), "applied");
+        assert!(cache
+            .apply(&settings, &mut write)
+            .unwrap()
+            .contains("already applied"));
+        cache.invalidate();
+        cache.apply(&settings, &mut write).unwrap();
+        let brighter = RgbSettings {
+            brightness: settings.brightness.saturating_sub(10),
+            ..settings
+        };
+        cache.apply(&brighter, &mut write).unwrap();
+        assert!(cache.apply(&settings, || bail!("unplugged")).is_err());
+        assert_eq!(writes, 3);
+    }
+
+    #[test]
+    fn fade_sends_every_frame_unless_a_newer_request_cuts_it_short() {
+        let from = RgbSettings::from_config(&AppConfig::default().rgb);
+        let to = RgbSettings {
+            custom: Some(rgb_protocol::Rgb { r: 0, g: 0, b: 255 }),
+            ..from
+        };
+        let frames = rgb_protocol::fade_frames(&from, &to).unwrap();
+        let mut written = Vec::new();
+        let mut slept = Duration::ZERO;
+
+        let sent = play_fade(
+            &frames,
+            Duration::from_millis(50),
+            |frame| {
+                written.push(frame.color_rgb());
+                Ok(())
+            },
+            || false,
+            |interval| slept += interval,
+        );
+        assert_eq!(sent, frames.len());
+        assert_eq!(written.len(), frames.len());
+        assert_eq!(slept, Duration::from_millis(50) * frames.len() as u32);
+
+        // A newer lighting request after the third frame wins.
+        written.clear();
+        let mut checks = 0;
+        let sent = play_fade(
+            &frames,
+            Duration::ZERO,
+            |frame| {
+                written.push(frame.color_rgb());
+                Ok(())
+            },
+            || {
+                checks += 1;
+                checks > 3
+            },
+            |_| {},
+        );
+        assert_eq!(sent, 3);
+        let first_three: Vec<_> = frames[..3].iter().map(RgbSettings::color_rgb).collect();
+        assert_eq!(written, first_three);
+
+        // A failed write stops the fade; the final write reports the error.
+        let failing = |_: &RgbSettings| bail!("unplugged");
+        let sent = play_fade(&frames, Duration::ZERO, failing, || false, |_| {});
+        assert_eq!(sent, 0);
+    }
+
+    #[test]
+    fn only_lighting_requests_interrupt_a_fade() {
+        let (tx, rx) = mpsc::channel();
+        let mut requests = Requests {
+            rx,
+            queued: VecDeque::new(),
+        };
+        let settings = RgbSettings::from_config(&AppConfig::default().rgb);
+
+        tx.send(HardwareRequest::Snapshot).unwrap();
+        assert!(!requests.rgb_waiting());
+        tx.send(HardwareRequest::ApplyRgb(settings)).unwrap();
+        assert!(requests.rgb_waiting());
+
+        // Requests read while peeking keep their order.
+        assert!(matches!(requests.next(), Some(HardwareRequest::Snapshot)));
+        assert!(requests.next().is_some_and(|request| request.changes_rgb()));
+        drop(tx);
+        assert!(requests.next().is_none());
+    }
+
+    #[test]
+    fn thermal_lighting_is_retinted_only_when_the_colour_moves() {
+        let thermal = RgbSettings {
+            effect_idx: rgb_protocol::THERMAL_EFFECT_INDEX,
+            ..RgbSettings::from_config(&AppConfig::default().rgb)
+        };
+        let mut cache = RgbCache::default();
+        let write = || Ok("applied".to_string());
+
+        cache.thermal = Some(thermal);
+        cache.apply(&thermal.tinted(Some(70)), write).unwrap();
+        cache.hottest = Some(70);
+        assert_eq!(cache.due_tint(), None);
+        // A one-degree wobble is not worth a write.
+        cache.hottest = Some(71);
+        assert_eq!(cache.due_tint(), None);
+        cache.hottest = Some(85);
+        assert_eq!(cache.due_tint(), Some(thermal.tinted(Some(85))));
+
+        // Nothing is sent while the keyboard is gone.
+        cache.invalidate();
+        assert_eq!(cache.due_tint(), None);
+        cache.apply(&thermal.tinted(Some(85)), write).unwrap();
+        cache.thermal = None;
+        cache.hottest = Some(40);
+        assert_eq!(cache.due_tint(), None);
+    }
+
+    #[test]
+    fn dry_run_validates_without_writing() {
+        let fake = FakeSysfs::new("dry-run");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        fake.write(&format!("{PS_BASE}/usb_charging"), "10");
+
+        let message = apply_control(&capabilities, ControlId::UsbCharging, "20", true).unwrap();
+        assert!(message.starts_with(DRY_RUN_PREFIX), "{message}");
+        assert!(apply_control(&capabilities, ControlId::UsbCharging, "15", true).is_err());
+        assert_eq!(
+            read_control_raw(&capabilities, ControlId::UsbCharging).unwrap(),
+            "10"
+        );
+    }
+
+    #[test]
+    fn calibration_needs_ac_and_stops_when_unplugged() {
+        let fake = FakeSysfs::new("calibration");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        fake.write(&format!("{POWER_SUPPLY_BASE}/ACAD/type"), "Mains");
+        fake.write(&format!("{POWER_SUPPLY_BASE}/ACAD/online"), "0");
+        fake.write(&format!("{POWER_SUPPLY_BASE}/BAT1/type"), "Battery");
+        fake.write(&format!("{POWER_SUPPLY_BASE}/BAT1/capacity"), "64");
+        fake.write(&format!("{PS_BASE}/battery_calibration"), "0");
+
+        let power = read_power_status(&fake.path(POWER_SUPPLY_BASE));
+        assert_eq!(power.ac_online, Some(false));
+        assert_eq!(power.battery_percent, Some(64));
+        assert!(apply_control(&capabilities, ControlId::BatteryCalibration, "1", false).is_err());
+        assert_eq!(supervise_calibration(&capabilities, &power, false), None);
+
+        fake.write(&format!("{PS_BASE}/battery_calibration"), "1");
+        assert!(supervise_calibration(&capabilities, &power, false)
+            .unwrap()
+            .contains("stopped"));
+        assert_eq!(
+            read_control_raw(&capabilities, ControlId::BatteryCalibration).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn charge_threshold_replaces_the_limiter_switch() {
+        let fake = FakeSysfs::new("charge-limit");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        fake.write(&format!("{PS_BASE}/battery_limiter"), "1");
+        let legacy = read_control(&capabilities, ControlId::BatteryLimiter, &[]);
+        assert_eq!(legacy.kind, ControlKind::Toggle);
+        assert_eq!(legacy.display, "80% Limit");
+
+        let threshold = format!("{POWER_SUPPLY_BASE}/BAT1/charge_control_end_threshold");
+        fake.write(&threshold, "100");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        let limit = read_control(&capabilities, ControlId::BatteryLimiter, &[]);
+        assert!(matches!(limit.kind, ControlKind::Choice(ref choices) if choices.len() == 3));
+        assert_eq!(limit.display, "Full Charge");
+
+        write_control(&capabilities, ControlId::BatteryLimiter, "60").unwrap();
+        assert_eq!(fs::read_to_string(fake.path(&threshold)).unwrap(), "60");
+        assert!(write_control(&capabilities, ControlId::BatteryLimiter, "40").is_err());
+        assert!(write_control(&capabilities, ControlId::BatteryLimiter, "1").is_err());
+    }
+
+    #[test]
+    fn battery_status_skips_peripherals_and_derives_watts() {
+        let root = std::env::temp_dir().join(format!("arch-sense-power-{}", std::process::id()));
+        write_file(&root.join("ADP1/type"), "Mains");
+        write_file(&root.join("ADP1/online"), "1");
+        write_file(&root.join("BAT1/type"), "Battery");
+        write_file(&root.join("BAT1/capacity"), "81");
+        write_file(&root.join("BAT1/status"), "Charging");
+        write_file(&root.join("BAT1/current_now"), "-2000000");
+        write_file(&root.join("BAT1/voltage_now"), "16500000");
+        write_file(&root.join("0003:1532:0084.0004/type"), "Battery");
+        write_file(&root.join("0003:1532:0084.0004/scope"), "Device");
+        write_file(&root.join("0003:1532:0084.0004/capacity"), "20");
+
+        let power = read_power_status(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(power.ac_online, Some(true));
+        assert_eq!(power.battery_percent, Some(81));
+        assert_eq!(power.battery_status.as_deref(), Some("Charging"));
+        assert_eq!(power.battery_watts, Some(33.0));
+    }
+
+    #[test]
+    fn write_errors_name_the_attribute() {
+        let fake = FakeSysfs::new("write-error");
+        let capabilities = Capabilities::probe(FanControl::Percent, true);
+        // A directory refuses writes even for root, unlike a 0444 file.
+        fs::create_dir_all(fake.path(&format!("{PS_BASE}/lcd_override"))).unwrap();
+
+        let error = write_control(&capabilities, ControlId::LcdOverride, "1").unwrap_err();
+        assert!(error.to_string().contains("lcd_override"), "{error}");
     }
 }
@@ -0,0 +1,204 @@
+//! Keyboard RGB transport over the kernel hidraw interface.
+//!
+//! Used when libusb cannot detach `usbhid` or claim the lighting interface.
+//! The same 8-byte reports are sent as HID feature reports with report ID 0,
+//! and zone frames as an output report, so the kernel driver stays bound the
+//! whole time.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::constants::sysfs;
+use crate::permissions::setup_hint;
+use crate::rgb_protocol::{probed_label, KeyboardModel, Transfer};
+
+const HIDRAW_CLASS_DIR: &str = "/sys/class/hidraw";
+const USB_BUS: u16 = 0x0003;
+
+/// `HIDIOCSFEATURE(len)`: `_IOC(_IOC_READ | _IOC_WRITE, 'H', 0x06, len)`.
+const fn hidiocsfeature(len: usize) -> u64 {
+    (3 << 30) | ((len as u64) << 16) | ((b'H' as u64) << 8) | 0x06
+}
+
+/// Sends every transfer and returns the node and keyboard used.
+pub(crate) fn send_transfers(
+    commands: &[Transfer],
+    keyboards: &[KeyboardModel],
+) -> Result<(PathBuf, KeyboardModel)> {
+    let (node, model) = find_keyboard_node(Path::new(&sysfs(HIDRAW_CLASS_DIR)), keyboards)
+        .with_context(|| format!("no hidraw node found (probed {})", probed_label(keyboards)))?;
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&node)
+        .with_context(|| format!("failed to open {}; {}", node.display(), setup_hint()))?;
+
+    for command in commands {
+        let command = match command {
+            Transfer::Report(command) => command,
+            Transfer::Frame(frame) => {
+                let mut report = Vec::with_capacity(frame.len() + 1);
+                report.push(0);
+                report.extend_from_slice(frame);
+                (&file)
+                    .write_all(&report)
+                    .context("hidraw output report failed for the zone frame")?;
+                continue;
+            }
+        };
+        let mut report = [0u8; 9];
+        report[1..].copy_from_slice(command);
+
+        // SAFETY: the buffer outlives the call and its length is encoded in the request.
+        let result = unsafe {
+            libc::ioctl(
+                file.as_raw_fd(),
+                hidiocsfeature(report.len()) as _,
+                report.as_mut_ptr(),
+            )
+        };
+        if result < 0 {
+            bail!(
+                "hidraw feature report failed for packet {command:02X?}: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    Ok((node, model))
+}
+
+/// Walks `<class_dir>/hidraw*/device/uevent` and returns the `/dev` node of
+/// the lighting interface of the first keyboard in `keyboards` that is present.
+pub(crate) fn find_keyboard_node(
+    class_dir: &Path,
+    keyboards: &[KeyboardModel],
+) -> Option<(PathBuf, KeyboardModel)> {
+    let mut names: Vec<String> = fs::read_dir(class_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("hidraw"))
+        .collect();
+    names.sort();
+
+    let uevents: Vec<(String, String)> = names
+        .into_iter()
+        .filter_map(|name| {
+            let uevent = fs::read_to_string(class_dir.join(&name).join("device/uevent")).ok()?;
+            Some((name, uevent))
+        })
+        .collect();
+
+    keyboards.iter().find_map(|model| {
+        uevents
+            .iter()
+            .find(|(_, uevent)| uevent_matches_keyboard(uevent, model))
+            .map(|(name, _)| (Path::new("/dev").join(name), *model))
+    })
+}
+
+fn uevent_matches_keyboard(uevent: &str, model: &KeyboardModel) -> bool {
+    let Some((bus, vid, pid)) = parse_hid_id(uevent) else {
+        return false;
+    };
+
+    // Boards without HID_PHYS expose a single interface, so accept them.
+    bus == USB_BUS
+        && vid == model.vid
+        && pid == model.pid
+        && parse_interface(uevent).is_none_or(|iface| iface == model.interface)
+}
+
+/// Parses `HID_ID=0003:000004F2:00000117` into `(bus, vid, pid)`.
+fn parse_hid_id(uevent: &str) -> Option<(u16, u16, u16)> {
+    let value = uevent_value(uevent, "HID_ID")?;
+    let mut parts = value.split(':');
+    let bus = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let vid = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let pid = u32::from_str_radix(parts.next()?, 16).ok()?;
+
+    Some((
+        u16::try_from(bus).ok()?,
+        u16::try_from(vid).ok()?,
+        u16::try_from(pid).ok()?,
+    ))
+}
+
+/// Parses the interface number from `HID_PHYS=usb-0000:00:14.0-8/input3`.
+fn parse_interface(uevent: &str) -> Option<u8> {
+    uevent_value(uevent, "HID_PHYS")?
+        .rsplit_once("/input")?
+        .1
+        .parse()
+        .ok()
+}
+
+fn uevent_value<'a>(uevent: &'a str, key: &str) -> Option<&'a str> {
+    uevent.lines().find_map(|line| {
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix('='))
+            .map(str::trim)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb_protocol::SUPPORTED_KEYBOARDS;
+
+    const KEYBOARD: KeyboardModel = SUPPORTED_KEYBOARDS[0];
+
+    const KEYBOARD_UEVENT: &str = "DRIVER=hid-generic\n\
+        HID_ID=0003:000004F2:00000117\n\
+        HID_NAME=Chicony USB Keyboard\n\
+        HID_PHYS=usb-0000:00:14.0-8/input3\n\
+        HID_UNIQ=\n\
+        MODALIAS=hid:b0003g0001v000004F2p00000117\n";
+
+    #[test]
+    fn hid_id_and_interface_are_parsed_from_uevent() {
+        assert_eq!(
+            parse_hid_id(KEYBOARD_UEVENT),
+            Some((USB_BUS, KEYBOARD.vid, KEYBOARD.pid))
+        );
+        assert_eq!(parse_interface(KEYBOARD_UEVENT), Some(3));
+        assert!(uevent_matches_keyboard(KEYBOARD_UEVENT, &KEYBOARD));
+    }
+
+    #[test]
+    fn other_devices_and_interfaces_are_rejected() {
+        let other_interface = KEYBOARD_UEVENT.replace("/input3", "/input0");
+        let other_device = KEYBOARD_UEVENT.replace("00000117", "00000118");
+        let bluetooth = KEYBOARD_UEVENT.replace("HID_ID=0003", "HID_ID=0005");
+
+        assert!(!uevent_matches_keyboard(&other_interface, &KEYBOARD));
+        assert!(!uevent_matches_keyboard(&other_device, &KEYBOARD));
+        assert!(!uevent_matches_keyboard(&bluetooth, &KEYBOARD));
+        assert!(!uevent_matches_keyboard("HID_ID=garbage\n", &KEYBOARD));
+        assert!(!uevent_matches_keyboard("", &KEYBOARD));
+    }
+
+    #[test]
+    fn keyboard_node_is_found_in_class_directory() {
+        let root = std::env::temp_dir().join(format!("arch-sense-hidraw-{}", std::process::id()));
+        for (name, uevent) in [
+            ("hidraw0", KEYBOARD_UEVENT.replace("/input3", "/input0")),
+            ("hidraw2", KEYBOARD_UEVENT.to_string()),
+        ] {
+            let device = root.join(name).join("device");
+            fs::create_dir_all(&device).unwrap();
+            fs::write(device.join("uevent"), uevent).unwrap();
+        }
+
+        let node = find_keyboard_node(&root, SUPPORTED_KEYBOARDS);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(node, Some((PathBuf::from("/dev/hidraw2"), KEYBOARD)));
+    }
+}
@@ -0,0 +1,282 @@
+//! Global hotkeys.
+//!
+//! Keys listed in `hotkeys.keys` are read from the matching input devices,
+//! so they work in any window, e.g. the PredatorSense key that acer-wmi
+//! reports as a `KEY_PROG` code. Devices are opened shared, never grabbed,
+//! so every key still reaches the desktop too. Devices that go away are
+//! dropped and new ones are picked up on the next scan.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+use crate::config::HotkeysConfig;
+use crate::input;
+
+/// How often the device list is re-read for keyboards that came or went.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+const EV_KEY: u16 = 1;
+/// `input_event.value` of a key press; 0 is a release and 2 a repeat.
+const KEY_PRESSED: i32 = 1;
+
+/// Names accepted in `hotkeys.keys`, from linux/input-event-codes.h. Any
+/// other key can be given by its number.
+const KEY_CODES: &[(&str, u16)] = &[
+    ("KEY_F1", 59),
+    ("KEY_F2", 60),
+    ("KEY_F3", 61),
+    ("KEY_F4", 62),
+    ("KEY_F5", 63),
+    ("KEY_F6", 64),
+    ("KEY_F7", 65),
+    ("KEY_F8", 66),
+    ("KEY_F9", 67),
+    ("KEY_F10", 68),
+    ("KEY_F11", 87),
+    ("KEY_F12", 88),
+    ("KEY_PROG1", 148),
+    ("KEY_PROG2", 149),
+    ("KEY_PROG3", 202),
+    ("KEY_PROG4", 203),
+    ("KEY_KBDILLUMTOGGLE", 228),
+    ("KEY_KBDILLUMDOWN", 229),
+    ("KEY_KBDILLUMUP", 230),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HotkeyAction {
+    /// Both fans from Auto to full speed, or back to Auto.
+    CycleFanMode,
+    /// The same as the RGB panel's power key.
+    ToggleRgb,
+}
+
+impl HotkeyAction {
+    const ALL: [Self; 2] = [Self::CycleFanMode, Self::ToggleRgb];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::CycleFanMode => "cycle_fan_mode",
+            Self::ToggleRgb => "toggle_rgb",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.name() == name.trim())
+    }
+}
+
+/// A matched key press: the key as named in the config, and its action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Hotkey {
+    pub(crate) key: String,
+    pub(crate) action: HotkeyAction,
+}
+
+fn key_code(name: &str) -> Option<u16> {
+    let name = name.trim();
+    if let Ok(code) = name.parse() {
+        return Some(code);
+    }
+    let upper = name.to_ascii_uppercase();
+    let upper = if upper.starts_with("KEY_") {
+        upper
+    } else {
+        format!("KEY_{upper}")
+    };
+    KEY_CODES
+        .iter()
+        .find(|(known, _)| *known == upper)
+        .map(|(_, code)| *code)
+}
+
+/// The configured keys by code, plus one warning per entry that was skipped.
+fn parse_keys(keys: &BTreeMap<String, String>) -> (Vec<(u16, Hotkey)>, Vec<String>) {
+    let mut parsed = Vec::new();
+    let mut warnings = Vec::new();
+    for (key, action) in keys {
+        let Some(code) = key_code(key) else {
+            warnings.push(format!("unknown key '{key}'"));
+            continue;
+        };
+        let Some(action) = HotkeyAction::parse(action) else {
+            warnings.push(format!("unknown action '{action}' for {key}"));
+            continue;
+        };
+        if parsed.iter().any(|(known, _)| *known == code) {
+            warnings.push(format!("{key} is listed twice"));
+            continue;
+        }
+        let key = key.trim().to_string();
+        parsed.push((code, Hotkey { key, action }));
+    }
+    (parsed, warnings)
+}
+
+/// Codes of the keys pressed in a buffer of whole `input_event`s. Releases,
+/// auto-repeat and non-key events are left out.
+fn key_presses(buffer: &[u8]) -> Vec<u16> {
+    buffer
+        .chunks_exact(size_of::<libc::input_event>())
+        .map(|chunk| {
+            // SAFETY: the chunk is exactly one input_event long, and every
+            // bit pattern is a valid input_event.
+            unsafe { chunk.as_ptr().cast::<libc::input_event>().read_unaligned() }
+        })
+        .filter(|event| event.type_ == EV_KEY && event.value == KEY_PRESSED)
+        .map(|event| event.code)
+        .collect()
+}
+
+pub(crate) struct HotkeyListener {
+    keys: Vec<(u16, Hotkey)>,
+    names: Vec<String>,
+    devices: Vec<(String, File)>,
+    next_scan: Instant,
+}
+
+impl HotkeyListener {
+    /// Parses the key map; the warning lists skipped entries.
+    pub(crate) fn new(config: &HotkeysConfig, now: Instant) -> (Self, Option<String>) {
+        let (keys, warnings) = parse_keys(&config.keys);
+        let warning =
+            (!warnings.is_empty()).then(|| format!("Ignored hotkeys: {}", warnings.join("; ")));
+
+        let listener = Self {
+            keys,
+            names: config.devices.clone(),
+            devices: Vec::new(),
+            next_scan: now,
+        };
+        (listener, warning)
+    }
+
+    /// Reads pending input and returns the configured keys pressed since
+    /// the last call.
+    pub(crate) fn poll(&mut self, now: Instant) -> Vec<Hotkey> {
+        if self.keys.is_empty() {
+            return Vec::new();
+        }
+        if now >= self.next_scan {
+            self.rescan();
+            self.next_scan = now + RESCAN_INTERVAL;
+        }
+
+        let mut pressed = Vec::new();
+        self.devices.retain_mut(|(_, device)| {
+            input::drain(device, |events| pressed.extend(key_presses(events)))
+        });
+        self.hotkeys(&pressed)
+    }
+
+    fn hotkeys(&self, pressed: &[u16]) -> Vec<Hotkey> {
+        pressed
+            .iter()
+            .filter_map(|code| {
+                self.keys
+                    .iter()
+                    .find(|(known, _)| known == code)
+                    .map(|(_, hotkey)| hotkey.clone())
+            })
+            .collect()
+    }
+
+    /// Opens matching devices that are not open yet.
+    fn rescan(&mut self) {
+        let nodes = input::event_nodes(&input::device_table(), |device| {
+            self.names
+                .iter()
+                .any(|name| device.name.contains(name.as_str()))
+        });
+        for node in nodes {
+            if self.devices.iter().any(|(open, _)| *open == node) {
+                continue;
+            }
+            if let Some(device) = input::open(&node) {
+                self.devices.push((node, device));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(type_: u16, code: u16, value: i32) -> Vec<u8> {
+        let event = libc::input_event {
+            time: libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            type_,
+            code,
+            value,
+        };
+        // SAFETY: input_event is plain data; this views it as its bytes.
+        unsafe {
+            std::slice::from_raw_parts(
+                (&event as *const libc::input_event).cast::<u8>(),
+                size_of::<libc::input_event>(),
+            )
+        }
+        .to_vec()
+    }
+
+    #[test]
+    fn key_map_accepts_names_and_codes_and_reports_the_rest() {
+        let keys = BTreeMap::from([
+            ("KEY_PROG3".to_string(), "cycle_fan_mode".to_string()),
+            ("kbdillumtoggle".to_string(), "toggle_rgb".to_string()),
+            ("425".to_string(), "toggle_rgb".to_string()),
+            ("KEY_NOPE".to_string(), "toggle_rgb".to_string()),
+            ("KEY_F9".to_string(), "self_destruct".to_string()),
+        ]);
+        let (parsed, warnings) = parse_keys(&keys);
+
+        let codes: Vec<u16> = parsed.iter().map(|(code, _)| *code).collect();
+        assert_eq!(codes, [425, 202, 228]);
+        assert_eq!(parsed[1].1.action, HotkeyAction::CycleFanMode);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings.iter().any(|warning| warning.contains("KEY_NOPE")));
+        assert!(warnings
+            .iter()
+            .any(|warning| warning.contains("self_destruct")));
+    }
+
+    #[test]
+    fn only_presses_of_mapped_keys_are_dispatched() {
+        let config = HotkeysConfig {
+            keys: BTreeMap::from([
+                ("KEY_PROG3".to_string(), "cycle_fan_mode".to_string()),
+                ("KEY_KBDILLUMTOGGLE".to_string(), "toggle_rgb".to_string()),
+            ]),
+            ..HotkeysConfig::default()
+        };
+        let (listener, warning) = HotkeyListener::new(&config, Instant::now());
+        assert!(warning.is_none());
+
+        // Press, repeat and release of PROG3, a sync event, an unmapped
+        // key, then the lighting key.
+        let buffer = [
+            event(EV_KEY, 202, 1),
+            event(EV_KEY, 202, 2),
+            event(EV_KEY, 202, 0),
+            event(0, 0, 0),
+            event(EV_KEY, 30, 1),
+            event(EV_KEY, 228, 1),
+        ]
+        .concat();
+        let hotkeys = listener.hotkeys(&key_presses(&buffer));
+
+        let actions: Vec<HotkeyAction> = hotkeys.iter().map(|hotkey| hotkey.action).collect();
+        assert_eq!(
+            actions,
+            [HotkeyAction::CycleFanMode, HotkeyAction::ToggleRgb]
+        );
+        assert_eq!(hotkeys[0].key, "KEY_PROG3");
+    }
+}
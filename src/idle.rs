@@ -0,0 +1,138 @@
+//! Software keyboard idle timeout.
+//!
+//! The firmware's `backlight_timeout` only knows "off after 30 s". With
+//! `rgb_idle_minutes` set, the lighting is switched off after that many
+//! minutes without input and restored on the next key press. Input is read
+//! from the keyboard event devices, so it is seen in any window, plus key
+//! presses in the TUI itself.
+
+use std::fs::File;
+use std::time::{Duration, Instant};
+
+use crate::input;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum IdleChange {
+    /// The timeout passed: switch the lighting off.
+    Blank,
+    /// Input after a blank: restore the lighting.
+    Wake,
+}
+
+pub(crate) struct IdleWatcher {
+    timeout: Option<Duration>,
+    devices: Vec<File>,
+    last_input: Instant,
+    blanked: bool,
+}
+
+impl IdleWatcher {
+    /// Opens the keyboard event devices when a timeout is configured. The
+    /// warning says when none could be read.
+    pub(crate) fn new(minutes: Option<u16>, now: Instant) -> (Self, Option<String>) {
+        let timeout = minutes
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+        let devices = if timeout.is_some() {
+            open_keyboards()
+        } else {
+            Vec::new()
+        };
+        let warning = (timeout.is_some() && devices.is_empty()).then(|| {
+            "RGB idle timeout cannot read /dev/input; only keys pressed here count as activity"
+                .to_string()
+        });
+
+        let watcher = Self {
+            timeout,
+            devices,
+            last_input: now,
+            blanked: false,
+        };
+        (watcher, warning)
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.timeout.is_some()
+    }
+
+    pub(crate) fn blanked(&self) -> bool {
+        self.blanked
+    }
+
+    /// Records input seen by the TUI itself.
+    pub(crate) fn input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    /// Drains pending device input and reports when the lighting should
+    /// change. While `hardware_timeout` is on the firmware already blanks
+    /// the keyboard, so no software blank is started on top of it.
+    pub(crate) fn poll(&mut self, now: Instant, hardware_timeout: bool) -> Option<IdleChange> {
+        if !self.enabled() {
+            return None;
+        }
+        // Every device is drained, not just the first one with input.
+        let mut seen = false;
+        self.devices
+            .retain_mut(|device| input::drain(device, |_| seen = true));
+        if seen {
+            self.last_input = now;
+        }
+        self.step(now, hardware_timeout)
+    }
+
+    fn step(&mut self, now: Instant, hardware_timeout: bool) -> Option<IdleChange> {
+        let timeout = self.timeout?;
+        let idle = now.saturating_duration_since(self.last_input) >= timeout;
+
+        if self.blanked && !idle {
+            self.blanked = false;
+            Some(IdleChange::Wake)
+        } else if !self.blanked && idle && !hardware_timeout {
+            self.blanked = true;
+            Some(IdleChange::Blank)
+        } else {
+            None
+        }
+    }
+}
+
+fn open_keyboards() -> Vec<File> {
+    input::event_nodes(&input::device_table(), |device| device.has_handler("kbd"))
+        .iter()
+        .filter_map(|node| input::open(node))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blanks_after_the_timeout_and_wakes_on_input() {
+        let start = Instant::now();
+        let (mut watcher, _) = IdleWatcher::new(Some(2), start);
+        let minute = Duration::from_secs(60);
+
+        assert_eq!(watcher.step(start + minute, false), None);
+        // The firmware timeout takes precedence while it is on.
+        assert_eq!(watcher.step(start + 3 * minute, true), None);
+        assert_eq!(
+            watcher.step(start + 3 * minute, false),
+            Some(IdleChange::Blank)
+        );
+        assert_eq!(watcher.step(start + 4 * minute, false), None);
+
+        watcher.input(start + 5 * minute);
+        assert_eq!(
+            watcher.step(start + 5 * minute, false),
+            Some(IdleChange::Wake)
+        );
+        assert!(!watcher.blanked());
+
+        let (mut disabled, warning) = IdleWatcher::new(Some(0), start);
+        assert_eq!(disabled.step(start + 60 * minute, false), None);
+        assert!(warning.is_none());
+    }
+}
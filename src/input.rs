@@ -0,0 +1,107 @@
+//! Shared reading of `/dev/input` event devices.
+//!
+//! The idle timeout and the global hotkeys both watch keys pressed in any
+//! window. Devices are picked from `/proc/bus/input/devices`, opened
+//! non-blocking and never grabbed, so input still reaches the desktop.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read};
+use std::mem::size_of;
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::constants::sysfs;
+
+const INPUT_DEVICES: &str = "/proc/bus/input/devices";
+
+/// One entry of the device table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct InputDevice<'a> {
+    pub(crate) name: &'a str,
+    handlers: &'a str,
+}
+
+impl InputDevice<'_> {
+    pub(crate) fn has_handler(&self, handler: &str) -> bool {
+        self.handlers
+            .split_whitespace()
+            .any(|known| known == handler)
+    }
+
+    fn event_node(&self) -> Option<&str> {
+        self.handlers
+            .split_whitespace()
+            .find(|handler| handler.starts_with("event"))
+    }
+}
+
+/// The kernel's device table; empty when it cannot be read.
+pub(crate) fn device_table() -> String {
+    fs::read_to_string(sysfs(INPUT_DEVICES)).unwrap_or_default()
+}
+
+/// `eventN` nodes of the devices in `table` that `wanted` picks.
+pub(crate) fn event_nodes(table: &str, wanted: impl Fn(&InputDevice) -> bool) -> Vec<String> {
+    table
+        .split("\n\n")
+        .filter_map(|block| {
+            let field = |prefix| block.lines().find_map(|line| line.strip_prefix(prefix));
+            Some(InputDevice {
+                name: field("N: Name=").unwrap_or_default().trim_matches('"'),
+                handlers: field("H: Handlers=")?,
+            })
+        })
+        .filter(|device| wanted(device))
+        .filter_map(|device| device.event_node().map(str::to_string))
+        .collect()
+}
+
+/// Opens `/dev/input/<node>` for shared, non-blocking reads.
+pub(crate) fn open(node: &str) -> Option<File> {
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(sysfs(&format!("/dev/input/{node}")))
+        .ok()
+}
+
+/// Hands everything queued on a non-blocking device to `on_read`, in whole
+/// `input_event`s. False once the device is gone.
+pub(crate) fn drain(device: &mut File, mut on_read: impl FnMut(&[u8])) -> bool {
+    let mut buffer = [0u8; 64 * size_of::<libc::input_event>()];
+    loop {
+        match device.read(&mut buffer) {
+            Ok(0) => return true,
+            Ok(read) => on_read(&buffer[..read]),
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return error.kind() == ErrorKind::WouldBlock,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn devices_are_picked_by_name_or_handler() {
+        let table = "I: Bus=0019 Vendor=0000 Product=0000 Version=0000\n\
+                     N: Name=\"Acer WMI hotkeys\"\n\
+                     H: Handlers=rfkill kbd event6 \n\
+                     \n\
+                     N: Name=\"AT Translated Set 2 keyboard\"\n\
+                     H: Handlers=sysrq kbd leds event3 \n\
+                     \n\
+                     N: Name=\"SYNA7DB5:01 06CB:CEB3 Touchpad\"\n\
+                     H: Handlers=mouse1 event9 \n\
+                     \n\
+                     N: Name=\"Chicony USB Keyboard\"\n\
+                     H: Handlers=sysrq kbd event12 leds\n";
+
+        let keyboards = event_nodes(table, |device| device.has_handler("kbd"));
+        assert_eq!(keyboards, ["event6", "event3", "event12"]);
+
+        let by_name = event_nodes(table, |device| device.name.contains("WMI"));
+        assert_eq!(by_name, ["event6"]);
+        assert!(event_nodes(table, |device| device.name == "Acer").is_empty());
+    }
+}
@@ -0,0 +1,538 @@
+//! Key bindings for the TUI.
+//!
+//! Every binding lives in one table, so key handling and the help overlay
+//! always agree on which key does what. The `keybindings` section of the
+//! config replaces the default keys of individual actions.
+
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    PageUp,
+    PageDown,
+    Filter,
+    NextPanel,
+    PreviousPanel,
+    Apply,
+    Edit,
+    HexColor,
+    ColorPicker,
+    Zones,
+    Direction,
+    Power,
+    AutoApply,
+    Refresh,
+    ExportHistory,
+    Cancel,
+    Profiles,
+    Log,
+    Help,
+    Quit,
+}
+
+impl Action {
+    pub(crate) const ALL: [Self; 26] = [
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+        Self::Top,
+        Self::Bottom,
+        Self::PageUp,
+        Self::PageDown,
+        Self::Filter,
+        Self::NextPanel,
+        Self::PreviousPanel,
+        Self::Apply,
+        Self::Edit,
+        Self::HexColor,
+        Self::ColorPicker,
+        Self::Zones,
+        Self::Direction,
+        Self::Power,
+        Self::AutoApply,
+        Self::Refresh,
+        Self::ExportHistory,
+        Self::Cancel,
+        Self::Profiles,
+        Self::Log,
+        Self::Help,
+        Self::Quit,
+    ];
+
+    /// Moves the selection without changing anything.
+    pub(crate) fn is_navigation(self) -> bool {
+        self.section() == HelpSection::Navigation && !matches!(self, Self::Left | Self::Right)
+    }
+
+    /// Name used in the config's `keybindings` section.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Up => "nav_up",
+            Self::Down => "nav_down",
+            Self::Left => "cycle_left",
+            Self::Right => "cycle_right",
+            Self::Top => "nav_top",
+            Self::Bottom => "nav_bottom",
+            Self::PageUp => "page_up",
+            Self::PageDown => "page_down",
+            Self::Filter => "filter",
+            Self::NextPanel => "next_panel",
+            Self::PreviousPanel => "previous_panel",
+            Self::Apply => "apply",
+            Self::Edit => "edit",
+            Self::HexColor => "hex_color",
+            Self::ColorPicker => "color_picker",
+            Self::Zones => "zones",
+            Self::Direction => "direction",
+            Self::Power => "power",
+            Self::AutoApply => "auto_apply",
+            Self::Refresh => "refresh",
+            Self::ExportHistory => "export_history",
+            Self::Cancel => "cancel",
+            Self::Profiles => "profiles",
+            Self::Log => "log",
+            Self::Help => "help",
+            Self::Quit => "quit",
+        }
+    }
+
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Self::Up => "Select previous item",
+            Self::Down => "Select next item",
+            Self::Left => "Previous value",
+            Self::Right => "Next value",
+            Self::Top => "First control (also gg)",
+            Self::Bottom => "Last control",
+            Self::PageUp => "Move up a page of controls",
+            Self::PageDown => "Move down a page of controls",
+            Self::Filter => "Filter controls by name or description",
+            Self::NextPanel => "Focus next panel",
+            Self::PreviousPanel => "Focus previous panel",
+            Self::Apply => "Apply or toggle the selection",
+            Self::Edit => "Type a value (or start with a digit)",
+            Self::HexColor => "Type a hex keyboard colour (Color row)",
+            Self::ColorPicker => "Pick a colour: ←→ hue, ↑↓ value, Shift saturation",
+            Self::Zones => "Toggle per-zone keyboard colours",
+            Self::Direction => "Cycle the direction of Wave and other directional effects",
+            Self::Power => "Switch keyboard lighting off, or back to the previous effect",
+            Self::AutoApply => "Toggle live RGB preview (auto-apply)",
+            Self::Refresh => "Refresh hardware state",
+            Self::ExportHistory => "Save sensor history as CSV",
+            Self::Cancel => "Cancel pending change",
+            Self::Profiles => "Open saved profiles (Ctrl+1..9 applies one)",
+            Self::Log => "Show or hide the status log (PageUp/PageDown scroll)",
+            Self::Help => "Show or hide this help",
+            Self::Quit => "Quit",
+        }
+    }
+
+    pub(crate) fn section(self) -> HelpSection {
+        match self {
+            Self::Up
+            | Self::Down
+            | Self::Left
+            | Self::Right
+            | Self::Top
+            | Self::Bottom
+            | Self::PageUp
+            | Self::PageDown
+            | Self::Filter => HelpSection::Navigation,
+            Self::NextPanel | Self::PreviousPanel => HelpSection::Panels,
+            Self::Apply
+            | Self::Edit
+            | Self::HexColor
+            | Self::ColorPicker
+            | Self::Zones
+            | Self::Direction
+            | Self::Power
+            | Self::AutoApply
+            | Self::Refresh
+            | Self::ExportHistory
+            | Self::Cancel
+            | Self::Profiles => HelpSection::Changes,
+            Self::Log | Self::Help | Self::Quit => HelpSection::System,
+        }
+    }
+
+    fn default_keys(self) -> Vec<KeyBinding> {
+        let keys: &[KeyCode] = match self {
+            Self::Up => &[KeyCode::Up, KeyCode::Char('k')],
+            Self::Down => &[KeyCode::Down, KeyCode::Char('j')],
+            Self::Left => &[KeyCode::Left, KeyCode::Char('h')],
+            Self::Right => &[KeyCode::Right, KeyCode::Char('l')],
+            Self::Top => &[KeyCode::Home],
+            Self::Bottom => &[KeyCode::End, KeyCode::Char('G')],
+            Self::PageUp => &[KeyCode::PageUp],
+            Self::PageDown => &[KeyCode::PageDown],
+            Self::Filter => &[KeyCode::Char('/')],
+            Self::NextPanel => &[KeyCode::Tab],
+            Self::PreviousPanel => &[KeyCode::BackTab],
+            Self::Apply => &[KeyCode::Enter, KeyCode::Char(' ')],
+            Self::Edit => &[KeyCode::Char('i')],
+            Self::HexColor => &[KeyCode::Char('c')],
+            Self::ColorPicker => &[KeyCode::Char('v')],
+            Self::Zones => &[KeyCode::Char('z')],
+            Self::Direction => &[KeyCode::Char('d')],
+            Self::Power => &[KeyCode::Char('o')],
+            Self::AutoApply => &[KeyCode::Char('a')],
+            Self::Refresh => &[KeyCode::Char('r'), KeyCode::Char('R')],
+            Self::ExportHistory => &[KeyCode::Char('E')],
+            Self::Cancel => &[KeyCode::Esc],
+            Self::Profiles => &[KeyCode::Char('p'), KeyCode::Char('P')],
+            Self::Log => &[KeyCode::Char('L')],
+            Self::Help => &[KeyCode::Char('?'), KeyCode::F(10)],
+            Self::Quit => &[KeyCode::Char('q'), KeyCode::Char('Q')],
+        };
+
+        keys.iter().map(|&code| KeyBinding::new(code)).collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HelpSection {
+    Navigation,
+    Panels,
+    Changes,
+    System,
+}
+
+impl HelpSection {
+    pub(crate) const ALL: [Self; 4] = [Self::Navigation, Self::Panels, Self::Changes, Self::System];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Navigation => "Navigation",
+            Self::Panels => "Panels",
+            Self::Changes => "Changes",
+            Self::System => "System",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    /// Parses descriptors such as `"q"`, `"ctrl+c"`, `"F2"`, `"space"` or `"shift+tab"`.
+    /// Named keys are case-insensitive; single characters are taken as typed.
+    pub(crate) fn parse(descriptor: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = descriptor.split('+').map(str::trim).collect();
+        // "ctrl++" binds the plus key itself.
+        if descriptor.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let Some(key) = parts.pop().filter(|key| !key.is_empty()) else {
+            return Err(format!("'{descriptor}' has no key"));
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in parts {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("'{descriptor}' has unknown modifier '{modifier}'")),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=24) => KeyCode::F(n),
+                    _ => return Err(format!("'{descriptor}' has unknown key '{key}'")),
+                },
+            },
+        };
+
+        Ok(Self { code, modifiers })
+    }
+
+    /// Shift is ignored because terminals disagree on reporting it for `?`,
+    /// capitals and Shift+Tab; the key code already tells those apart.
+    fn matches(&self, key: &KeyEvent) -> bool {
+        let relevant = |modifiers: KeyModifiers| modifiers - KeyModifiers::SHIFT;
+        self.code == key.code && relevant(self.modifiers) == relevant(key.modifiers)
+    }
+
+    fn is_reserved(&self) -> bool {
+        self.modifiers.contains(KeyModifiers::CONTROL)
+            && matches!(self.code, KeyCode::Char('c' | '1'..='9'))
+    }
+
+    pub(crate) fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "Shift+Tab".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("F{n}"),
+            other => format!("{other:?}"),
+        };
+
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            format!("Ctrl+{key}")
+        } else if self.modifiers.contains(KeyModifiers::ALT) {
+            format!("Alt+{key}")
+        } else {
+            key
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct KeyMap {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|&action| (action, action.default_keys()))
+                .collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// Applies the config's `keybindings` over the defaults.
+    ///
+    /// Unknown actions, unparsable keys, Ctrl+C (always quit) and keys already
+    /// used by another action are skipped with a warning; an action left with
+    /// no usable keys keeps its defaults.
+    pub(crate) fn from_config(overrides: &BTreeMap<String, Vec<String>>) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut custom: Vec<(Action, Vec<KeyBinding>)> = Vec::new();
+
+        for (name, descriptors) in overrides {
+            let Some(action) = Action::ALL.into_iter().find(|action| action.name() == name) else {
+                warnings.push(format!("unknown keybinding action '{name}'"));
+                continue;
+            };
+
+            let keys = descriptors
+                .iter()
+                .filter_map(|descriptor| match KeyBinding::parse(descriptor) {
+                    Ok(binding) if binding.is_reserved() => {
+                        warnings.push(format!(
+                            "{name}: {} is reserved (Ctrl+C quits, Ctrl+1..9 apply profiles)",
+                            binding.label()
+                        ));
+                        None
+                    }
+                    Ok(binding) => Some(binding),
+                    Err(error) => {
+                        warnings.push(format!("{name}: {error}"));
+                        None
+                    }
+                })
+                .collect();
+            custom.push((action, keys));
+        }
+
+        let mut keymap = Self::default();
+        for (action, keys) in &custom {
+            keymap.set(*action, keys.clone());
+        }
+
+        // Drop configured keys that collide with any other action's keys.
+        for (action, keys) in &custom {
+            let accepted: Vec<KeyBinding> = keys
+                .iter()
+                .copied()
+                .filter(|binding| match keymap.owner_other_than(*action, binding) {
+                    Some(other) => {
+                        warnings.push(format!(
+                            "{}: '{}' is already bound to {}",
+                            action.name(),
+                            binding.label(),
+                            other.name()
+                        ));
+                        false
+                    }
+                    None => true,
+                })
+                .collect();
+            keymap.set(*action, accepted);
+        }
+
+        for action in Action::ALL {
+            if keymap.keys(action).is_empty() {
+                let defaults = action
+                    .default_keys()
+                    .into_iter()
+                    .filter(|binding| keymap.owner_other_than(action, binding).is_none())
+                    .collect();
+                keymap.set(action, defaults);
+            }
+        }
+
+        (keymap, warnings)
+    }
+
+    fn set(&mut self, action: Action, keys: Vec<KeyBinding>) {
+        if let Some((_, bound)) = self.bindings.iter_mut().find(|(bound, _)| *bound == action) {
+            *bound = keys;
+        }
+    }
+
+    fn owner_other_than(&self, action: Action, binding: &KeyBinding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(other, keys)| *other != action && keys.contains(binding))
+            .map(|(other, _)| *other)
+    }
+
+    pub(crate) fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|binding| binding.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    pub(crate) fn keys(&self, action: Action) -> &[KeyBinding] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// The first key for `action`, for compact hints like the footer.
+    pub(crate) fn primary_label(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map_or_else(String::new, KeyBinding::label)
+    }
+
+    /// Every key for `action`, joined for display, e.g. "↑ / k".
+    pub(crate) fn label(&self, action: Action) -> String {
+        self.keys(action)
+            .iter()
+            .map(KeyBinding::label)
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn default_keys_resolve_regardless_of_shift() {
+        let keymap = KeyMap::default();
+
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::Char('?'), KeyModifiers::SHIFT)),
+            Some(Action::Help)
+        );
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            Some(Action::PreviousPanel)
+        );
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::F(10), KeyModifiers::NONE)),
+            Some(Action::Help)
+        );
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::Char('q'), KeyModifiers::CONTROL)),
+            None
+        );
+        assert_eq!(keymap.label(Action::Up), "↑ / k");
+        assert!(Action::ALL
+            .iter()
+            .all(|&action| !keymap.keys(action).is_empty()));
+    }
+
+    #[test]
+    fn key_descriptors_are_parsed() {
+        let parsed = |descriptor| KeyBinding::parse(descriptor).map(|binding| binding.label());
+
+        assert_eq!(parsed("ctrl+q"), Ok("Ctrl+q".to_string()));
+        assert_eq!(parsed("F2"), Ok("F2".to_string()));
+        assert_eq!(parsed("space"), Ok("Space".to_string()));
+        assert_eq!(parsed("Shift+Tab"), Ok("Shift+Tab".to_string()));
+        assert_eq!(parsed("ctrl++"), Ok("Ctrl++".to_string()));
+        assert!(parsed("hyper+x").is_err());
+        assert!(parsed("f99").is_err());
+        assert!(parsed("").is_err());
+    }
+
+    #[test]
+    fn config_overrides_defaults_and_rejects_conflicts() {
+        let overrides = BTreeMap::from([
+            (
+                "nav_up".to_string(),
+                vec!["up".to_string(), "u".to_string()],
+            ),
+            ("nav_down".to_string(), vec!["k".to_string()]),
+            (
+                "quit".to_string(),
+                vec!["ctrl+c".to_string(), "h".to_string()],
+            ),
+            ("jump".to_string(), vec!["g".to_string()]),
+        ]);
+        let (keymap, warnings) = KeyMap::from_config(&overrides);
+
+        // 'k' is free once nav_up is rebound, so nav_down may take it.
+        assert_eq!(keymap.label(Action::Up), "↑ / u");
+        assert_eq!(keymap.label(Action::Down), "k");
+        // Both quit keys were rejected, so it falls back to q / Q.
+        assert_eq!(keymap.label(Action::Quit), "q / Q");
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::Char('u'), KeyModifiers::NONE)),
+            Some(Action::Up)
+        );
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+    }
+}
@@ -1,21 +1,48 @@
 pub mod app;
 pub mod cli;
+pub mod color_picker;
 pub mod commands;
 pub mod config;
+pub mod conflicts;
 pub mod constants;
+pub mod doctor;
+pub mod export;
 pub mod hardware;
+pub mod hidraw;
+pub mod hotkeys;
+pub mod idle;
+pub mod input;
+pub mod keymap;
 pub mod models;
+pub mod notify;
 pub mod permissions;
+pub mod profiles;
+pub mod quirks;
+pub mod rgb_protocol;
+pub mod schedule;
+pub mod status;
 pub mod theme;
 pub mod ui;
+pub mod user_dirs;
 
 use anyhow::Result;
 
 use app::App;
+use cli::Cli;
+
+pub fn run(cli: &Cli) -> Result<()> {
+    let app = App::new(cli)?;
+    // try_init also installs a panic hook that leaves raw mode and the
+    // alternate screen before the panic message is printed.
+    let terminal = ratatui::try_init()?;
+
+    #[cfg(debug_assertions)]
+    if cli.debug_panic {
+        let mut terminal = terminal;
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+        panic!("--debug-panic: the terminal should be usable after this message");
+    }
 
-pub fn run() -> Result<()> {
-    let app = App::new()?;
-    let terminal = ratatui::init();
     let result = app.run(terminal);
     ratatui::restore();
     result
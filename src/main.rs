@@ -6,8 +6,16 @@ use arch_sense::commands;
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    if let Some(shell) = cli.completions {
+        return commands::print_completions(shell);
+    }
+
     if cli.doctor {
-        return commands::print_permission_report();
+        return commands::doctor(cli.json);
+    }
+
+    if cli.status {
+        return commands::status(cli.json);
     }
 
     if cli.install_permissions_root {
@@ -22,9 +30,9 @@ fn main() -> Result<()> {
         return commands::apply_permissions();
     }
 
-    if cli.apply {
-        return commands::apply_saved_config();
+    if let Some(target) = cli.apply {
+        return commands::apply_saved_config(&cli, target);
     }
 
-    arch_sense::run()
+    arch_sense::run(&cli)
 }
@@ -1,4 +1,12 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
 use crate::config::RgbConfig;
+use crate::rgb_protocol::{
+    thermal_color, ColorDef, Rgb, RgbEffect, COLOR_PALETTE, DIRECTIONS, OFF_EFFECT_INDEX,
+    RANDOM_COLOR_INDEX, RGB_EFFECTS, STATIC_EFFECT_INDEX, THERMAL_EFFECT_INDEX, ZONES_EFFECT_INDEX,
+    ZONE_COUNT,
+};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum FocusPanel {
@@ -32,16 +40,18 @@ pub(crate) enum ControlId {
     BatteryCalibration,
     BatteryLimiter,
     BootAnimation,
-    FanSpeed,
+    CpuFan,
+    GpuFan,
     LcdOverride,
     UsbCharging,
 }
 
 impl ControlId {
-    pub(crate) const ALL: [Self; 8] = [
+    pub(crate) const ALL: [Self; 9] = [
         Self::ThermalProfile,
         Self::BatteryLimiter,
-        Self::FanSpeed,
+        Self::CpuFan,
+        Self::GpuFan,
         Self::BacklightTimeout,
         Self::BatteryCalibration,
         Self::BootAnimation,
@@ -56,11 +66,76 @@ impl ControlId {
             Self::BatteryCalibration => "Battery Calibration",
             Self::BatteryLimiter => "Battery Limit",
             Self::BootAnimation => "Boot Animation",
-            Self::FanSpeed => "Fan Speed",
+            Self::CpuFan => "CPU Fan",
+            Self::GpuFan => "GPU Fan",
             Self::LcdOverride => "LCD Override",
             Self::UsbCharging => "USB Charging",
         }
     }
+
+    /// What the control does, matched by the Controls filter.
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Self::ThermalProfile => "Quiet, balanced, performance or low-power mode",
+            Self::BacklightTimeout => "Turn keyboard RGB off after 30 s idle",
+            Self::BatteryCalibration => "Run a battery calibration cycle",
+            Self::BatteryLimiter => "Cap charging for battery longevity",
+            Self::BootAnimation => "Boot animation and sound",
+            Self::CpuFan => "CPU fan speed, auto or a fixed percentage",
+            Self::GpuFan => "GPU fan speed, auto or a fixed percentage",
+            Self::LcdOverride => "Reduce display latency and ghosting",
+            Self::UsbCharging => "Power USB ports while the laptop is off",
+        }
+    }
+
+    /// Case-insensitive substring match on the label and description.
+    pub(crate) fn matches(self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+        self.label().to_lowercase().contains(&filter)
+            || self.description().to_lowercase().contains(&filter)
+    }
+
+    /// Stable name used for this control in config files.
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Self::ThermalProfile => "thermal_profile",
+            Self::BacklightTimeout => "backlight_timeout",
+            Self::BatteryCalibration => "battery_calibration",
+            Self::BatteryLimiter => "battery_limiter",
+            Self::BootAnimation => "boot_animation",
+            Self::CpuFan => "cpu_fan",
+            Self::GpuFan => "gpu_fan",
+            Self::LcdOverride => "lcd_override",
+            Self::UsbCharging => "usb_charging",
+        }
+    }
+
+    /// Settings worth restoring later. Calibration is excluded because
+    /// re-applying it would start a multi-hour cycle.
+    pub(crate) fn is_persistent(self) -> bool {
+        self != Self::BatteryCalibration
+    }
+
+    /// What writing `value` will do, for values risky enough to need a
+    /// confirmation popup first.
+    pub(crate) fn confirmation(self, value: &str) -> Option<&'static str> {
+        match (self, value) {
+            (Self::BatteryCalibration, "1") => Some(
+                "Calibration fully discharges and then recharges the battery. \
+                 It takes several hours and must stay on AC power the whole time.",
+            ),
+            (Self::CpuFan | Self::GpuFan, "100,100") => Some(
+                "Both fans will run at full speed, ignoring the firmware's fan curve, \
+                 until you set them back to Auto. This is loud.",
+            ),
+            _ => None,
+        }
+    }
+
+    /// The two fan rows share predator_sense's `fan_speed` attribute.
+    pub(crate) fn is_fan(self) -> bool {
+        matches!(self, Self::CpuFan | Self::GpuFan)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -92,6 +167,7 @@ pub(crate) struct ControlItem {
     pub(crate) kind: ControlKind,
     pub(crate) pending: Option<usize>,
     pub(crate) last_error: Option<String>,
+    pub(crate) writable: bool,
 }
 
 impl ControlItem {
@@ -99,6 +175,36 @@ impl ControlItem {
         self.id.label()
     }
 
+    /// Accepted values for controls that can be typed in, or `None` when the
+    /// control only takes its listed choices. USB charging takes whichever
+    /// thresholds the driver listed.
+    pub(crate) fn typed_hint(&self) -> Option<String> {
+        match (self.id, &self.kind) {
+            (ControlId::CpuFan | ControlId::GpuFan, _) => Some("0-100 (0 = Auto)".to_string()),
+            (ControlId::UsbCharging, ControlKind::Choice(choices)) if !choices.is_empty() => {
+                let values: Vec<&str> =
+                    choices.iter().map(|choice| choice.value.as_str()).collect();
+                Some(match values.split_last() {
+                    Some((last, rest)) if !rest.is_empty() => {
+                        format!("{} or {last}", rest.join(", "))
+                    }
+                    _ => values.join(", "),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn accepts_typed(&self, value: u8) -> bool {
+        match (self.id, &self.kind) {
+            (ControlId::CpuFan | ControlId::GpuFan, _) => value <= 100,
+            (ControlId::UsbCharging, ControlKind::Choice(choices)) => choices
+                .iter()
+                .any(|choice| choice.value == value.to_string()),
+            _ => false,
+        }
+    }
+
     pub(crate) fn pending_choice(&self) -> Option<&ControlChoice> {
         match (&self.kind, self.pending) {
             (ControlKind::Choice(choices), Some(index)) => choices.get(index),
@@ -112,6 +218,32 @@ impl ControlItem {
             .unwrap_or_else(|| self.display.clone())
     }
 
+    /// Previews a typed number, adding it as a choice if it is not listed.
+    pub(crate) fn preview_typed(&mut self, value: u8) {
+        let ControlKind::Choice(choices) = &mut self.kind else {
+            return;
+        };
+
+        let raw = value.to_string();
+        let index = choices
+            .iter()
+            .position(|choice| choice.value == raw)
+            .unwrap_or_else(|| {
+                let at = choices
+                    .iter()
+                    .position(|choice| {
+                        choice
+                            .value
+                            .parse::<u8>()
+                            .is_ok_and(|listed| listed > value)
+                    })
+                    .unwrap_or(choices.len());
+                choices.insert(at, ControlChoice::new(raw, format!("{value}%")));
+                at
+            });
+        self.pending = Some(index);
+    }
+
     pub(crate) fn current_choice_index(&self) -> Option<usize> {
         match &self.kind {
             ControlKind::Choice(choices) => choices
@@ -168,11 +300,74 @@ pub(crate) struct SensorSnapshot {
     pub(crate) gpu_fan: SensorMetric,
     pub(crate) cpu_fan_mode: FanMode,
     pub(crate) gpu_fan_mode: FanMode,
+    /// Manual percentages from fan_speed; `None` in auto mode.
+    pub(crate) cpu_fan_percent: Option<u8>,
+    pub(crate) gpu_fan_percent: Option<u8>,
+}
+
+impl SensorSnapshot {
+    /// The hotter of the CPU and GPU, in whole °C.
+    pub(crate) fn hottest_temp(&self) -> Option<u64> {
+        [self.cpu_temp.value, self.gpu_temp.value]
+            .into_iter()
+            .flatten()
+            .map(|celsius| celsius.max(0.0).round() as u64)
+            .max()
+    }
+}
+
+/// What `--apply` restores from the saved config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ApplyTarget {
+    /// Hardware settings, then keyboard lighting
+    #[default]
+    All,
+    /// Keyboard lighting only
+    Rgb,
+    /// Thermal profile, fans, battery and other sysfs settings only
+    Hardware,
+}
+
+impl ApplyTarget {
+    pub(crate) fn includes_rgb(self) -> bool {
+        matches!(self, Self::All | Self::Rgb)
+    }
+
+    pub(crate) fn includes_hardware(self) -> bool {
+        matches!(self, Self::All | Self::Hardware)
+    }
+}
+
+/// Transport used to send keyboard lighting reports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RgbBackend {
+    /// Try libusb first and fall back to hidraw
+    #[default]
+    Auto,
+    /// Detach usbhid and use USB control transfers
+    Libusb,
+    /// Send HID feature reports through /dev/hidraw*
+    Hidraw,
+}
+
+/// Where GPU temperature and fan readings come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum GpuSource {
+    /// AMD dGPU via DRM, then any GPU hwmon, then nvidia-smi
+    #[default]
+    Auto,
+    Nvidia,
+    Amd,
+    /// Do not read GPU sensors at all
+    None,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum RgbField {
     Effect,
+    Zone,
     Color,
     Brightness,
     Speed,
@@ -180,8 +375,9 @@ pub(crate) enum RgbField {
 }
 
 impl RgbField {
-    pub(crate) const ALL: [Self; 5] = [
+    pub(crate) const ALL: [Self; 6] = [
         Self::Effect,
+        Self::Zone,
         Self::Color,
         Self::Brightness,
         Self::Speed,
@@ -191,214 +387,31 @@ impl RgbField {
     pub(crate) fn label(self) -> &'static str {
         match self {
             Self::Effect => "Mode",
+            Self::Zone => "Zone",
             Self::Color => "Color",
             Self::Brightness => "Brightness",
             Self::Speed => "Speed",
             Self::Direction => "Direction",
         }
     }
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct Rgb {
-    pub(crate) r: u8,
-    pub(crate) g: u8,
-    pub(crate) b: u8,
-}
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct ColorDef {
-    pub(crate) name: &'static str,
-    pub(crate) rgb: Rgb,
+    /// Percentage rows that take fine steps and jumps to either end.
+    pub(crate) fn is_slider(self) -> bool {
+        matches!(self, Self::Brightness | Self::Speed)
+    }
 }
 
-pub(crate) const COLOR_PALETTE: [ColorDef; 11] = [
-    ColorDef {
-        name: "Red",
-        rgb: Rgb {
-            r: 255,
-            g: 70,
-            b: 70,
-        },
-    },
-    ColorDef {
-        name: "Orange",
-        rgb: Rgb {
-            r: 255,
-            g: 142,
-            b: 45,
-        },
-    },
-    ColorDef {
-        name: "Gold",
-        rgb: Rgb {
-            r: 250,
-            g: 204,
-            b: 21,
-        },
-    },
-    ColorDef {
-        name: "Emerald",
-        rgb: Rgb {
-            r: 52,
-            g: 211,
-            b: 153,
-        },
-    },
-    ColorDef {
-        name: "Cyan",
-        rgb: Rgb {
-            r: 34,
-            g: 211,
-            b: 238,
-        },
-    },
-    ColorDef {
-        name: "Blue",
-        rgb: Rgb {
-            r: 96,
-            g: 165,
-            b: 250,
-        },
-    },
-    ColorDef {
-        name: "Violet",
-        rgb: Rgb {
-            r: 167,
-            g: 139,
-            b: 250,
-        },
-    },
-    ColorDef {
-        name: "Magenta",
-        rgb: Rgb {
-            r: 232,
-            g: 121,
-            b: 249,
-        },
-    },
-    ColorDef {
-        name: "Pink",
-        rgb: Rgb {
-            r: 244,
-            g: 114,
-            b: 182,
-        },
-    },
-    ColorDef {
-        name: "White",
-        rgb: Rgb {
-            r: 255,
-            g: 255,
-            b: 255,
-        },
-    },
-    ColorDef {
-        name: "Random",
-        rgb: Rgb { r: 0, g: 0, b: 0 },
-    },
-];
-
-pub(crate) const RANDOM_COLOR_INDEX: usize = 10;
-
+/// How one key press moves a Brightness or Speed slider.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct RgbEffect {
-    pub(crate) name: &'static str,
-    pub(crate) opcode: u8,
-    pub(crate) has_color: bool,
-    pub(crate) has_direction: bool,
+pub(crate) enum SliderMove {
+    /// ±10 points, from an arrow key.
+    Step(i8),
+    /// ±1 point, from Shift and an arrow key.
+    Fine(i8),
+    Min,
+    Max,
 }
 
-pub(crate) const RGB_EFFECTS: [RgbEffect; 14] = [
-    RgbEffect {
-        name: "Off",
-        opcode: 0x01,
-        has_color: false,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Static",
-        opcode: 0x01,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Breathing",
-        opcode: 0x02,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Wave",
-        opcode: 0x03,
-        has_color: false,
-        has_direction: true,
-    },
-    RgbEffect {
-        name: "Snake",
-        opcode: 0x05,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Ripple",
-        opcode: 0x06,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Rainbow",
-        opcode: 0x08,
-        has_color: false,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Rain",
-        opcode: 0x0A,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Lightning",
-        opcode: 0x12,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Spot",
-        opcode: 0x25,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Stars",
-        opcode: 0x26,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Fireball",
-        opcode: 0x27,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Snow",
-        opcode: 0x28,
-        has_color: true,
-        has_direction: false,
-    },
-    RgbEffect {
-        name: "Heartbeat",
-        opcode: 0x29,
-        has_color: true,
-        has_direction: false,
-    },
-];
-
-pub(crate) const OFF_EFFECT_INDEX: usize = 0;
-pub(crate) const DIRECTIONS: [&str; 6] = ["Right", "Left", "Up", "Down", "Clockwise", "Counter-CW"];
-
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) struct RgbSettings {
     pub(crate) effect_idx: usize,
@@ -406,6 +419,16 @@ pub(crate) struct RgbSettings {
     pub(crate) brightness: u8,
     pub(crate) speed: u8,
     pub(crate) direction_idx: usize,
+    /// Exact colour from the picker; overrides `color_idx` until a palette
+    /// colour is chosen again.
+    pub(crate) custom: Option<Rgb>,
+    /// Per-zone colours used by the Zones effect, left to right.
+    pub(crate) zones: [Rgb; ZONE_COUNT],
+    /// Thermal effect colours for cool and hot.
+    pub(crate) thermal_low: Rgb,
+    pub(crate) thermal_high: Rgb,
+    /// Effect to switch back to from Off; never Off itself.
+    pub(crate) last_effect: usize,
 }
 
 impl RgbSettings {
@@ -416,6 +439,14 @@ impl RgbSettings {
             brightness: config.brightness.min(100),
             speed: config.speed.min(100),
             direction_idx: config.direction.min(DIRECTIONS.len() - 1),
+            custom: config.custom_rgb.map(|(r, g, b)| Rgb { r, g, b }),
+            zones: config.zones.map(|(r, g, b)| Rgb { r, g, b }),
+            thermal_low: tuple_rgb(config.thermal_low),
+            thermal_high: tuple_rgb(config.thermal_high),
+            last_effect: match config.last_effect.min(RGB_EFFECTS.len() - 1) {
+                OFF_EFFECT_INDEX => STATIC_EFFECT_INDEX,
+                effect => effect,
+            },
         }
     }
 
@@ -426,6 +457,11 @@ impl RgbSettings {
             brightness: self.brightness,
             speed: self.speed,
             direction: self.direction_idx,
+            custom_rgb: self.custom.map(|rgb| (rgb.r, rgb.g, rgb.b)),
+            zones: self.zones.map(|rgb| (rgb.r, rgb.g, rgb.b)),
+            thermal_low: rgb_tuple(self.thermal_low),
+            thermal_high: rgb_tuple(self.thermal_high),
+            last_effect: self.last_effect,
         }
     }
 
@@ -437,23 +473,129 @@ impl RgbSettings {
         COLOR_PALETTE[self.color_idx]
     }
 
+    /// The colour loaded onto the keyboard: the custom one if set.
+    pub(crate) fn color_rgb(&self) -> Rgb {
+        self.custom.unwrap_or(self.color().rgb)
+    }
+
+    pub(crate) fn is_random_color(&self) -> bool {
+        self.custom.is_none() && self.color_idx == RANDOM_COLOR_INDEX
+    }
+
+    pub(crate) fn is_zones(&self) -> bool {
+        self.effect_idx == ZONES_EFFECT_INDEX
+    }
+
+    pub(crate) fn is_off(&self) -> bool {
+        self.effect_idx == OFF_EFFECT_INDEX
+    }
+
+    /// Switches to `effect`, remembering the current one when that turns
+    /// the lighting off.
+    fn set_effect(&mut self, effect: usize) {
+        if effect == OFF_EFFECT_INDEX && !self.is_off() {
+            self.last_effect = self.effect_idx;
+        }
+        self.effect_idx = effect;
+    }
+
+    /// Off, or back to the effect that was on before; colour, speed and
+    /// the rest were never changed by Off.
+    pub(crate) fn toggle_power(&mut self) {
+        if self.is_off() {
+            self.effect_idx = self.last_effect;
+        } else {
+            self.set_effect(OFF_EFFECT_INDEX);
+        }
+    }
+
+    pub(crate) fn is_thermal(&self) -> bool {
+        self.effect_idx == THERMAL_EFFECT_INDEX
+    }
+
+    /// Thermal effect colour at `temp` °C.
+    pub(crate) fn thermal_color(&self, temp: Option<u64>) -> Rgb {
+        thermal_color(self.thermal_low, self.thermal_high, temp)
+    }
+
+    /// What goes to the keyboard: Thermal becomes Static in the colour for
+    /// `temp`, every other effect is sent as is.
+    pub(crate) fn tinted(self, temp: Option<u64>) -> Self {
+        if !self.is_thermal() {
+            return self;
+        }
+        Self {
+            effect_idx: STATIC_EFFECT_INDEX,
+            custom: Some(self.thermal_color(temp)),
+            ..self
+        }
+    }
+
+    /// Whether the Color row applies: a loaded colour or the zone colours.
+    pub(crate) fn uses_color(&self) -> bool {
+        self.effect().has_color || self.is_zones()
+    }
+
+    /// Steps `zone` through the palette presets; a custom colour restarts
+    /// at the first preset.
+    pub(crate) fn step_zone_color(&mut self, zone: usize, step: i8) {
+        let presets = &COLOR_PALETTE[..RANDOM_COLOR_INDEX];
+        let next = presets
+            .iter()
+            .position(|color| color.rgb == self.zones[zone])
+            .map_or(0, |current| wrap_index(current, presets.len(), step));
+        self.zones[zone] = presets[next].rgb;
+    }
+
+    /// Moves to the next effect in `step`'s direction that `offered`
+    /// accepts, wrapping around; stays put if there is none.
+    pub(crate) fn step_effect(&mut self, step: i8, offered: impl Fn(usize) -> bool) {
+        let mut index = self.effect_idx;
+        for _ in 0..RGB_EFFECTS.len() {
+            index = wrap_index(index, RGB_EFFECTS.len(), step);
+            if offered(index) {
+                self.set_effect(index);
+                return;
+            }
+        }
+    }
+
+    /// Moves the Brightness or Speed slider and returns its new value;
+    /// `None` for any other field.
+    pub(crate) fn move_slider(&mut self, field: RgbField, movement: SliderMove) -> Option<u8> {
+        let value = match field {
+            RgbField::Brightness => &mut self.brightness,
+            RgbField::Speed => &mut self.speed,
+            _ => return None,
+        };
+        *value = match movement {
+            SliderMove::Step(step) => adjust_percent(*value, step),
+            SliderMove::Fine(step) => {
+                (i16::from(*value) + i16::from(step.signum())).clamp(0, 100) as u8
+            }
+            SliderMove::Min => 0,
+            SliderMove::Max => 100,
+        };
+        Some(*value)
+    }
+
     pub(crate) fn direction_name(&self) -> &'static str {
         DIRECTIONS[self.direction_idx]
     }
 
+    /// `RgbField::Zone` only picks which zone to edit, so the App handles it.
     pub(crate) fn adjust(&mut self, field: RgbField, step: i8) {
         match field {
-            RgbField::Effect => {
-                self.effect_idx = wrap_index(self.effect_idx, RGB_EFFECTS.len(), step);
-            }
+            RgbField::Effect => self.step_effect(step, |_| true),
+            RgbField::Zone => {}
             RgbField::Color => {
-                self.color_idx = wrap_index(self.color_idx, COLOR_PALETTE.len(), step);
-            }
-            RgbField::Brightness => {
-                self.brightness = adjust_percent(self.brightness, step);
+                // Stepping from a custom colour lands back on its palette slot.
+                if self.custom.take().is_none() {
+                    self.color_idx = wrap_index(self.color_idx, COLOR_PALETTE.len(), step);
+                }
             }
-            RgbField::Speed => {
-                self.speed = adjust_percent(self.speed, step);
+            RgbField::Brightness | RgbField::Speed => {
+                self.move_slider(field, SliderMove::Step(step));
             }
             RgbField::Direction => {
                 self.direction_idx = wrap_index(self.direction_idx, DIRECTIONS.len(), step);
@@ -479,10 +621,59 @@ fn adjust_percent(current: u8, step: i8) -> u8 {
     (current as i16 + delta).clamp(0, 100) as u8
 }
 
+fn tuple_rgb((r, g, b): (u8, u8, u8)) -> Rgb {
+    Rgb { r, g, b }
+}
+
+fn rgb_tuple(rgb: Rgb) -> (u8, u8, u8) {
+    (rgb.r, rgb.g, rgb.b)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn typed_values_are_inserted_in_order() {
+        let mut item = ControlItem {
+            id: ControlId::CpuFan,
+            raw: "0".to_string(),
+            display: "Auto".to_string(),
+            kind: ControlKind::Choice(vec![
+                ControlChoice::new("0", "Auto"),
+                ControlChoice::new("40", "40%"),
+                ControlChoice::new("50", "50%"),
+            ]),
+            pending: None,
+            last_error: None,
+            writable: true,
+        };
+
+        item.preview_typed(43);
+        assert_eq!(item.visible_value(), "43%");
+        assert_eq!(item.pending, Some(2));
+        item.preview_typed(50);
+        assert_eq!(item.pending, Some(3));
+        assert!(!ControlItem {
+            id: ControlId::GpuFan,
+            ..item.clone()
+        }
+        .accepts_typed(101));
+
+        let usb = ControlItem {
+            id: ControlId::UsbCharging,
+            kind: ControlKind::Choice(vec![
+                ControlChoice::new("0", "Off"),
+                ControlChoice::new("25", "Until 25%"),
+                ControlChoice::new("50", "Until 50%"),
+            ]),
+            ..item
+        };
+        assert!(usb.accepts_typed(25));
+        assert!(!usb.accepts_typed(20));
+        assert_eq!(usb.typed_hint().as_deref(), Some("0, 25 or 50"));
+    }
+
     #[test]
     fn rgb_config_values_are_clamped() {
         let config = RgbConfig {
@@ -491,6 +682,7 @@ mod tests {
             brightness: 140,
             speed: 120,
             direction: 99,
+            ..RgbConfig::default()
         };
 
         let rgb = RgbSettings::from_config(&config);
@@ -510,6 +702,13 @@ mod tests {
         rgb.adjust(RgbField::Effect, -1);
         assert_eq!(rgb.effect_idx, RGB_EFFECTS.len() - 1);
 
+        rgb.step_effect(1, |index| index == 3 || index == 5);
+        assert_eq!(rgb.effect_idx, 3);
+        rgb.step_effect(1, |index| index == 3 || index == 5);
+        assert_eq!(rgb.effect_idx, 5);
+        rgb.step_effect(-1, |_| false);
+        assert_eq!(rgb.effect_idx, 5);
+
         rgb.brightness = 95;
         rgb.adjust(RgbField::Brightness, 1);
         assert_eq!(rgb.brightness, 100);
@@ -518,4 +717,38 @@ mod tests {
         rgb.adjust(RgbField::Speed, -1);
         assert_eq!(rgb.speed, 0);
     }
+
+    #[test]
+    fn off_remembers_the_effect_to_return_to() {
+        let mut rgb = RgbSettings::from_config(&RgbConfig::default());
+        rgb.effect_idx = 3; // Wave
+        rgb.direction_idx = 2;
+
+        rgb.toggle_power();
+        assert!(rgb.is_off());
+        assert_eq!(rgb.last_effect, 3);
+        rgb.toggle_power();
+        assert_eq!((rgb.effect_idx, rgb.direction_idx), (3, 2));
+
+        // Stepping onto Off through the Mode row remembers too.
+        rgb.effect_idx = OFF_EFFECT_INDEX + 1;
+        rgb.step_effect(-1, |_| true);
+        assert!(rgb.is_off());
+        assert_eq!(rgb.last_effect, OFF_EFFECT_INDEX + 1);
+
+        // Off and the remembered effect both survive a save.
+        rgb.last_effect = 3;
+        let saved = RgbSettings::from_config(&rgb.to_config());
+        assert!(saved.is_off());
+        assert_eq!(saved.last_effect, 3);
+
+        let config = RgbConfig {
+            last_effect: OFF_EFFECT_INDEX,
+            ..RgbConfig::default()
+        };
+        assert_eq!(
+            RgbSettings::from_config(&config).last_effect,
+            STATIC_EFFECT_INDEX
+        );
+    }
 }
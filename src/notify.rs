@@ -0,0 +1,204 @@
+//! Desktop notifications while the TUI runs in the background.
+//!
+//! Alerts go out through `notify-send` on the session bus of the user at the
+//! desktop, which is the invoking user when started with sudo. Without a
+//! reachable session bus, or without `notify-send`, they are dropped.
+
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::NotificationsConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Alert {
+    CpuHot(u64),
+    GpuHot(u64),
+    KeyboardLost,
+}
+
+impl Alert {
+    fn kind(self) -> usize {
+        match self {
+            Self::CpuHot(_) => 0,
+            Self::GpuHot(_) => 1,
+            Self::KeyboardLost => 2,
+        }
+    }
+
+    fn summary(self) -> &'static str {
+        match self {
+            Self::CpuHot(_) => "CPU temperature high",
+            Self::GpuHot(_) => "GPU temperature high",
+            Self::KeyboardLost => "Keyboard disconnected",
+        }
+    }
+
+    fn body(self) -> String {
+        match self {
+            Self::CpuHot(temp) => format!("CPU reached {temp} °C"),
+            Self::GpuHot(temp) => format!("GPU reached {temp} °C"),
+            Self::KeyboardLost => "The RGB keyboard is no longer on the USB bus".to_string(),
+        }
+    }
+}
+
+/// Turns snapshots into alerts: a threshold crossed upwards or the keyboard
+/// going away, each kind at most once per cooldown.
+#[derive(Clone, Debug)]
+pub(crate) struct Notifier {
+    config: NotificationsConfig,
+    cpu_hot: bool,
+    gpu_hot: bool,
+    last_sent: [Option<Instant>; 3],
+}
+
+impl Notifier {
+    pub(crate) fn new(config: &NotificationsConfig) -> Self {
+        Self {
+            config: config.clone(),
+            cpu_hot: false,
+            gpu_hot: false,
+            last_sent: [None; 3],
+        }
+    }
+
+    pub(crate) fn check(
+        &mut self,
+        cpu_temp: Option<f64>,
+        gpu_temp: Option<f64>,
+        keyboard_lost: bool,
+        now: Instant,
+    ) -> Vec<Alert> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+
+        let mut alerts = Vec::new();
+        if let Some(temp) = crossed(&mut self.cpu_hot, cpu_temp, self.config.cpu_threshold) {
+            alerts.push(Alert::CpuHot(temp));
+        }
+        if let Some(temp) = crossed(&mut self.gpu_hot, gpu_temp, self.config.gpu_threshold) {
+            alerts.push(Alert::GpuHot(temp));
+        }
+        if keyboard_lost {
+            alerts.push(Alert::KeyboardLost);
+        }
+
+        let cooldown = Duration::from_secs(self.config.cooldown_secs);
+        alerts.retain(|alert| {
+            let last = &mut self.last_sent[alert.kind()];
+            if last.is_some_and(|sent| now.saturating_duration_since(sent) < cooldown) {
+                return false;
+            }
+            *last = Some(now);
+            true
+        });
+        alerts
+    }
+}
+
+/// Returns the rounded temperature when it rises to `threshold`. A failed
+/// read leaves the state alone so a gap does not re-arm the alert.
+fn crossed(hot: &mut bool, temp: Option<f64>, threshold: u64) -> Option<u64> {
+    let temp = temp?.round() as u64;
+    let was_hot = std::mem::replace(hot, temp >= threshold);
+    (*hot && !was_hot).then_some(temp)
+}
+
+/// Shows `alert` without blocking the caller.
+pub(crate) fn send(alert: Alert) {
+    let Some(mut command) = notify_command() else {
+        return;
+    };
+    command
+        .args(["--app-name=Arch-Sense", "--urgency=critical"])
+        .arg(alert.summary())
+        .arg(alert.body())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // Reap the child off the UI thread; a missing notify-send just fails here.
+    thread::spawn(move || {
+        let _ = command.status();
+    });
+}
+
+/// `notify-send` aimed at the desktop user's session bus, if there is one.
+fn notify_command() -> Option<Command> {
+    // Under sudo our own environment points at root's (absent) session.
+    if let (Ok(user), Ok(uid)) = (env::var("SUDO_USER"), env::var("SUDO_UID")) {
+        let bus = format!("/run/user/{uid}/bus");
+        if user.is_empty() || user == "root" || !Path::new(&bus).exists() {
+            return None;
+        }
+        let mut command = Command::new("sudo");
+        command.args(["-u", &user, "env"]);
+        command.arg(format!("DBUS_SESSION_BUS_ADDRESS=unix:path={bus}"));
+        command.arg("notify-send");
+        return Some(command);
+    }
+
+    if env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some() {
+        return Some(Command::new("notify-send"));
+    }
+
+    let bus = Path::new(&env::var_os("XDG_RUNTIME_DIR")?).join("bus");
+    bus.exists().then(|| {
+        let mut command = Command::new("notify-send");
+        command.env(
+            "DBUS_SESSION_BUS_ADDRESS",
+            format!("unix:path={}", bus.display()),
+        );
+        command
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alerts_fire_on_upward_crossings_within_the_cooldown() {
+        let config = NotificationsConfig {
+            enabled: true,
+            ..NotificationsConfig::default()
+        };
+        let mut notifier = Notifier::new(&config);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(notifier.check(Some(89.4), Some(70.0), false, at(0)), []);
+        assert_eq!(
+            notifier.check(Some(91.0), None, true, at(1)),
+            [Alert::CpuHot(91), Alert::KeyboardLost]
+        );
+        // Staying hot, or a gap, is not a new crossing.
+        assert_eq!(notifier.check(Some(95.0), None, false, at(2)), []);
+        assert_eq!(
+            notifier.check(None, Some(86.0), false, at(3)),
+            [Alert::GpuHot(86)]
+        );
+
+        // Dropping and crossing again inside the cooldown stays quiet.
+        notifier.check(Some(80.0), None, false, at(4));
+        assert_eq!(notifier.check(Some(92.0), None, false, at(5)), []);
+        notifier.check(Some(80.0), None, false, at(400));
+        assert_eq!(
+            notifier.check(Some(90.0), None, false, at(401)),
+            [Alert::CpuHot(90)]
+        );
+    }
+
+    #[test]
+    fn disabled_notifier_stays_silent() {
+        let mut notifier = Notifier::new(&NotificationsConfig::default());
+        assert_eq!(
+            notifier.check(Some(100.0), Some(100.0), true, Instant::now()),
+            []
+        );
+    }
+}
@@ -9,12 +9,14 @@ use std::process::Command;
 use anyhow::{bail, Context, Result};
 use rusb::{DeviceHandle, Error as UsbError, GlobalContext};
 
-use crate::config::{config_dir, config_path};
-use crate::constants::{ps, KB_PID, KB_VID, PLATFORM_PROFILE};
+use crate::config::{config_dir, config_path, AppConfig, CONFIG_FILE_MODE};
+use crate::constants::{find_ps_base, ps, sysfs, PLATFORM_PROFILE};
+use crate::hardware::charge_threshold_path;
+use crate::rgb_protocol::{probed_label, KeyboardModel};
 
 pub(crate) const HARDWARE_GROUP: &str = "arch-sense";
 
-const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/70-arch-sense.rules";
+pub(crate) const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/70-arch-sense.rules";
 const PERMISSION_SERVICE_PATH: &str = "/etc/systemd/system/arch-sense-permissions.service";
 const INSTALLED_BINARY_PATH: &str = "/usr/bin/arch-sense";
 const ROOT_INSTALL_FLAG: &str = "--install-permissions-root";
@@ -24,22 +26,25 @@ const SYSFS_ATTRS: &[&str] = &[
     "battery_calibration",
     "battery_limiter",
     "boot_animation_sound",
+    "boot_animation",
     "fan_speed",
     "lcd_override",
+    "lcd_overdrive",
     "usb_charging",
 ];
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum UsbAccess {
-    Accessible,
-    PermissionDenied,
+    Accessible(KeyboardModel),
+    /// The keyboard is on the bus but udev has not granted access to it.
+    PermissionDenied(KeyboardModel),
     NotFound,
     Error(String),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum KeyboardOpenError {
-    PermissionDenied,
+    PermissionDenied(KeyboardModel),
     NotFound,
     Other(String),
 }
@@ -55,34 +60,15 @@ pub(crate) enum PathAccess {
 #[derive(Clone, Debug)]
 pub(crate) struct PermissionReport {
     pub(crate) is_root: bool,
-    pub(crate) sysfs: Vec<(PathBuf, PathAccess)>,
     pub(crate) usb: UsbAccess,
 }
 
 impl PermissionReport {
-    pub(crate) fn collect() -> Self {
+    pub(crate) fn collect(keyboards: &[KeyboardModel]) -> Self {
         Self {
             is_root: is_root(),
-            sysfs: sysfs_write_paths()
-                .into_iter()
-                .map(|path| {
-                    let access = path_write_access(&path);
-                    (path, access)
-                })
-                .collect(),
-            usb: keyboard_access(),
-        }
-    }
-
-    pub(crate) fn has_limited_access(&self) -> bool {
-        if self.is_root {
-            return false;
+            usb: keyboard_access(keyboards),
         }
-
-        let sysfs_denied = self.sysfs.iter().any(|(_, access)| {
-            matches!(access, PathAccess::PermissionDenied | PathAccess::Error(_))
-        });
-        sysfs_denied || matches!(self.usb, UsbAccess::PermissionDenied)
     }
 }
 
@@ -90,62 +76,73 @@ pub(crate) fn setup_hint() -> &'static str {
     "run `arch-sense --install-permissions` once, then log out and back in if prompted"
 }
 
-pub(crate) fn keyboard_present() -> bool {
-    !matches!(keyboard_access(), UsbAccess::NotFound)
+pub(crate) fn keyboard_present(keyboards: &[KeyboardModel]) -> bool {
+    !matches!(keyboard_access(keyboards), UsbAccess::NotFound)
 }
 
-pub(crate) fn keyboard_access() -> UsbAccess {
-    match try_open_keyboard() {
-        Ok(_) => UsbAccess::Accessible,
-        Err(KeyboardOpenError::PermissionDenied) => UsbAccess::PermissionDenied,
+pub(crate) fn keyboard_access(keyboards: &[KeyboardModel]) -> UsbAccess {
+    match try_open_keyboard(keyboards) {
+        Ok((_, model)) => UsbAccess::Accessible(model),
+        Err(KeyboardOpenError::PermissionDenied(model)) => UsbAccess::PermissionDenied(model),
         Err(KeyboardOpenError::NotFound) => UsbAccess::NotFound,
         Err(KeyboardOpenError::Other(err)) => UsbAccess::Error(err),
     }
 }
 
-pub(crate) fn open_keyboard() -> Result<DeviceHandle<GlobalContext>> {
-    match try_open_keyboard() {
-        Ok(handle) => Ok(handle),
-        Err(KeyboardOpenError::PermissionDenied) => bail!(
-            "Keyboard USB access denied (VID:04F2 PID:0117); {}",
+pub(crate) fn open_keyboard(
+    keyboards: &[KeyboardModel],
+) -> Result<(DeviceHandle<GlobalContext>, KeyboardModel)> {
+    match try_open_keyboard(keyboards) {
+        Ok(opened) => Ok(opened),
+        Err(KeyboardOpenError::PermissionDenied(model)) => bail!(
+            "{} ({}) is connected but USB access was denied; {}",
+            model.name,
+            model.id_label(),
             setup_hint()
         ),
-        Err(KeyboardOpenError::NotFound) => bail!("Keyboard not found (VID:04F2 PID:0117)"),
+        Err(KeyboardOpenError::NotFound) => {
+            bail!("Keyboard not found (probed {})", probed_label(keyboards))
+        }
         Err(KeyboardOpenError::Other(err)) => {
             bail!("Keyboard found but could not be opened: {err}")
         }
     }
 }
 
-fn try_open_keyboard() -> std::result::Result<DeviceHandle<GlobalContext>, KeyboardOpenError> {
+/// Opens the first keyboard from `keyboards` present on the bus, in table order.
+fn try_open_keyboard(
+    keyboards: &[KeyboardModel],
+) -> std::result::Result<(DeviceHandle<GlobalContext>, KeyboardModel), KeyboardOpenError> {
     let devices = rusb::devices().map_err(|e| KeyboardOpenError::Other(e.to_string()))?;
     let mut found = false;
-    let mut access_denied = false;
+    let mut access_denied = None;
     let mut last_error = None;
 
-    for device in devices.iter() {
-        let desc = match device.device_descriptor() {
-            Ok(desc) => desc,
-            Err(err) => {
-                last_error = Some(err.to_string());
+    for model in keyboards {
+        for device in devices.iter() {
+            let desc = match device.device_descriptor() {
+                Ok(desc) => desc,
+                Err(err) => {
+                    last_error = Some(err.to_string());
+                    continue;
+                }
+            };
+
+            if desc.vendor_id() != model.vid || desc.product_id() != model.pid {
                 continue;
             }
-        };
-
-        if desc.vendor_id() != KB_VID || desc.product_id() != KB_PID {
-            continue;
-        }
 
-        found = true;
-        match device.open() {
-            Ok(handle) => return Ok(handle),
-            Err(UsbError::Access) => access_denied = true,
-            Err(err) => last_error = Some(err.to_string()),
+            found = true;
+            match device.open() {
+                Ok(handle) => return Ok((handle, *model)),
+                Err(UsbError::Access) => access_denied = access_denied.or(Some(*model)),
+                Err(err) => last_error = Some(err.to_string()),
+            }
         }
     }
 
-    if access_denied {
-        Err(KeyboardOpenError::PermissionDenied)
+    if let Some(model) = access_denied {
+        Err(KeyboardOpenError::PermissionDenied(model))
     } else if found {
         Err(KeyboardOpenError::Other(
             last_error.unwrap_or_else(|| "unknown USB error".to_string()),
@@ -155,40 +152,6 @@ fn try_open_keyboard() -> std::result::Result<DeviceHandle<GlobalContext>, Keybo
     }
 }
 
-pub fn print_permission_report() -> Result<()> {
-    let report = PermissionReport::collect();
-
-    println!("Arch-Sense permission report");
-    println!(
-        "  Effective root: {}",
-        if report.is_root { "yes" } else { "no" }
-    );
-    println!(
-        "  Hardware group: {} ({})",
-        HARDWARE_GROUP,
-        if group_exists(HARDWARE_GROUP) {
-            "exists"
-        } else {
-            "missing"
-        }
-    );
-    println!("  USB keyboard: {}", usb_access_label(&report.usb));
-    println!("  Sysfs write access:");
-
-    for (path, access) in &report.sysfs {
-        println!("    {}: {}", path.display(), path_access_label(access));
-    }
-
-    println!("  Config path: {}", config_path().display());
-
-    if report.has_limited_access() {
-        println!();
-        println!("Fix: {}", setup_hint());
-    }
-
-    Ok(())
-}
-
 pub fn install_permissions() -> Result<()> {
     if !is_root() {
         return reexec_install_permissions();
@@ -214,7 +177,8 @@ pub fn install_permissions_as_root() -> Result<()> {
     };
 
     let binary = service_binary_path();
-    write_root_file(Path::new(UDEV_RULE_PATH), &udev_rules(&binary))?;
+    let (keyboards, _) = AppConfig::load().keyboards();
+    write_root_file(Path::new(UDEV_RULE_PATH), &udev_rules(&binary, &keyboards))?;
     write_root_file(
         Path::new(PERMISSION_SERVICE_PATH),
         &permission_service(&binary),
@@ -310,7 +274,7 @@ pub fn apply_permissions_as_root() -> Result<()> {
                 config.as_os_str().to_os_string(),
             ],
         )?;
-        fs::set_permissions(&config, fs::Permissions::from_mode(0o664))
+        fs::set_permissions(&config, fs::Permissions::from_mode(CONFIG_FILE_MODE))
             .with_context(|| format!("setting permissions on {}", config.display()))?;
     }
 
@@ -353,12 +317,33 @@ fn reexec_install_permissions() -> Result<()> {
 
 fn sysfs_write_paths() -> Vec<PathBuf> {
     let mut paths = Vec::with_capacity(SYSFS_ATTRS.len() + 1);
-    paths.push(PathBuf::from(PLATFORM_PROFILE));
-    paths.extend(SYSFS_ATTRS.iter().map(|attr| PathBuf::from(ps(attr))));
+    paths.push(PathBuf::from(sysfs(PLATFORM_PROFILE)));
+    let base = find_ps_base();
+    paths.extend(
+        SYSFS_ATTRS
+            .iter()
+            .map(|attr| PathBuf::from(ps(base.as_deref(), attr))),
+    );
+    paths.extend(charge_threshold_path());
     paths
 }
 
-fn path_write_access(path: &Path) -> PathAccess {
+/// True when not root and the first predator_sense attribute that exists
+/// refuses writes, so the TUI should start read-only.
+pub(crate) fn lacks_write_access() -> bool {
+    if is_root() {
+        return false;
+    }
+
+    let base = find_ps_base();
+    SYSFS_ATTRS
+        .iter()
+        .map(|attr| path_write_access(Path::new(&ps(base.as_deref(), attr))))
+        .find(|access| *access != PathAccess::Missing)
+        == Some(PathAccess::PermissionDenied)
+}
+
+pub(crate) fn path_write_access(path: &Path) -> PathAccess {
     match OpenOptions::new().write(true).open(path) {
         Ok(_) => PathAccess::Writable,
         Err(err) if err.kind() == ErrorKind::NotFound => PathAccess::Missing,
@@ -373,11 +358,11 @@ fn effective_uid() -> Option<u32> {
     uid_line.split_whitespace().nth(2)?.parse().ok()
 }
 
-fn is_root() -> bool {
+pub(crate) fn is_root() -> bool {
     effective_uid() == Some(0)
 }
 
-fn group_exists(group: &str) -> bool {
+pub(crate) fn group_exists(group: &str) -> bool {
     fs::read_to_string("/etc/group")
         .ok()
         .map(|groups| {
@@ -466,14 +451,25 @@ fn service_binary_path() -> PathBuf {
     }
 }
 
-fn udev_rules(binary: &Path) -> String {
+/// The USB and hidraw rules that let the active user open `model`.
+pub(crate) fn keyboard_udev_rules(model: &KeyboardModel) -> String {
+    let (vid, pid) = (format!("{:04x}", model.vid), format!("{:04x}", model.pid));
+    format!(
+        r#"ACTION=="add|change", SUBSYSTEM=="usb", ENV{{DEVTYPE}}=="usb_device", ATTR{{idVendor}}=="{vid}", ATTR{{idProduct}}=="{pid}", TAG+="uaccess", GROUP="{HARDWARE_GROUP}", MODE="0660"
+ACTION=="add|change", SUBSYSTEM=="hidraw", ATTRS{{idVendor}}=="{vid}", ATTRS{{idProduct}}=="{pid}", TAG+="uaccess", GROUP="{HARDWARE_GROUP}", MODE="0660"
+"#
+    )
+}
+
+fn udev_rules(binary: &Path, keyboards: &[KeyboardModel]) -> String {
+    let keyboard_rules: String = keyboards.iter().map(keyboard_udev_rules).collect();
+
     format!(
         r#"# Arch-Sense hardware permissions
 # Managed by: arch-sense --install-permissions
 
-# Let the active local user and the arch-sense group open the keyboard USB device.
-ACTION=="add|change", SUBSYSTEM=="usb", ENV{{DEVTYPE}}=="usb_device", ATTR{{idVendor}}=="04f2", ATTR{{idProduct}}=="0117", TAG+="uaccess", GROUP="{HARDWARE_GROUP}", MODE="0660"
-
+# Let the active local user and the arch-sense group open the keyboard USB and hidraw devices.
+{keyboard_rules}
 # Reapply sysfs permissions whenever the Acer platform device is announced.
 ACTION=="add|change", SUBSYSTEM=="platform", KERNEL=="acer-wmi", RUN+="{binary} --apply-permissions"
 "#,
@@ -551,20 +547,22 @@ where
     }
 }
 
-fn usb_access_label(access: &UsbAccess) -> String {
-    match access {
-        UsbAccess::Accessible => "accessible".to_string(),
-        UsbAccess::PermissionDenied => format!("permission denied; {}", setup_hint()),
-        UsbAccess::NotFound => "not found".to_string(),
-        UsbAccess::Error(err) => format!("error: {err}"),
-    }
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgb_protocol::SUPPORTED_KEYBOARDS;
+
+    #[test]
+    fn keyboard_rules_cover_usb_and_hidraw() {
+        let rules = keyboard_udev_rules(&SUPPORTED_KEYBOARDS[0]);
+        let lines: Vec<&str> = rules.lines().collect();
 
-fn path_access_label(access: &PathAccess) -> String {
-    match access {
-        PathAccess::Writable => "writable".to_string(),
-        PathAccess::Missing => "missing".to_string(),
-        PathAccess::PermissionDenied => format!("permission denied; {}", setup_hint()),
-        PathAccess::Error(err) => format!("error: {err}"),
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(r#"SUBSYSTEM=="usb""#));
+        assert!(lines[1].contains(r#"SUBSYSTEM=="hidraw""#));
+        assert!(lines
+            .iter()
+            .all(|line| line.contains(r#"=="04f2""#) && line.contains(r#"=="0117""#)));
+        assert!(udev_rules(Path::new("/usr/bin/arch-sense"), SUPPORTED_KEYBOARDS).contains(&rules));
     }
 }
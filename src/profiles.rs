@@ -0,0 +1,190 @@
+//! The profiles popup: pick, save, rename or delete named setups.
+//!
+//! Key handling only produces a [`ProfileCommand`]; the App owns the config
+//! and the hardware worker, so it carries the command out.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::keymap::Action;
+
+/// Long enough for "Gaming (plugged in)", short enough for the popup.
+const PROFILE_NAME_MAX: usize = 24;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ProfileCommand {
+    Apply(usize),
+    SaveAs(String),
+    Rename(usize, String),
+    /// First `d`; the App asks for a second one.
+    ArmDelete(usize),
+    Delete(usize),
+    Close,
+}
+
+/// A name being typed, for a new profile or to rename `target`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ProfileNaming {
+    pub(crate) buffer: String,
+    pub(crate) target: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProfilesPopup {
+    pub(crate) selected: usize,
+    pub(crate) naming: Option<ProfileNaming>,
+    delete_armed: Option<usize>,
+}
+
+impl ProfilesPopup {
+    /// `names` are the saved profiles in order; `action` is what the keymap
+    /// resolved `key` to, so navigation follows custom bindings.
+    pub(crate) fn on_key(
+        &mut self,
+        key: KeyEvent,
+        action: Option<Action>,
+        names: &[String],
+    ) -> Option<ProfileCommand> {
+        if self.naming.is_some() {
+            return self.on_naming_key(key);
+        }
+
+        let armed = self.delete_armed.take();
+        self.selected = self.selected.min(names.len().saturating_sub(1));
+
+        match (action, key.code) {
+            (Some(Action::Up), _) if !names.is_empty() => {
+                self.selected = self.selected.checked_sub(1).unwrap_or(names.len() - 1);
+                None
+            }
+            (Some(Action::Down), _) if !names.is_empty() => {
+                self.selected = (self.selected + 1) % names.len();
+                None
+            }
+            (Some(Action::Apply), _) if !names.is_empty() => {
+                Some(ProfileCommand::Apply(self.selected))
+            }
+            (Some(Action::Cancel | Action::Profiles | Action::Quit), _) => {
+                Some(ProfileCommand::Close)
+            }
+            (_, KeyCode::Char('s')) => {
+                self.naming = Some(ProfileNaming::default());
+                None
+            }
+            (_, KeyCode::Char('n')) if !names.is_empty() => {
+                self.naming = Some(ProfileNaming {
+                    buffer: names[self.selected].clone(),
+                    target: Some(self.selected),
+                });
+                None
+            }
+            (_, KeyCode::Char('d')) if !names.is_empty() => {
+                if armed == Some(self.selected) {
+                    Some(ProfileCommand::Delete(self.selected))
+                } else {
+                    self.delete_armed = Some(self.selected);
+                    Some(ProfileCommand::ArmDelete(self.selected))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn on_naming_key(&mut self, key: KeyEvent) -> Option<ProfileCommand> {
+        let naming = self.naming.as_mut()?;
+
+        match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                    && naming.buffer.chars().count() < PROFILE_NAME_MAX =>
+            {
+                naming.buffer.push(c);
+                None
+            }
+            KeyCode::Backspace => {
+                naming.buffer.pop();
+                None
+            }
+            KeyCode::Esc => {
+                self.naming = None;
+                None
+            }
+            KeyCode::Enter => {
+                let name = naming.buffer.trim().to_string();
+                if name.is_empty() {
+                    return None;
+                }
+                let target = naming.target;
+                self.naming = None;
+                Some(match target {
+                    Some(index) => ProfileCommand::Rename(index, name),
+                    None => ProfileCommand::SaveAs(name),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn popup_keys_produce_commands() {
+        let names = ["Library".to_string(), "Gaming".to_string()];
+        let mut popup = ProfilesPopup::default();
+
+        assert_eq!(
+            popup.on_key(key(KeyCode::Down), Some(Action::Down), &names),
+            None
+        );
+        assert_eq!(
+            popup.on_key(key(KeyCode::Enter), Some(Action::Apply), &names),
+            Some(ProfileCommand::Apply(1))
+        );
+
+        // Delete needs two presses in a row.
+        let d = key(KeyCode::Char('d'));
+        assert_eq!(
+            popup.on_key(d, None, &names),
+            Some(ProfileCommand::ArmDelete(1))
+        );
+        assert_eq!(
+            popup.on_key(key(KeyCode::Up), Some(Action::Up), &names),
+            None
+        );
+        assert_eq!(
+            popup.on_key(d, None, &names),
+            Some(ProfileCommand::ArmDelete(0))
+        );
+        assert_eq!(
+            popup.on_key(d, None, &names),
+            Some(ProfileCommand::Delete(0))
+        );
+
+        // Renaming starts from the current name; 'q' is text, not quit.
+        popup.on_key(key(KeyCode::Char('n')), None, &names);
+        for code in [KeyCode::Backspace, KeyCode::Char('q')] {
+            assert_eq!(popup.on_key(key(code), Some(Action::Quit), &names), None);
+        }
+        assert_eq!(
+            popup.on_key(key(KeyCode::Enter), Some(Action::Apply), &names),
+            Some(ProfileCommand::Rename(0, "Librarq".to_string()))
+        );
+
+        popup.on_key(key(KeyCode::Char('s')), None, &names);
+        assert_eq!(popup.on_key(key(KeyCode::Enter), None, &names), None);
+        assert!(popup.naming.is_some(), "empty names are not accepted");
+        popup.on_key(key(KeyCode::Esc), Some(Action::Cancel), &names);
+        assert_eq!(
+            popup.on_key(key(KeyCode::Esc), Some(Action::Cancel), &names),
+            Some(ProfileCommand::Close)
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Per-model differences, matched on the DMI strings the firmware reports.
+//!
+//! Unknown machines get `DEFAULT_QUIRKS`, which is the PH16-71 behaviour the
+//! tool was written against. Their DMI strings are shown so they can be
+//! reported and added to `MODEL_QUIRKS`.
+
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::constants::{sysfs, NITRO_BASE};
+use crate::models::ControlId;
+use crate::rgb_protocol::{KeyboardModel, RGB_EFFECTS, SUPPORTED_KEYBOARDS};
+
+const DMI_DIR: &str = "/sys/class/dmi/id";
+
+/// The thresholds every predator_sense build so far accepts.
+pub(crate) const USB_CHARGING_THRESHOLDS: &[u8] = &[0, 10, 20, 30];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ModelQuirks {
+    pub(crate) name: &'static str,
+    /// Case-insensitive substrings of `product_name` or `board_name`.
+    pub(crate) dmi_match: &'static [&'static str],
+    /// Keyboards probed before `SUPPORTED_KEYBOARDS`.
+    pub(crate) keyboards: &'static [KeyboardModel],
+    pub(crate) usb_charging: bool,
+    /// `usb_charging` percentages used when the driver does not list its
+    /// own in `usb_charging_choices`; 0 is off.
+    pub(crate) usb_charging_thresholds: &'static [u8],
+    /// Sense attribute directory tried before the usual candidates.
+    pub(crate) sense_base: Option<&'static str>,
+    /// `RGB_EFFECTS` names the keyboard firmware does not implement; they
+    /// are skipped when cycling effects.
+    pub(crate) missing_effects: &'static [&'static str],
+    pub(crate) fan_control: FanControl,
+}
+
+/// What the numbers in `fan_speed` mean. The app works in percentages
+/// either way; values are converted when the attribute is read or written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum FanControl {
+    /// 0 is auto, 1–100 a fixed percentage.
+    #[default]
+    Percent,
+    /// 0 is auto, 1 to N are the firmware's fixed speeds and N is full.
+    Levels(u8),
+}
+
+impl FanControl {
+    /// `fan_levels` from config.json; 0 or 100 levels are just percentages.
+    pub(crate) fn from_levels(levels: u8) -> Self {
+        match levels {
+            1..=99 => Self::Levels(levels),
+            _ => Self::Percent,
+        }
+    }
+
+    /// The `fan_speed` number for `percent`. A manual speed never rounds
+    /// down to auto.
+    pub(crate) fn to_raw(self, percent: u8) -> u8 {
+        let percent = percent.min(100);
+        match self {
+            Self::Percent => percent,
+            Self::Levels(_) if percent == 0 => 0,
+            Self::Levels(levels) => {
+                let level = (u16::from(percent) * u16::from(levels) + 50) / 100;
+                level.clamp(1, u16::from(levels)) as u8
+            }
+        }
+    }
+
+    /// The percentage shown for a `fan_speed` number.
+    pub(crate) fn to_percent(self, raw: u8) -> u8 {
+        match self {
+            Self::Percent => raw.min(100),
+            Self::Levels(levels) => {
+                let level = u16::from(raw.min(levels));
+                ((level * 100 + u16::from(levels) / 2) / u16::from(levels)) as u8
+            }
+        }
+    }
+
+    /// Percentages the fan rows step through after Auto.
+    pub(crate) fn steps(self, percent_step: u8) -> Vec<u8> {
+        match self {
+            Self::Percent => (percent_step..=100).step_by(percent_step.into()).collect(),
+            Self::Levels(levels) => (1..=levels).map(|level| self.to_percent(level)).collect(),
+        }
+    }
+
+    /// Row label for a manual `percent`.
+    pub(crate) fn label(self, percent: u8) -> String {
+        match self {
+            Self::Percent => format!("{percent}%"),
+            Self::Levels(_) if percent >= 100 => "Max".to_string(),
+            Self::Levels(_) => format!("Level {}", self.to_raw(percent)),
+        }
+    }
+
+    pub(crate) fn describe(self) -> String {
+        match self {
+            Self::Percent => "percentages".to_string(),
+            Self::Levels(levels) => format!("{levels} levels"),
+        }
+    }
+}
+
+impl ModelQuirks {
+    pub(crate) fn offers(&self, id: ControlId) -> bool {
+        id != ControlId::UsbCharging || self.usb_charging
+    }
+
+    /// Whether `RGB_EFFECTS[index]` can be sent to this model's keyboard.
+    pub(crate) fn offers_effect(&self, index: usize) -> bool {
+        RGB_EFFECTS
+            .get(index)
+            .is_some_and(|effect| !self.missing_effects.contains(&effect.name))
+    }
+}
+
+pub(crate) const DEFAULT_QUIRKS: ModelQuirks = ModelQuirks {
+    name: "Acer Predator",
+    dmi_match: &[],
+    keyboards: &[],
+    usb_charging: true,
+    usb_charging_thresholds: USB_CHARGING_THRESHOLDS,
+    sense_base: None,
+    missing_effects: &[],
+    fan_control: FanControl::Percent,
+};
+
+pub(crate) const MODEL_QUIRKS: &[ModelQuirks] = &[
+    ModelQuirks {
+        name: "Predator Helios 16 (PH16-71)",
+        dmi_match: &["PH16-71"],
+        keyboards: &[SUPPORTED_KEYBOARDS[0]],
+        usb_charging: true,
+        usb_charging_thresholds: USB_CHARGING_THRESHOLDS,
+        sense_base: None,
+        missing_effects: &[],
+        fan_control: FanControl::Percent,
+    },
+    ModelQuirks {
+        name: "Predator Helios 18 (PH18-71)",
+        dmi_match: &["PH18-71"],
+        keyboards: &[SUPPORTED_KEYBOARDS[0]],
+        usb_charging: true,
+        usb_charging_thresholds: USB_CHARGING_THRESHOLDS,
+        sense_base: None,
+        missing_effects: &[],
+        fan_control: FanControl::Percent,
+    },
+    ModelQuirks {
+        name: "Predator Helios Neo 16 (PHN16-71)",
+        dmi_match: &["PHN16-71"],
+        keyboards: &[],
+        usb_charging: true,
+        usb_charging_thresholds: USB_CHARGING_THRESHOLDS,
+        sense_base: None,
+        missing_effects: &[],
+        fan_control: FanControl::Percent,
+    },
+    ModelQuirks {
+        name: "Nitro 5 (AN515-58)",
+        dmi_match: &["AN515-58"],
+        keyboards: &[],
+        usb_charging: false,
+        usb_charging_thresholds: USB_CHARGING_THRESHOLDS,
+        sense_base: Some(NITRO_BASE),
+        missing_effects: &[],
+        fan_control: FanControl::Percent,
+    },
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DmiInfo {
+    pub(crate) product_name: String,
+    pub(crate) board_name: String,
+}
+
+impl DmiInfo {
+    /// Missing or unreadable files read as empty strings.
+    pub(crate) fn read_from(dir: &Path) -> Self {
+        let field = |name: &str| {
+            fs::read_to_string(dir.join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        Self {
+            product_name: field("product_name"),
+            board_name: field("board_name"),
+        }
+    }
+
+    pub(crate) fn label(&self) -> String {
+        match (self.product_name.is_empty(), self.board_name.is_empty()) {
+            (true, true) => "no DMI strings".to_string(),
+            _ => format!(
+                "product '{}', board '{}'",
+                self.product_name, self.board_name
+            ),
+        }
+    }
+}
+
+pub(crate) fn lookup(dmi: &DmiInfo) -> Option<&'static ModelQuirks> {
+    let product = dmi.product_name.to_ascii_uppercase();
+    let board = dmi.board_name.to_ascii_uppercase();
+
+    MODEL_QUIRKS.iter().find(|quirks| {
+        quirks.dmi_match.iter().any(|pattern| {
+            let pattern = pattern.to_ascii_uppercase();
+            product.contains(&pattern) || board.contains(&pattern)
+        })
+    })
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ModelDetection {
+    pub(crate) dmi: DmiInfo,
+    pub(crate) known: Option<&'static ModelQuirks>,
+}
+
+impl ModelDetection {
+    pub(crate) fn quirks(&self) -> &'static ModelQuirks {
+        self.known.unwrap_or(&DEFAULT_QUIRKS)
+    }
+
+    /// Name for the header; unknown machines show their product name.
+    pub(crate) fn label(&self) -> String {
+        match self.known {
+            Some(quirks) => quirks.name.to_string(),
+            None if !self.dmi.product_name.is_empty() => self.dmi.product_name.clone(),
+            None => DEFAULT_QUIRKS.name.to_string(),
+        }
+    }
+
+    /// Startup note for machines missing from `MODEL_QUIRKS`.
+    pub(crate) fn unknown_warning(&self) -> Option<String> {
+        self.known.is_none().then(|| {
+            format!(
+                "Unknown model ({}); using PH16-71 defaults. Please report these strings",
+                self.dmi.label()
+            )
+        })
+    }
+}
+
+/// The running machine, read once: DMI strings do not change while we run.
+pub(crate) fn detected() -> &'static ModelDetection {
+    static DETECTED: OnceLock<ModelDetection> = OnceLock::new();
+    DETECTED.get_or_init(|| {
+        // Tests see an unknown model, whatever machine or fake tree the
+        // first caller happens to run on.
+        let dmi = if cfg!(test) {
+            DmiInfo::default()
+        } else {
+            DmiInfo::read_from(Path::new(&sysfs(DMI_DIR)))
+        };
+        let known = lookup(&dmi);
+        ModelDetection { dmi, known }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dmi(product: &str, board: &str) -> DmiInfo {
+        DmiInfo {
+            product_name: product.to_string(),
+            board_name: board.to_string(),
+        }
+    }
+
+    #[test]
+    fn lookup_matches_product_or_board_ignoring_case() {
+        let helios = lookup(&dmi("Predator PH16-71", "")).unwrap();
+        assert_eq!(helios.name, "Predator Helios 16 (PH16-71)");
+        assert_eq!(
+            lookup(&dmi("", "ph18-71_rpl")).map(|quirks| quirks.name),
+            Some("Predator Helios 18 (PH18-71)")
+        );
+
+        let nitro = lookup(&dmi("Nitro AN515-58", "Ray_ADS")).unwrap();
+        assert!(!nitro.offers(ControlId::UsbCharging));
+        assert!(nitro.offers(ControlId::CpuFan));
+    }
+
+    #[test]
+    fn missing_effects_are_matched_by_name() {
+        let quirks = ModelQuirks {
+            missing_effects: &["Snake", "Heartbeat"],
+            ..DEFAULT_QUIRKS
+        };
+        let offered: Vec<&str> = (0..RGB_EFFECTS.len())
+            .filter(|&index| quirks.offers_effect(index))
+            .map(|index| RGB_EFFECTS[index].name)
+            .collect();
+
+        assert_eq!(offered.len(), RGB_EFFECTS.len() - 2);
+        assert!(!offered.contains(&"Snake") && offered.contains(&"Static"));
+        assert!(!quirks.offers_effect(RGB_EFFECTS.len()));
+    }
+
+    #[test]
+    fn fan_levels_convert_to_and_from_percentages() {
+        let levels = FanControl::Levels(4);
+
+        assert_eq!(levels.steps(10), [25, 50, 75, 100]);
+        assert_eq!(
+            (0..=4)
+                .map(|raw| levels.to_percent(raw))
+                .collect::<Vec<_>>(),
+            [0, 25, 50, 75, 100]
+        );
+        // Presets land on the nearest level; a slow manual speed stays manual.
+        assert_eq!(levels.to_raw(0), 0);
+        assert_eq!(levels.to_raw(10), 1);
+        assert_eq!(levels.to_raw(70), 3);
+        assert_eq!(levels.to_raw(100), 4);
+        for raw in 0..=4 {
+            assert_eq!(levels.to_raw(levels.to_percent(raw)), raw);
+        }
+        assert_eq!(levels.to_percent(9), 100);
+        assert_eq!(levels.label(50), "Level 2");
+        assert_eq!(levels.label(100), "Max");
+
+        assert_eq!(FanControl::Percent.to_raw(70), 70);
+        assert_eq!(FanControl::Percent.to_percent(70), 70);
+        assert_eq!(FanControl::Percent.steps(10).len(), 10);
+        assert_eq!(FanControl::from_levels(0), FanControl::Percent);
+        assert_eq!(FanControl::from_levels(3), FanControl::Levels(3));
+    }
+
+    #[test]
+    fn unknown_models_fall_back_and_report_dmi_strings() {
+        let detection = ModelDetection {
+            dmi: dmi("Aspire A715-42G", "Azalea_CAS"),
+            known: None,
+        };
+
+        assert_eq!(detection.quirks(), &DEFAULT_QUIRKS);
+        assert_eq!(detection.label(), "Aspire A715-42G");
+        let warning = detection.unknown_warning().unwrap();
+        assert!(warning.contains("product 'Aspire A715-42G', board 'Azalea_CAS'"));
+    }
+
+    #[test]
+    fn dmi_fields_are_trimmed_and_missing_ones_empty() {
+        let dir = std::env::temp_dir().join(format!("arch-sense-dmi-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("product_name"), "Predator PH16-71\n").unwrap();
+
+        let info = DmiInfo::read_from(&dir);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(info, dmi("Predator PH16-71", ""));
+        assert_eq!(DmiInfo::default().label(), "no DMI strings");
+    }
+}
@@ -0,0 +1,828 @@
+//! Keyboard RGB USB protocol for Acer Predator keyboards (see `SUPPORTED_KEYBOARDS`).
+//!
+//! Every transfer is an 8-byte HID feature report sent with SET_REPORT:
+//!
+//! 1. `PREAMBLE` resets the controller's command state.
+//! 2. Colour-capable effects with a fixed colour first load it with a
+//!    `0x14` packet: `[0x14, 0x00, 0x00, R, G, B, 0x00, 0x00]`.
+//! 3. The `0x08` effect packet selects the animation:
+//!    `[0x08, 0x02, opcode, speed, brightness, colour preset, direction, 0x9B]`
+//!    where speed runs 1 (fast) to 9 (slow), brightness 0 to 0x32, and the
+//!    colour preset is 0x01 for the loaded colour or 0x08 for random.
+//!
+//! The Zones mode replaces step 2 with `FRAME_HEADER` followed by a
+//! `FRAME_LEN`-byte framebuffer written to the interrupt OUT endpoint, one
+//! quarter per zone, and selects it with the user-frame opcode 0x33.
+
+use crate::models::RgbSettings;
+
+// RGB protocol limits
+pub(crate) const BRIGHT_HW_MAX: u8 = 50; // 0x32
+pub(crate) const SPEED_HW_FAST: u8 = 1;
+pub(crate) const SPEED_HW_SLOW: u8 = 9;
+pub(crate) const PREAMBLE: [u8; 8] = [0xB1, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x4E];
+/// Static effect at zero brightness; the firmware has no dedicated "off" opcode.
+pub(crate) const OFF_PACKET: [u8; 8] = [0x08, 0x02, 0x01, 0x00, 0x00, 0x01, 0x01, 0x9B];
+
+/// Announces a framebuffer on the interrupt endpoint.
+pub(crate) const FRAME_HEADER: [u8; 8] = [0x12, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0xE5];
+pub(crate) const FRAME_LEN: usize = 1024;
+/// Left to right across the keyboard; each owns a quarter of the framebuffer.
+pub(crate) const ZONE_COUNT: usize = 4;
+
+const COLOR_PRESET_LOADED: u8 = 0x01;
+const COLOR_PRESET_RANDOM: u8 = 0x08;
+
+/// A USB keyboard that speaks this protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct KeyboardModel {
+    pub(crate) vid: u16,
+    pub(crate) pid: u16,
+    pub(crate) interface: u8,
+    pub(crate) endpoint: u8,
+    pub(crate) name: &'static str,
+}
+
+impl KeyboardModel {
+    pub(crate) fn id_label(&self) -> String {
+        format!("{:04X}:{:04X}", self.vid, self.pid)
+    }
+}
+
+/// Keyboards probed in order; the first one found on the bus is used.
+pub(crate) const SUPPORTED_KEYBOARDS: &[KeyboardModel] = &[KeyboardModel {
+    vid: 0x04F2,
+    pid: 0x0117,
+    interface: 3,
+    endpoint: 0x04,
+    name: "Predator PH16-71",
+}];
+
+/// Comma-separated `VID:PID` list for "not found" messages.
+pub(crate) fn probed_label(models: &[KeyboardModel]) -> String {
+    models
+        .iter()
+        .map(KeyboardModel::id_label)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Rgb {
+    pub(crate) r: u8,
+    pub(crate) g: u8,
+    pub(crate) b: u8,
+}
+
+impl Rgb {
+    /// Parses `RGB` or `RRGGBB`, with or without a leading `#`.
+    pub(crate) fn from_hex(input: &str) -> Option<Self> {
+        let digits = input.trim().trim_start_matches('#');
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |hex: &str| u8::from_str_radix(hex, 16).ok();
+
+        match digits.len() {
+            3 => {
+                let short = |i: usize| channel(&digits[i..=i]).map(|value| value * 17);
+                Some(Self {
+                    r: short(0)?,
+                    g: short(1)?,
+                    b: short(2)?,
+                })
+            }
+            6 => Some(Self {
+                r: channel(&digits[0..2])?,
+                g: channel(&digits[2..4])?,
+                b: channel(&digits[4..6])?,
+            }),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+
+    /// `hue` in degrees, `saturation` and `value` in percent.
+    pub(crate) fn from_hsv(hue: u16, saturation: u8, value: u8) -> Self {
+        let s = f64::from(saturation.min(100)) / 100.0;
+        let v = f64::from(value.min(100)) / 100.0;
+        let h = f64::from(hue % 360) / 60.0;
+        let c = v * s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = v - c;
+        let byte = |channel: f64| ((channel + m) * 255.0).round() as u8;
+
+        Self {
+            r: byte(r),
+            g: byte(g),
+            b: byte(b),
+        }
+    }
+
+    /// Inverse of `from_hsv`, rounded to whole degrees and percent.
+    pub(crate) fn to_hsv(self) -> (u16, u8, u8) {
+        let [r, g, b] = [self.r, self.g, self.b].map(|channel| f64::from(channel) / 255.0);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+        (
+            hue.round() as u16 % 360,
+            (saturation * 100.0).round() as u8,
+            (max * 100.0).round() as u8,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ColorDef {
+    pub(crate) name: &'static str,
+    pub(crate) rgb: Rgb,
+}
+
+pub(crate) const COLOR_PALETTE: [ColorDef; 11] = [
+    ColorDef {
+        name: "Red",
+        rgb: Rgb {
+            r: 255,
+            g: 70,
+            b: 70,
+        },
+    },
+    ColorDef {
+        name: "Orange",
+        rgb: Rgb {
+            r: 255,
+            g: 142,
+            b: 45,
+        },
+    },
+    ColorDef {
+        name: "Gold",
+        rgb: Rgb {
+            r: 250,
+            g: 204,
+            b: 21,
+        },
+    },
+    ColorDef {
+        name: "Emerald",
+        rgb: Rgb {
+            r: 52,
+            g: 211,
+            b: 153,
+        },
+    },
+    ColorDef {
+        name: "Cyan",
+        rgb: Rgb {
+            r: 34,
+            g: 211,
+            b: 238,
+        },
+    },
+    ColorDef {
+        name: "Blue",
+        rgb: Rgb {
+            r: 96,
+            g: 165,
+            b: 250,
+        },
+    },
+    ColorDef {
+        name: "Violet",
+        rgb: Rgb {
+            r: 167,
+            g: 139,
+            b: 250,
+        },
+    },
+    ColorDef {
+        name: "Magenta",
+        rgb: Rgb {
+            r: 232,
+            g: 121,
+            b: 249,
+        },
+    },
+    ColorDef {
+        name: "Pink",
+        rgb: Rgb {
+            r: 244,
+            g: 114,
+            b: 182,
+        },
+    },
+    ColorDef {
+        name: "White",
+        rgb: Rgb {
+            r: 255,
+            g: 255,
+            b: 255,
+        },
+    },
+    ColorDef {
+        name: "Random",
+        rgb: Rgb { r: 0, g: 0, b: 0 },
+    },
+];
+
+pub(crate) const RANDOM_COLOR_INDEX: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct RgbEffect {
+    pub(crate) name: &'static str,
+    pub(crate) opcode: u8,
+    pub(crate) has_color: bool,
+    pub(crate) has_direction: bool,
+}
+
+pub(crate) const RGB_EFFECTS: [RgbEffect; 16] = [
+    RgbEffect {
+        name: "Off",
+        opcode: 0x01,
+        has_color: false,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Static",
+        opcode: 0x01,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Breathing",
+        opcode: 0x02,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Wave",
+        opcode: 0x03,
+        has_color: false,
+        has_direction: true,
+    },
+    RgbEffect {
+        name: "Snake",
+        opcode: 0x05,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Ripple",
+        opcode: 0x06,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Rainbow",
+        opcode: 0x08,
+        has_color: false,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Rain",
+        opcode: 0x0A,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Lightning",
+        opcode: 0x12,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Spot",
+        opcode: 0x25,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Stars",
+        opcode: 0x26,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Fireball",
+        opcode: 0x27,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Snow",
+        opcode: 0x28,
+        has_color: true,
+        has_direction: false,
+    },
+    RgbEffect {
+        name: "Heartbeat",
+        opcode: 0x29,
+        has_color: true,
+        has_direction: false,
+    },
+    // Colours come from the zone framebuffer, not a loaded colour.
+    RgbEffect {
+        name: "Zones",
+        opcode: 0x33,
+        has_color: false,
+        has_direction: false,
+    },
+    // Sent as Static in a colour picked from the temperature.
+    RgbEffect {
+        name: "Thermal",
+        opcode: 0x01,
+        has_color: false,
+        has_direction: false,
+    },
+];
+
+pub(crate) const OFF_EFFECT_INDEX: usize = 0;
+pub(crate) const STATIC_EFFECT_INDEX: usize = 1;
+pub(crate) const BREATHING_EFFECT_INDEX: usize = 2;
+pub(crate) const ZONES_EFFECT_INDEX: usize = 14;
+pub(crate) const THERMAL_EFFECT_INDEX: usize = 15;
+/// The Thermal effect shows its low colour up to here...
+pub(crate) const THERMAL_LOW_C: u64 = 60;
+/// ...and its high colour from here on.
+pub(crate) const THERMAL_HIGH_C: u64 = 80;
+pub(crate) const DIRECTIONS: [&str; 6] = ["Right", "Left", "Up", "Down", "Clockwise", "Counter-CW"];
+
+/// Colour of the Thermal effect at `temp` °C. Between the two thresholds
+/// the hue turns the short way round, so green to red passes through
+/// yellow instead of a dim olive. Without a reading it shows `low`.
+pub(crate) fn thermal_color(low: Rgb, high: Rgb, temp: Option<u64>) -> Rgb {
+    let temp = temp.unwrap_or(0);
+    if temp <= THERMAL_LOW_C {
+        return low;
+    }
+    if temp >= THERMAL_HIGH_C {
+        return high;
+    }
+
+    let t = (temp - THERMAL_LOW_C) as f64 / (THERMAL_HIGH_C - THERMAL_LOW_C) as f64;
+    let (low_hue, low_sat, low_val) = low.to_hsv();
+    let (high_hue, high_sat, high_val) = high.to_hsv();
+    let mut turn = f64::from(high_hue) - f64::from(low_hue);
+    if turn > 180.0 {
+        turn -= 360.0;
+    } else if turn < -180.0 {
+        turn += 360.0;
+    }
+    let hue = (f64::from(low_hue) + turn * t).rem_euclid(360.0).round() as u16;
+    let lerp = |from: u8, to: u8| (f64::from(from) * (1.0 - t) + f64::from(to) * t).round() as u8;
+
+    Rgb::from_hsv(hue, lerp(low_sat, high_sat), lerp(low_val, high_val))
+}
+
+/// Intermediate frames of a fade between two solid colours.
+pub(crate) const FADE_STEPS: usize = 8;
+
+/// The frames between `from` and `to`, both excluded, when both are Static
+/// in a fixed colour. Colour and brightness move together in a straight
+/// line; animated effects and random colours switch instantly (`None`).
+pub(crate) fn fade_frames(from: &RgbSettings, to: &RgbSettings) -> Option<Vec<RgbSettings>> {
+    let solid = |settings: &RgbSettings| {
+        settings.effect_idx == STATIC_EFFECT_INDEX && !settings.is_random_color()
+    };
+    if !solid(from) || !solid(to) || from == to {
+        return None;
+    }
+
+    let (start, end) = (from.color_rgb(), to.color_rgb());
+    let frames = (1..=FADE_STEPS)
+        .map(|step| {
+            let t = step as f64 / (FADE_STEPS + 1) as f64;
+            let lerp = |a: u8, b: u8| (f64::from(a) * (1.0 - t) + f64::from(b) * t).round() as u8;
+            RgbSettings {
+                custom: Some(Rgb {
+                    r: lerp(start.r, end.r),
+                    g: lerp(start.g, end.g),
+                    b: lerp(start.b, end.b),
+                }),
+                brightness: lerp(from.brightness, to.brightness),
+                ..*to
+            }
+        })
+        .collect();
+    Some(frames)
+}
+
+/// Maps a 0-100 brightness percentage onto the hardware range 0..=0x32.
+pub(crate) fn hardware_brightness(percent: u8) -> u8 {
+    (percent.min(100) as u16 * BRIGHT_HW_MAX as u16 / 100) as u8
+}
+
+/// Maps a 0-100 speed percentage onto the hardware range, where 1 is the
+/// fastest animation and 9 the slowest.
+pub(crate) fn hardware_speed(percent: u8) -> u8 {
+    if percent >= 100 {
+        return SPEED_HW_FAST;
+    }
+
+    let range = (SPEED_HW_SLOW - SPEED_HW_FAST) as u16;
+    (SPEED_HW_SLOW - (percent as u16 * range / 100) as u8).max(SPEED_HW_FAST)
+}
+
+pub(crate) fn color_packet(color: Rgb) -> [u8; 8] {
+    [0x14, 0x00, 0x00, color.r, color.g, color.b, 0x00, 0x00]
+}
+
+pub(crate) fn effect_packet(settings: &RgbSettings) -> [u8; 8] {
+    let effect = settings.effect();
+    let color_preset = if settings.is_random_color() {
+        COLOR_PRESET_RANDOM
+    } else {
+        COLOR_PRESET_LOADED
+    };
+    let direction = if effect.has_direction {
+        settings.direction_idx as u8 + 1
+    } else {
+        0x01
+    };
+
+    [
+        0x08,
+        0x02,
+        effect.opcode,
+        hardware_speed(settings.speed),
+        hardware_brightness(settings.brightness),
+        color_preset,
+        direction,
+        0x9B,
+    ]
+}
+
+/// One write to the keyboard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Transfer {
+    /// 8-byte feature report sent with SET_REPORT.
+    Report([u8; 8]),
+    /// Zone framebuffer written to the interrupt OUT endpoint.
+    Frame(Vec<u8>),
+}
+
+/// `FRAME_LEN` bytes of `[R, G, B, 0x00]` LED slots, one quarter per zone.
+pub(crate) fn zone_frame(zones: &[Rgb; ZONE_COUNT]) -> Vec<u8> {
+    let slots = FRAME_LEN / ZONE_COUNT / 4;
+    zones
+        .iter()
+        .flat_map(|rgb| [rgb.r, rgb.g, rgb.b, 0x00].repeat(slots))
+        .collect()
+}
+
+/// Every write that applies `settings`, in order.
+pub(crate) fn transfers(settings: &RgbSettings) -> Vec<Transfer> {
+    if !settings.is_zones() {
+        return packets(settings)
+            .into_iter()
+            .map(Transfer::Report)
+            .collect();
+    }
+
+    vec![
+        Transfer::Report(PREAMBLE),
+        Transfer::Report(FRAME_HEADER),
+        Transfer::Frame(zone_frame(&settings.zones)),
+        Transfer::Report(effect_packet(settings)),
+    ]
+}
+
+/// Packet sequence for effects that need no framebuffer, preamble included.
+pub(crate) fn packets(settings: &RgbSettings) -> Vec<[u8; 8]> {
+    if settings.effect_idx == OFF_EFFECT_INDEX {
+        return vec![PREAMBLE, OFF_PACKET];
+    }
+
+    let mut packets = vec![PREAMBLE];
+    if settings.effect().has_color && !settings.is_random_color() {
+        packets.push(color_packet(settings.color_rgb()));
+    }
+    packets.push(effect_packet(settings));
+    packets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RgbConfig;
+
+    fn settings() -> RgbSettings {
+        RgbSettings::from_config(&RgbConfig::default())
+    }
+
+    #[test]
+    fn effect_packet_maps_brightness_and_speed_to_hardware_ranges() {
+        let mut settings = settings();
+        settings.brightness = 100;
+        settings.speed = 0;
+
+        let packet = effect_packet(&settings);
+
+        assert_eq!(packet[3], SPEED_HW_SLOW);
+        assert_eq!(packet[4], BRIGHT_HW_MAX);
+    }
+
+    #[test]
+    fn effect_packet_bytes_are_pinned_for_speed_and_brightness() {
+        let mut settings = settings();
+        settings.effect_idx = BREATHING_EFFECT_INDEX;
+
+        // Byte 3 is speed (1 fastest, 9 slowest), byte 4 is brightness.
+        for (speed, brightness, expected) in [
+            (0, 0, [0x08, 0x02, 0x02, 0x09, 0x00, 0x01, 0x01, 0x9B]),
+            (50, 0, [0x08, 0x02, 0x02, 0x05, 0x00, 0x01, 0x01, 0x9B]),
+            (100, 0, [0x08, 0x02, 0x02, 0x01, 0x00, 0x01, 0x01, 0x9B]),
+            (0, 50, [0x08, 0x02, 0x02, 0x09, 0x19, 0x01, 0x01, 0x9B]),
+            (50, 50, [0x08, 0x02, 0x02, 0x05, 0x19, 0x01, 0x01, 0x9B]),
+            (100, 100, [0x08, 0x02, 0x02, 0x01, 0x32, 0x01, 0x01, 0x9B]),
+        ] {
+            settings.speed = speed;
+            settings.brightness = brightness;
+
+            assert_eq!(
+                effect_packet(&settings),
+                expected,
+                "speed {speed}%, brightness {brightness}%"
+            );
+        }
+    }
+
+    #[test]
+    fn brightness_lands_in_effect_packet_without_dimming_the_color() {
+        let mut settings = settings();
+
+        for (percent, expected) in [(0, 0x00), (50, 0x19), (100, 0x32)] {
+            settings.brightness = percent;
+            let packets = packets(&settings);
+
+            assert_eq!(packets[1], [0x14, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0x00]);
+            assert_eq!(packets[2][4], expected, "brightness {percent}%");
+        }
+    }
+
+    #[test]
+    fn default_static_white_produces_exact_packets() {
+        assert_eq!(
+            packets(&settings()),
+            vec![
+                PREAMBLE,
+                [0x14, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x00, 0x00],
+                [0x08, 0x02, 0x01, 0x05, 0x0F, 0x01, 0x01, 0x9B],
+            ]
+        );
+    }
+
+    #[test]
+    fn wave_carries_direction_and_skips_color_load() {
+        let mut settings = settings();
+        settings.effect_idx = 3; // Wave
+        settings.direction_idx = 2; // Up
+        settings.speed = 100;
+        settings.brightness = 100;
+
+        assert_eq!(
+            packets(&settings),
+            vec![PREAMBLE, [0x08, 0x02, 0x03, 0x01, 0x32, 0x01, 0x03, 0x9B]]
+        );
+    }
+
+    #[test]
+    fn random_color_rain_uses_random_preset() {
+        let mut settings = settings();
+        settings.effect_idx = 7; // Rain
+        settings.color_idx = RANDOM_COLOR_INDEX;
+
+        assert_eq!(
+            packets(&settings),
+            vec![PREAMBLE, [0x08, 0x02, 0x0A, 0x05, 0x0F, 0x08, 0x01, 0x9B]]
+        );
+    }
+
+    #[test]
+    fn color_effects_load_color_before_effect_packet() {
+        for (index, effect) in RGB_EFFECTS
+            .iter()
+            .enumerate()
+            .take(ZONES_EFFECT_INDEX)
+            .skip(1)
+        {
+            let mut settings = settings();
+            settings.effect_idx = index;
+
+            let packets = packets(&settings);
+            let color_loaded = packets.iter().any(|packet| packet[0] == 0x14);
+
+            assert_eq!(color_loaded, effect.has_color, "{}", effect.name);
+            assert_eq!(packets.last().map(|packet| packet[0]), Some(0x08));
+            if effect.has_color {
+                assert_eq!(packets[1], color_packet(settings.color().rgb));
+            }
+        }
+    }
+
+    #[test]
+    fn random_color_skips_color_load_for_every_effect() {
+        for (index, effect) in RGB_EFFECTS
+            .iter()
+            .enumerate()
+            .take(ZONES_EFFECT_INDEX)
+            .skip(1)
+        {
+            let mut settings = settings();
+            settings.effect_idx = index;
+            settings.color_idx = RANDOM_COLOR_INDEX;
+
+            let packets = packets(&settings);
+
+            assert_eq!(packets.len(), 2, "{}", effect.name);
+            assert_eq!(packets[1][5], COLOR_PRESET_RANDOM);
+        }
+    }
+
+    #[test]
+    fn custom_color_overrides_palette_and_random() {
+        let mut settings = settings();
+        settings.color_idx = RANDOM_COLOR_INDEX;
+        settings.custom = Rgb::from_hex("#2EB8B8");
+
+        let packets = packets(&settings);
+        assert_eq!(packets[1], [0x14, 0x00, 0x00, 0x2E, 0xB8, 0xB8, 0x00, 0x00]);
+        assert_eq!(packets[2][5], COLOR_PRESET_LOADED);
+    }
+
+    #[test]
+    fn hex_and_hsv_colors_convert() {
+        let teal = Rgb::from_hex("2eb8b8").unwrap();
+        assert_eq!(teal.hex(), "#2EB8B8");
+        assert_eq!(
+            Rgb::from_hex("#F80"),
+            Some(Rgb {
+                r: 255,
+                g: 136,
+                b: 0
+            })
+        );
+        for bad in ["", "#12345", "GGHHII", "#1234567"] {
+            assert_eq!(Rgb::from_hex(bad), None, "{bad}");
+        }
+
+        assert_eq!(Rgb::from_hsv(0, 100, 100), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(Rgb::from_hsv(240, 100, 50), Rgb { r: 0, g: 0, b: 128 });
+        let (h, s, v) = teal.to_hsv();
+        assert_eq!((h, s, v), (180, 75, 72));
+        assert_eq!(Rgb::from_hsv(h, s, v).hex(), "#2EB8B8");
+    }
+
+    #[test]
+    fn zones_send_a_quartered_framebuffer_between_header_and_effect() {
+        let mut settings = settings();
+        settings.effect_idx = ZONES_EFFECT_INDEX;
+        settings.brightness = 100;
+        settings.zones = [
+            Rgb { r: 1, g: 2, b: 3 },
+            Rgb { r: 4, g: 5, b: 6 },
+            Rgb { r: 7, g: 8, b: 9 },
+            Rgb {
+                r: 10,
+                g: 11,
+                b: 12,
+            },
+        ];
+
+        let transfers = transfers(&settings);
+        assert_eq!(
+            transfers[..2],
+            [Transfer::Report(PREAMBLE), Transfer::Report(FRAME_HEADER)]
+        );
+        assert_eq!(
+            transfers[3],
+            Transfer::Report([0x08, 0x02, 0x33, 0x05, 0x32, 0x01, 0x01, 0x9B])
+        );
+
+        let Transfer::Frame(frame) = &transfers[2] else {
+            panic!("expected a framebuffer, got {:?}", transfers[2]);
+        };
+        assert_eq!(frame.len(), FRAME_LEN);
+        for (zone, quarter) in frame.chunks(FRAME_LEN / ZONE_COUNT).enumerate() {
+            let rgb = settings.zones[zone];
+            assert!(
+                quarter
+                    .chunks(4)
+                    .all(|slot| slot == [rgb.r, rgb.g, rgb.b, 0x00]),
+                "zone {zone}"
+            );
+        }
+    }
+
+    #[test]
+    fn other_effects_transfer_only_reports() {
+        assert_eq!(
+            transfers(&settings()),
+            packets(&settings())
+                .into_iter()
+                .map(Transfer::Report)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn rgb(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    #[test]
+    fn thermal_color_fades_between_thresholds() {
+        let green = rgb(0, 255, 0);
+        let red = rgb(255, 0, 0);
+        let yellow = rgb(255, 255, 0);
+        let orange = rgb(255, 128, 0);
+
+        assert_eq!(thermal_color(green, red, None), green);
+        assert_eq!(thermal_color(green, red, Some(45)), green);
+        assert_eq!(thermal_color(green, red, Some(THERMAL_LOW_C)), green);
+        assert_eq!(thermal_color(green, red, Some(70)), yellow);
+        assert_eq!(thermal_color(green, red, Some(75)), orange);
+        assert_eq!(thermal_color(green, red, Some(THERMAL_HIGH_C)), red);
+        assert_eq!(thermal_color(green, red, Some(95)), red);
+
+        // Hue takes the short way: red to magenta passes pink, not green.
+        let magenta = rgb(255, 0, 255);
+        let pink = rgb(255, 0, 128);
+        assert_eq!(thermal_color(red, magenta, Some(70)), pink);
+    }
+
+    #[test]
+    fn fades_run_only_between_solid_colours() {
+        let from = settings();
+        let to = RgbSettings {
+            custom: Some(rgb(0, 0, 255)),
+            brightness: 100,
+            ..from
+        };
+
+        let frames = fade_frames(&from, &to).unwrap();
+        assert_eq!(frames.len(), FADE_STEPS);
+        assert_eq!(frames[0].color_rgb(), rgb(227, 227, 255));
+        assert_eq!(frames[0].brightness, 38);
+        assert_eq!(frames[FADE_STEPS - 1].color_rgb(), rgb(28, 28, 255));
+        assert_eq!(frames[FADE_STEPS - 1].brightness, 92);
+        assert!(frames
+            .windows(2)
+            .all(|pair| pair[0].color_rgb().r > pair[1].color_rgb().r));
+
+        assert_eq!(fade_frames(&from, &from), None);
+        let mut wave = to;
+        wave.effect_idx = 3;
+        assert_eq!(fade_frames(&from, &wave), None);
+        let mut random = to;
+        random.custom = None;
+        random.color_idx = RANDOM_COLOR_INDEX;
+        assert_eq!(fade_frames(&random, &to), None);
+    }
+
+    #[test]
+    fn thermal_is_sent_as_static_in_the_tint() {
+        let mut settings = settings();
+        settings.effect_idx = THERMAL_EFFECT_INDEX;
+
+        let sent = settings.tinted(Some(90));
+        assert_eq!(sent.effect_idx, STATIC_EFFECT_INDEX);
+        assert_eq!(sent.color_rgb(), settings.thermal_high);
+        assert_eq!(packets(&sent)[1], color_packet(settings.thermal_high));
+        assert_eq!(settings.tinted(Some(30)).color_rgb(), settings.thermal_low);
+
+        let mut wave = settings;
+        wave.effect_idx = 3;
+        assert_eq!(wave.tinted(Some(90)), wave);
+    }
+
+    #[test]
+    fn off_sends_zero_brightness_static() {
+        let mut settings = settings();
+        settings.effect_idx = OFF_EFFECT_INDEX;
+
+        assert_eq!(packets(&settings), vec![PREAMBLE, OFF_PACKET]);
+    }
+}
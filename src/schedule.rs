@@ -0,0 +1,275 @@
+//! Time-of-day profile switching.
+//!
+//! Each entry in `schedules` names a saved profile and the local time (and
+//! optionally the weekdays) to apply it. Entries only fire when their time
+//! is crossed, so a change made by hand afterwards stays until the next
+//! entry comes round.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Schedule;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+const MINUTES_PER_WEEK: u16 = 7 * MINUTES_PER_DAY;
+/// A longer jump is a suspend of more than a day or the clock moving
+/// backwards; nothing is applied for it.
+const MAX_CATCH_UP: u16 = MINUTES_PER_DAY;
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const EVERY_DAY: u8 = 0b111_1111;
+const WEEKDAYS: u8 = 0b001_1111;
+const WEEKEND: u8 = 0b110_0000;
+
+/// Broken-down local time, or None when the C library cannot convert it.
+pub(crate) fn local_tm(at: SystemTime) -> Option<libc::tm> {
+    let secs = at
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default() as libc::time_t;
+    // SAFETY: localtime_r only writes into the tm we hand it, and an
+    // all-zero tm is a valid value to start from.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return None;
+    }
+    Some(tm)
+}
+
+/// Minutes since Monday 00:00, local time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct WeekMinute(u16);
+
+impl WeekMinute {
+    /// `day` counts from Monday = 0.
+    pub(crate) fn new(day: u8, hour: u8, minute: u8) -> Self {
+        let minutes = u16::from(day % 7) * MINUTES_PER_DAY + u16::from(hour) * 60;
+        Self(minutes + u16::from(minute))
+    }
+
+    pub(crate) fn now() -> Option<Self> {
+        let tm = local_tm(SystemTime::now())?;
+        // tm_wday counts from Sunday.
+        let day = (tm.tm_wday + 6) % 7;
+        Some(Self::new(day as u8, tm.tm_hour as u8, tm.tm_min as u8))
+    }
+
+    /// Minutes from `self` forward to `later`, across the week boundary.
+    fn until(self, later: Self) -> u16 {
+        (later.0 + MINUTES_PER_WEEK - self.0) % MINUTES_PER_WEEK
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Entry {
+    pub(crate) profile: String,
+    /// `HH:MM` as written in the config, for messages.
+    pub(crate) at: String,
+    minute: u16,
+    /// Bit 0 is Monday.
+    days: u8,
+}
+
+impl Entry {
+    pub(crate) fn parse(schedule: &Schedule) -> Result<Self, String> {
+        let minute = parse_time(&schedule.at)
+            .ok_or_else(|| format!("'{}' is not a HH:MM time", schedule.at))?;
+        let mut days = 0;
+        for day in &schedule.days {
+            days |= parse_days(day).ok_or_else(|| format!("'{day}' is not a day"))?;
+        }
+
+        Ok(Self {
+            profile: schedule.profile.clone(),
+            at: schedule.at.trim().to_string(),
+            minute,
+            days: if days == 0 { EVERY_DAY } else { days },
+        })
+    }
+
+    /// Every time this entry fires in the week.
+    fn fires(&self) -> impl Iterator<Item = WeekMinute> + '_ {
+        (0..7u16)
+            .filter(|day| self.days & (1 << day) != 0)
+            .map(|day| WeekMinute(day * MINUTES_PER_DAY + self.minute))
+    }
+}
+
+fn parse_time(at: &str) -> Option<u16> {
+    let (hour, minute) = at.trim().split_once(':')?;
+    let hour: u16 = hour.parse().ok().filter(|hour| *hour < 24)?;
+    let minute: u16 = minute.parse().ok().filter(|minute| *minute < 60)?;
+    Some(hour * 60 + minute)
+}
+
+fn parse_days(day: &str) -> Option<u8> {
+    let day = day.trim().to_ascii_lowercase();
+    match day.as_str() {
+        "weekdays" => Some(WEEKDAYS),
+        "weekend" => Some(WEEKEND),
+        _ => DAY_NAMES
+            .iter()
+            .position(|name| day.starts_with(name))
+            .map(|index| 1 << index),
+    }
+}
+
+/// Parses every entry, returning the valid ones and a warning per invalid one.
+pub(crate) fn parse_all(schedules: &[Schedule]) -> (Vec<Entry>, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut warnings = Vec::new();
+    for (index, schedule) in schedules.iter().enumerate() {
+        match Entry::parse(schedule) {
+            Ok(entry) => entries.push(entry),
+            Err(error) => warnings.push(format!("schedule {}: {error}", index + 1)),
+        }
+    }
+    (entries, warnings)
+}
+
+/// The entry whose time was crossed last going from `from` (exclusive) to
+/// `to` (inclusive), when there is one.
+pub(crate) fn due(entries: &[Entry], from: WeekMinute, to: WeekMinute) -> Option<&Entry> {
+    let span = from.until(to);
+    if span == 0 || span > MAX_CATCH_UP {
+        return None;
+    }
+    entries
+        .iter()
+        .flat_map(|entry| entry.fires().map(move |at| (from.until(at), entry)))
+        .filter(|(offset, _)| (1..=span).contains(offset))
+        .max_by_key(|(offset, _)| *offset)
+        .map(|(_, entry)| entry)
+}
+
+/// Remembers the last minute looked at, so each entry fires once when its
+/// time comes round.
+#[derive(Debug, Default)]
+pub(crate) struct ScheduleWatch {
+    entries: Vec<Entry>,
+    last: Option<WeekMinute>,
+}
+
+impl ScheduleWatch {
+    pub(crate) fn new(entries: Vec<Entry>) -> Self {
+        Self {
+            entries,
+            last: None,
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    /// The entry to apply now. The first call only starts the clock, so
+    /// starting the app never overrides the restored settings.
+    pub(crate) fn poll(&mut self, now: WeekMinute) -> Option<Entry> {
+        let last = self.last.replace(now)?;
+        due(&self.entries, last, now).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(at: &str, days: &[&str], profile: &str) -> Entry {
+        Entry::parse(&Schedule {
+            at: at.to_string(),
+            days: days.iter().map(|day| day.to_string()).collect(),
+            profile: profile.to_string(),
+        })
+        .unwrap()
+    }
+
+    fn fired(entries: &[Entry], from: WeekMinute, to: WeekMinute) -> Option<&str> {
+        due(entries, from, to).map(|entry| entry.profile.as_str())
+    }
+
+    #[test]
+    fn the_last_time_crossed_fires_including_across_midnight() {
+        let entries = [
+            entry("22:00", &[], "Night"),
+            entry("08:00", &["weekdays"], "Work"),
+            entry("10:00", &["sat", "Sunday"], "Weekend"),
+        ];
+        let (mon, tue, fri, sat, sun) = (0, 1, 4, 5, 6);
+
+        let at = WeekMinute::new;
+        assert_eq!(
+            fired(&entries, at(mon, 21, 59), at(mon, 22, 0)),
+            Some("Night")
+        );
+        assert_eq!(fired(&entries, at(mon, 22, 0), at(mon, 22, 1)), None);
+        assert_eq!(fired(&entries, at(mon, 7, 58), at(mon, 7, 59)), None);
+        assert_eq!(fired(&entries, at(mon, 7, 59), at(mon, 8, 0)), Some("Work"));
+        // Days filter: no Work on Saturday, Weekend instead.
+        assert_eq!(fired(&entries, at(sat, 7, 59), at(sat, 8, 0)), None);
+        assert_eq!(
+            fired(&entries, at(sat, 9, 59), at(sat, 10, 0)),
+            Some("Weekend")
+        );
+
+        // Asleep overnight: only the last entry crossed applies.
+        assert_eq!(
+            fired(&entries, at(fri, 21, 30), at(sat, 10, 30)),
+            Some("Weekend")
+        );
+        assert_eq!(
+            fired(&entries, at(mon, 21, 30), at(mon, 23, 0)),
+            Some("Night")
+        );
+        assert_eq!(
+            fired(&entries, at(mon, 21, 30), at(tue, 8, 30)),
+            Some("Work")
+        );
+
+        // Sunday 23:59 to Monday 00:00 wraps the week.
+        let midnight = [entry("00:00", &["mon"], "Monday")];
+        assert_eq!(
+            fired(&midnight, at(sun, 23, 59), at(mon, 0, 0)),
+            Some("Monday")
+        );
+        assert_eq!(
+            fired(&entries, at(sun, 21, 59), at(mon, 8, 0)),
+            Some("Work")
+        );
+
+        // Nothing catches up after days asleep or the clock going back.
+        assert_eq!(fired(&entries, at(mon, 21, 0), at(fri, 8, 30)), None);
+        assert_eq!(fired(&entries, at(mon, 22, 30), at(mon, 21, 30)), None);
+    }
+
+    #[test]
+    fn the_first_poll_only_starts_the_clock() {
+        let mut watch = ScheduleWatch::new(vec![entry("22:00", &[], "Night")]);
+        let at = WeekMinute::new;
+
+        assert_eq!(watch.poll(at(2, 22, 0)), None);
+        assert_eq!(watch.poll(at(2, 22, 0)), None);
+        assert_eq!(watch.poll(at(2, 22, 1)), None);
+        assert_eq!(
+            watch.poll(at(3, 22, 0)).map(|entry| entry.profile),
+            Some("Night".to_string())
+        );
+        assert_eq!(watch.poll(at(3, 22, 0)), None);
+    }
+
+    #[test]
+    fn bad_entries_are_reported_and_dropped() {
+        let schedule = |at: &str, day: &str| Schedule {
+            at: at.to_string(),
+            days: vec![day.to_string()],
+            profile: "Quiet".to_string(),
+        };
+        let (entries, warnings) = parse_all(&[
+            schedule("7:30", "mon"),
+            schedule("24:00", "mon"),
+            schedule("08:00", "someday"),
+        ]);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].minute, 7 * 60 + 30);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[1].starts_with("schedule 3:"));
+    }
+}
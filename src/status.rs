@@ -0,0 +1,370 @@
+//! `arch-sense --status`: one read of sensors, settings, lighting and the
+//! keyboard for scripts and status bars, without starting the TUI.
+//!
+//! Nothing here needs root. Whatever cannot be read is `null` in the JSON
+//! and `N/A` in the table. The JSON layout is the one documented in
+//! `--help`; fields are only ever added, and anything else bumps `schema`.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::AppConfig;
+use crate::conflicts;
+use crate::hardware::{self, Capabilities, PowerStatus, SensorSetup};
+use crate::models::{ControlId, ControlItem, FanMode, RgbSettings, SensorSnapshot};
+use crate::permissions::{keyboard_access, UsbAccess};
+use crate::quirks;
+use crate::rgb_protocol::RGB_EFFECTS;
+
+/// Bumped whenever a field is renamed, removed or changes its type.
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct Status {
+    schema: u32,
+    model: String,
+    module_loaded: bool,
+    sensors: Sensors,
+    power: Power,
+    /// Every control by its config key; `null` when absent or unreadable.
+    settings: BTreeMap<&'static str, Option<Setting>>,
+    rgb: Lighting,
+    keyboard: Keyboard,
+    /// Running tools that also write `platform_profile`.
+    conflicts: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Sensors {
+    cpu_temp_c: Option<f64>,
+    gpu_temp_c: Option<f64>,
+    cpu_fan_rpm: Option<f64>,
+    gpu_fan_rpm: Option<f64>,
+    cpu_fan_mode: &'static str,
+    gpu_fan_mode: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Power {
+    ac_online: Option<bool>,
+    battery_percent: Option<u8>,
+    battery_status: Option<String>,
+    battery_watts: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+struct Setting {
+    /// What the driver reports, as written back by `--apply`.
+    value: String,
+    /// The same value as the TUI shows it.
+    label: String,
+}
+
+/// The saved lighting, which is what the keyboard shows after `--apply`;
+/// the keyboard itself cannot be read back.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+struct Lighting {
+    effect: &'static str,
+    /// `#RRGGBB`, `random`, or `null` when the effect takes no colour.
+    color: Option<String>,
+    brightness: u8,
+    speed: u8,
+    /// `null` when the effect has no direction.
+    direction: Option<&'static str>,
+    /// The effect the power key switches back to from Off.
+    last_effect: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+struct Keyboard {
+    /// `accessible`, `permission_denied`, `not_found` or `error`.
+    state: &'static str,
+    name: Option<&'static str>,
+    /// USB `VID:PID`.
+    id: Option<String>,
+}
+
+impl Status {
+    fn new(
+        model: String,
+        module_loaded: bool,
+        sensors: &SensorSnapshot,
+        power: PowerStatus,
+        controls: &[ControlItem],
+        rgb: &RgbSettings,
+        keyboard: &UsbAccess,
+    ) -> Self {
+        Self {
+            schema: SCHEMA_VERSION,
+            model,
+            module_loaded,
+            sensors: Sensors {
+                cpu_temp_c: sensors.cpu_temp.value,
+                gpu_temp_c: sensors.gpu_temp.value,
+                cpu_fan_rpm: sensors.cpu_fan.value,
+                gpu_fan_rpm: sensors.gpu_fan.value,
+                cpu_fan_mode: fan_mode_key(sensors.cpu_fan_mode),
+                gpu_fan_mode: fan_mode_key(sensors.gpu_fan_mode),
+            },
+            power: Power {
+                ac_online: power.ac_online,
+                battery_percent: power.battery_percent,
+                battery_status: power.battery_status,
+                battery_watts: power.battery_watts,
+            },
+            settings: settings(controls),
+            rgb: lighting(rgb),
+            keyboard: keyboard_state(keyboard),
+            conflicts: Vec::new(),
+        }
+    }
+}
+
+/// Reads everything once and prints it as a table or as JSON.
+pub fn run(json: bool) -> Result<()> {
+    let status = collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+    } else {
+        print!("{}", render(&status));
+    }
+    Ok(())
+}
+
+fn collect() -> Status {
+    let config = AppConfig::load();
+    let (keyboards, _) = config.keyboards();
+    let capabilities = Capabilities::probe(config.fan_control(), config.manages_thermal_profile());
+
+    let status = Status::new(
+        quirks::detected().label(),
+        capabilities.module_loaded(),
+        &hardware::read_sensors_once(&SensorSetup::from_config(&config)),
+        hardware::read_power_once(),
+        &hardware::load_controls(&capabilities),
+        &RgbSettings::from_config(&config.rgb),
+        &keyboard_access(&keyboards),
+    );
+    Status {
+        conflicts: conflicts::detect(),
+        ..status
+    }
+}
+
+fn fan_mode_key(mode: FanMode) -> &'static str {
+    match mode {
+        FanMode::Auto => "auto",
+        FanMode::Max => "max",
+    }
+}
+
+fn settings(controls: &[ControlItem]) -> BTreeMap<&'static str, Option<Setting>> {
+    ControlId::ALL
+        .iter()
+        .map(|&id| {
+            let setting = controls
+                .iter()
+                .find(|item| item.id == id && item.last_error.is_none())
+                .map(|item| Setting {
+                    value: item.raw.clone(),
+                    label: item.display.clone(),
+                });
+            (id.key(), setting)
+        })
+        .collect()
+}
+
+fn lighting(rgb: &RgbSettings) -> Lighting {
+    let effect = rgb.effect();
+    let color = (effect.has_color && !rgb.is_zones()).then(|| {
+        if rgb.is_random_color() {
+            "random".to_string()
+        } else {
+            rgb.color_rgb().hex()
+        }
+    });
+
+    Lighting {
+        effect: effect.name,
+        color,
+        brightness: rgb.brightness,
+        speed: rgb.speed,
+        direction: effect.has_direction.then(|| rgb.direction_name()),
+        last_effect: RGB_EFFECTS[rgb.last_effect].name,
+    }
+}
+
+fn keyboard_state(access: &UsbAccess) -> Keyboard {
+    let (state, model) = match access {
+        UsbAccess::Accessible(model) => ("accessible", Some(model)),
+        UsbAccess::PermissionDenied(model) => ("permission_denied", Some(model)),
+        UsbAccess::NotFound => ("not_found", None),
+        UsbAccess::Error(_) => ("error", None),
+    };
+    Keyboard {
+        state,
+        name: model.map(|model| model.name),
+        id: model.map(|model| model.id_label()),
+    }
+}
+
+fn render(status: &Status) -> String {
+    let row = |label: &str, value: &str| format!("  {label:<22}{value}\n");
+    let sensors = &status.sensors;
+
+    let module = if status.module_loaded {
+        "loaded"
+    } else {
+        "not loaded"
+    };
+
+    let mut out = format!("Arch-Sense status: {}\n", status.model);
+    out += &row("Kernel module", module);
+    out += &row("CPU temperature", &temp(sensors.cpu_temp_c));
+    out += &row("GPU temperature", &temp(sensors.gpu_temp_c));
+    out += &row("CPU fan", &fan(sensors.cpu_fan_rpm, sensors.cpu_fan_mode));
+    out += &row("GPU fan", &fan(sensors.gpu_fan_rpm, sensors.gpu_fan_mode));
+    out += &row("Power", &power(&status.power));
+
+    for id in ControlId::ALL {
+        let value = status.settings.get(id.key()).cloned().flatten();
+        out += &row(
+            id.label(),
+            value.as_ref().map_or("N/A", |setting| &setting.label),
+        );
+    }
+
+    out += &row("Lighting", &lighting_summary(&status.rgb));
+    let keyboard = &status.keyboard;
+    let state = keyboard.state.replace('_', " ");
+    out += &row(
+        "Keyboard",
+        &match (keyboard.name, &keyboard.id) {
+            (Some(name), Some(id)) => format!("{name} ({id}), {state}"),
+            _ => state,
+        },
+    );
+    if !status.conflicts.is_empty() {
+        out += &row("Also managing profile", &status.conflicts.join(", "));
+    }
+    out
+}
+
+fn temp(value: Option<f64>) -> String {
+    value.map_or_else(|| "N/A".to_string(), |value| format!("{value:.0} °C"))
+}
+
+fn fan(rpm: Option<f64>, mode: &str) -> String {
+    match rpm {
+        Some(rpm) => format!("{rpm:.0} RPM ({mode})"),
+        None => format!("N/A ({mode})"),
+    }
+}
+
+fn power(power: &Power) -> String {
+    let source = match power.ac_online {
+        Some(true) => "AC",
+        Some(false) => "battery",
+        None => "N/A",
+    };
+    let mut parts = vec![source.to_string()];
+    if let Some(percent) = power.battery_percent {
+        parts.push(format!("{percent}%"));
+    }
+    if let Some(status) = &power.battery_status {
+        parts.push(status.clone());
+    }
+    if let Some(watts) = power.battery_watts {
+        parts.push(format!("{watts:.1} W"));
+    }
+    parts.join(", ")
+}
+
+fn lighting_summary(rgb: &Lighting) -> String {
+    let mut parts = vec![rgb.effect.to_string()];
+    parts.extend(rgb.color.clone());
+    parts.extend(rgb.direction.map(str::to_string));
+    parts.push(format!("brightness {}%", rgb.brightness));
+    parts.push(format!("speed {}%", rgb.speed));
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RgbConfig;
+    use crate::models::{ControlKind, SensorMetric};
+
+    fn control(id: ControlId, raw: &str, error: Option<&str>) -> ControlItem {
+        ControlItem {
+            id,
+            raw: raw.to_string(),
+            display: raw.to_uppercase(),
+            kind: ControlKind::Toggle,
+            pending: None,
+            last_error: error.map(str::to_string),
+            writable: false,
+        }
+    }
+
+    #[test]
+    fn json_keeps_every_key_and_nulls_what_could_not_be_read() {
+        let sensors = SensorSnapshot {
+            cpu_temp: SensorMetric::available(61.0),
+            gpu_temp: SensorMetric::unavailable("nvidia-smi is not installed"),
+            cpu_fan: SensorMetric::available(2400.0),
+            gpu_fan: SensorMetric::unavailable("no fan"),
+            cpu_fan_mode: FanMode::Auto,
+            gpu_fan_mode: FanMode::Max,
+            cpu_fan_percent: None,
+            gpu_fan_percent: None,
+        };
+        let power = PowerStatus {
+            ac_online: Some(true),
+            battery_percent: Some(80),
+            ..PowerStatus::default()
+        };
+        let controls = [
+            control(ControlId::ThermalProfile, "balanced", None),
+            control(ControlId::BootAnimation, "N/A", Some("permission denied")),
+        ];
+        let rgb = RgbSettings::from_config(&RgbConfig::default());
+
+        let status = Status::new(
+            "Predator PH16-71".to_string(),
+            true,
+            &sensors,
+            power,
+            &controls,
+            &rgb,
+            &UsbAccess::NotFound,
+        );
+        let status = Status {
+            conflicts: vec!["power-profiles-daemon".to_string()],
+            ..status
+        };
+        let json = serde_json::to_value(&status).unwrap();
+
+        assert_eq!(json["schema"], SCHEMA_VERSION);
+        assert_eq!(json["sensors"]["cpu_temp_c"], 61.0);
+        assert!(json["sensors"]["gpu_temp_c"].is_null());
+        assert_eq!(json["sensors"]["gpu_fan_mode"], "max");
+        assert_eq!(json["power"]["battery_percent"], 80);
+        assert!(json["power"]["battery_watts"].is_null());
+
+        let settings = json["settings"].as_object().unwrap();
+        assert_eq!(settings.len(), ControlId::ALL.len());
+        assert_eq!(settings["thermal_profile"]["value"], "balanced");
+        assert!(settings["boot_animation"].is_null());
+        assert!(settings["usb_charging"].is_null());
+
+        assert_eq!(json["rgb"]["effect"], rgb.effect().name);
+        assert_eq!(json["rgb"]["brightness"], rgb.brightness);
+        assert_eq!(json["keyboard"]["state"], "not_found");
+        assert!(json["keyboard"]["id"].is_null());
+        assert_eq!(json["conflicts"][0], "power-profiles-daemon");
+    }
+}
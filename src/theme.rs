@@ -1,100 +1,324 @@
+//! Colours of the TUI.
+//!
+//! The `theme` section of the config picks a preset by name and can override
+//! a few colours with hex strings; `--theme` swaps the preset for one run.
+
 use ratatui::style::Color;
 
-pub(crate) struct Theme;
+use crate::config::ThemeConfig;
+use crate::rgb_protocol::Rgb;
 
-impl Theme {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Theme {
     // -------------------------------------------------------------------------
     // Backgrounds & Surfaces
     // We leave these None to respect the user's terminal transparency,
     // but you can define them if you use filled blocks.
     // -------------------------------------------------------------------------
-    pub(crate) const BG: Option<Color> = None;
-    pub(crate) const SURFACE: Option<Color> = None;
-    pub(crate) const ELEVATED: Option<Color> = Some(Color::Rgb(28, 36, 51));
+    pub(crate) bg: Option<Color>,
+    pub(crate) surface: Option<Color>,
+    pub(crate) elevated: Option<Color>,
 
     // -------------------------------------------------------------------------
     // Borders
     // -------------------------------------------------------------------------
     /// Global frame border for header/footer framing.
-    pub(crate) const BORDER_FRAME: Color = Color::Rgb(83, 112, 153);
-    /// Focused panel border (electric cyan/blue).
-    pub(crate) const BORDER_FOCUS: Color = Color::Rgb(99, 183, 255);
-    /// Inactive panel border (subtle slate).
-    pub(crate) const BORDER_IDLE: Color = Color::Rgb(58, 69, 96);
+    pub(crate) border_frame: Color,
+    /// Focused panel border.
+    pub(crate) border_focus: Color,
+    /// Inactive panel border.
+    pub(crate) border_idle: Color,
 
     // -------------------------------------------------------------------------
     // Typography
     // -------------------------------------------------------------------------
     /// High-emphasis readable text.
-    pub(crate) const TEXT_PRIMARY: Color = Color::Rgb(220, 228, 244);
+    pub(crate) text_primary: Color,
     /// Supporting labels and less important values.
-    pub(crate) const TEXT_SECONDARY: Color = Color::Rgb(154, 169, 198);
+    pub(crate) text_secondary: Color,
     /// Tertiary hints, separators, and passive metadata.
-    pub(crate) const TEXT_TERTIARY: Color = Color::Rgb(116, 129, 157);
+    pub(crate) text_tertiary: Color,
     /// Disabled and de-emphasized text.
-    pub(crate) const TEXT_DISABLED: Color = Color::Rgb(88, 99, 124);
+    pub(crate) text_disabled: Color,
 
     // -------------------------------------------------------------------------
     // Accents & Branding
     // -------------------------------------------------------------------------
     /// Main brand/accent color for key highlights.
-    pub(crate) const BRAND_PRIMARY: Color = Color::Rgb(95, 182, 255);
+    pub(crate) brand_primary: Color,
     /// Tertiary accent for animated pulse blending.
-    pub(crate) const BRAND_TERTIARY: Color = Color::Rgb(160, 149, 245);
+    pub(crate) brand_tertiary: Color,
 
     // -------------------------------------------------------------------------
     // Semantic & Status Colors
     // -------------------------------------------------------------------------
-    pub(crate) const STATE_INFO: Color = Color::Rgb(106, 189, 255);
-    pub(crate) const STATE_SUCCESS: Color = Color::Rgb(128, 214, 145);
-    pub(crate) const STATE_WARNING: Color = Color::Rgb(243, 189, 101);
-    pub(crate) const STATE_ERROR: Color = Color::Rgb(240, 111, 132);
+    pub(crate) state_info: Color,
+    pub(crate) state_success: Color,
+    pub(crate) state_warning: Color,
+    pub(crate) state_error: Color,
 
     // -------------------------------------------------------------------------
     // Sensor Specific Colors
     // -------------------------------------------------------------------------
-    pub(crate) const TEMP_COOL: Color = Color::Rgb(112, 196, 255);
-    pub(crate) const TEMP_NORMAL: Color = Color::Rgb(128, 214, 145);
-    pub(crate) const TEMP_WARM: Color = Color::Rgb(242, 186, 101);
-    pub(crate) const TEMP_HOT: Color = Color::Rgb(240, 106, 123);
+    pub(crate) temp_cool: Color,
+    pub(crate) temp_normal: Color,
+    pub(crate) temp_warm: Color,
+    pub(crate) temp_hot: Color,
 
-    pub(crate) const FAN_QUIET: Color = Color::Rgb(120, 201, 255);
-    pub(crate) const FAN_NORMAL: Color = Color::Rgb(131, 208, 153);
-    pub(crate) const FAN_LOUD: Color = Color::Rgb(239, 180, 94);
-    pub(crate) const FAN_MAX: Color = Color::Rgb(236, 101, 119);
+    pub(crate) fan_quiet: Color,
+    pub(crate) fan_normal: Color,
+    pub(crate) fan_loud: Color,
+    pub(crate) fan_max: Color,
 
     /// Interactive/value emphasis colors.
-    pub(crate) const VALUE_PRIMARY: Color = Color::Rgb(96, 186, 255);
-    pub(crate) const VALUE_SELECTED: Color = Color::Rgb(95, 225, 214);
+    pub(crate) value_primary: Color,
+    pub(crate) value_selected: Color,
+}
+
+impl Theme {
+    /// The original slate and electric-blue palette.
+    pub(crate) const DEFAULT: Self = Self {
+        bg: None,
+        surface: None,
+        elevated: Some(Color::Rgb(28, 36, 51)),
+        border_frame: Color::Rgb(83, 112, 153),
+        border_focus: Color::Rgb(99, 183, 255),
+        border_idle: Color::Rgb(58, 69, 96),
+        text_primary: Color::Rgb(220, 228, 244),
+        text_secondary: Color::Rgb(154, 169, 198),
+        text_tertiary: Color::Rgb(116, 129, 157),
+        text_disabled: Color::Rgb(88, 99, 124),
+        brand_primary: Color::Rgb(95, 182, 255),
+        brand_tertiary: Color::Rgb(160, 149, 245),
+        state_info: Color::Rgb(106, 189, 255),
+        state_success: Color::Rgb(128, 214, 145),
+        state_warning: Color::Rgb(243, 189, 101),
+        state_error: Color::Rgb(240, 111, 132),
+        temp_cool: Color::Rgb(112, 196, 255),
+        temp_normal: Color::Rgb(128, 214, 145),
+        temp_warm: Color::Rgb(242, 186, 101),
+        temp_hot: Color::Rgb(240, 106, 123),
+        fan_quiet: Color::Rgb(120, 201, 255),
+        fan_normal: Color::Rgb(131, 208, 153),
+        fan_loud: Color::Rgb(239, 180, 94),
+        fan_max: Color::Rgb(236, 101, 119),
+        value_primary: Color::Rgb(96, 186, 255),
+        value_selected: Color::Rgb(95, 225, 214),
+    };
+
+    /// Predator green accents on the default palette.
+    pub(crate) const PREDATOR: Self = Self {
+        elevated: Some(Color::Rgb(24, 40, 30)),
+        border_frame: Color::Rgb(70, 130, 90),
+        border_focus: Color::Rgb(60, 230, 120),
+        border_idle: Color::Rgb(50, 80, 62),
+        brand_primary: Color::Rgb(60, 230, 120),
+        brand_tertiary: Color::Rgb(170, 240, 90),
+        state_info: Color::Rgb(90, 220, 150),
+        value_primary: Color::Rgb(80, 225, 130),
+        value_selected: Color::Rgb(170, 240, 90),
+        ..Self::DEFAULT
+    };
+
+    /// Terminal greys only, for monochrome or low-colour terminals.
+    pub(crate) const MONO: Self = Self {
+        bg: None,
+        surface: None,
+        elevated: Some(Color::DarkGray),
+        border_frame: Color::Gray,
+        border_focus: Color::White,
+        border_idle: Color::DarkGray,
+        text_primary: Color::White,
+        text_secondary: Color::Gray,
+        text_tertiary: Color::Gray,
+        text_disabled: Color::DarkGray,
+        brand_primary: Color::White,
+        brand_tertiary: Color::Gray,
+        state_info: Color::Gray,
+        state_success: Color::White,
+        state_warning: Color::White,
+        state_error: Color::White,
+        temp_cool: Color::Gray,
+        temp_normal: Color::Gray,
+        temp_warm: Color::White,
+        temp_hot: Color::White,
+        fan_quiet: Color::Gray,
+        fan_normal: Color::Gray,
+        fan_loud: Color::White,
+        fan_max: Color::White,
+        value_primary: Color::White,
+        value_selected: Color::White,
+    };
+
+    /// Dark text and deeper accents for light terminal backgrounds.
+    pub(crate) const LIGHT: Self = Self {
+        bg: None,
+        surface: None,
+        elevated: Some(Color::Rgb(218, 226, 240)),
+        border_frame: Color::Rgb(120, 138, 168),
+        border_focus: Color::Rgb(20, 110, 200),
+        border_idle: Color::Rgb(178, 188, 206),
+        text_primary: Color::Rgb(28, 34, 46),
+        text_secondary: Color::Rgb(70, 82, 104),
+        text_tertiary: Color::Rgb(98, 110, 132),
+        text_disabled: Color::Rgb(140, 149, 166),
+        brand_primary: Color::Rgb(20, 110, 200),
+        brand_tertiary: Color::Rgb(110, 88, 200),
+        state_info: Color::Rgb(20, 110, 200),
+        state_success: Color::Rgb(30, 135, 60),
+        state_warning: Color::Rgb(176, 106, 0),
+        state_error: Color::Rgb(196, 36, 60),
+        temp_cool: Color::Rgb(20, 110, 200),
+        temp_normal: Color::Rgb(30, 135, 60),
+        temp_warm: Color::Rgb(176, 106, 0),
+        temp_hot: Color::Rgb(196, 36, 60),
+        fan_quiet: Color::Rgb(20, 110, 200),
+        fan_normal: Color::Rgb(30, 135, 60),
+        fan_loud: Color::Rgb(176, 106, 0),
+        fan_max: Color::Rgb(196, 36, 60),
+        value_primary: Color::Rgb(20, 100, 190),
+        value_selected: Color::Rgb(0, 128, 118),
+    };
+
+    pub(crate) const PRESETS: [(&'static str, Self); 4] = [
+        ("default", Self::DEFAULT),
+        ("predator", Self::PREDATOR),
+        ("mono", Self::MONO),
+        ("light", Self::LIGHT),
+    ];
+
+    pub(crate) fn preset(name: &str) -> Option<Self> {
+        Self::PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name.trim()))
+            .map(|(_, theme)| *theme)
+    }
+
+    /// Builds the theme from the config, with `preset` (from `--theme`)
+    /// taking precedence. Unknown presets and invalid colours are skipped
+    /// and reported.
+    pub(crate) fn from_config(config: &ThemeConfig, preset: Option<&str>) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut theme = match preset.or(config.preset.as_deref()) {
+            Some(name) => Self::preset(name).unwrap_or_else(|| {
+                warnings.push(format!("unknown theme '{name}'"));
+                Self::DEFAULT
+            }),
+            None => Self::DEFAULT,
+        };
+
+        let mut color = |field: &str, value: &Option<String>| -> Option<Color> {
+            let value = value.as_deref()?;
+            match Rgb::from_hex(value) {
+                Some(rgb) => Some(Color::Rgb(rgb.r, rgb.g, rgb.b)),
+                None => {
+                    warnings.push(format!("theme {field} '{value}' is not a hex colour"));
+                    None
+                }
+            }
+        };
+
+        if let Some(accent) = color("accent", &config.accent) {
+            theme.brand_primary = accent;
+            theme.border_focus = accent;
+            theme.value_primary = accent;
+            theme.state_info = accent;
+        }
+        if let Some(dim) = color("dim", &config.dim) {
+            theme.text_disabled = dim;
+            theme.border_idle = dim;
+        }
+        if let Some(highlight) = color("highlight", &config.highlight) {
+            theme.elevated = Some(highlight);
+        }
+        if let Some(cool) = color("cool", &config.cool) {
+            theme.temp_cool = cool;
+            theme.fan_quiet = cool;
+        }
+        if let Some(warm) = color("warm", &config.warm) {
+            theme.temp_warm = warm;
+            theme.fan_loud = warm;
+        }
+        if let Some(hot) = color("hot", &config.hot) {
+            theme.temp_hot = hot;
+            theme.fan_max = hot;
+        }
+        if let Some(error) = color("error", &config.error) {
+            theme.state_error = error;
+        }
+
+        (theme, warnings)
+    }
 
     /// Determine color based on temperature thresholds
-    pub(crate) fn temp_color(value: f64) -> Color {
+    pub(crate) fn temp_color(&self, value: f64) -> Color {
         if value < 50.0 {
-            Self::TEMP_COOL
+            self.temp_cool
         } else if value < 75.0 {
-            Self::TEMP_NORMAL
+            self.temp_normal
         } else if value < 85.0 {
-            Self::TEMP_WARM
+            self.temp_warm
         } else {
-            Self::TEMP_HOT
+            self.temp_hot
         }
     }
 
     /// Determine color based on fan RPM percentage
-    pub(crate) fn fan_rpm_color(value: f64, max_rpm: f64) -> Color {
+    pub(crate) fn fan_rpm_color(&self, value: f64, max_rpm: f64) -> Color {
         if value <= 0.0 || max_rpm <= 0.0 {
-            Self::TEXT_TERTIARY
+            self.text_tertiary
         } else {
             let ratio = value / max_rpm;
             if ratio < 0.35 {
-                Self::FAN_QUIET
+                self.fan_quiet
             } else if ratio < 0.65 {
-                Self::FAN_NORMAL
+                self.fan_normal
             } else if ratio < 0.85 {
-                Self::FAN_LOUD
+                self.fan_loud
             } else {
-                Self::FAN_MAX
+                self.fan_max
             }
         }
     }
 }
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_chosen_by_name_and_cli_wins() {
+        let config = ThemeConfig {
+            preset: Some("light".to_string()),
+            ..ThemeConfig::default()
+        };
+
+        assert_eq!(Theme::from_config(&config, None).0, Theme::LIGHT);
+        assert_eq!(Theme::from_config(&config, Some("Mono")).0, Theme::MONO);
+
+        let (theme, warnings) = Theme::from_config(&config, Some("neon"));
+        assert_eq!(theme, Theme::DEFAULT);
+        assert_eq!(warnings, ["unknown theme 'neon'"]);
+    }
+
+    #[test]
+    fn invalid_colours_fall_back_per_field() {
+        let config = ThemeConfig {
+            accent: Some("#00ff7f".to_string()),
+            hot: Some("reddish".to_string()),
+            ..ThemeConfig::default()
+        };
+
+        let (theme, warnings) = Theme::from_config(&config, None);
+        assert_eq!(theme.brand_primary, Color::Rgb(0, 255, 127));
+        assert_eq!(theme.border_focus, Color::Rgb(0, 255, 127));
+        assert_eq!(theme.temp_hot, Theme::DEFAULT.temp_hot);
+        assert_eq!(warnings, ["theme hot 'reddish' is not a hex colour"]);
+    }
+}
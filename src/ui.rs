@@ -1,16 +1,35 @@
 use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
 
 use ratatui::prelude::*;
 use ratatui::symbols;
 use ratatui::widgets::*;
 
-use crate::app::{AnimatedMetric, App, MessageLevel};
-use crate::models::{FanMode, FocusPanel, Rgb, RgbField, COLOR_PALETTE, RANDOM_COLOR_INDEX};
+use crate::app::{AnimatedMetric, App, MessageLevel, PendingConfirm, SensorsState, StatusLog};
+use crate::color_picker::ColorInput;
+use crate::hardware::PowerStatus;
+use crate::keymap::{Action, HelpSection};
+use crate::models::{FanMode, FocusPanel, RgbField};
 use crate::permissions::UsbAccess;
+use crate::profiles::ProfilesPopup;
+use crate::rgb_protocol::{
+    Rgb, COLOR_PALETTE, RANDOM_COLOR_INDEX, THERMAL_HIGH_C, THERMAL_LOW_C, ZONE_COUNT,
+};
+use crate::schedule::local_tm;
 use crate::theme::Theme;
 
 /// Consistent spacing/padding throughout the UI (in character units)
 const SPACING: u16 = 1;
+/// How long an externally changed value flashes before it only stays marked.
+const EXTERNAL_CHANGE_FLASH: Duration = Duration::from_millis(1200);
+
+/// Smaller terminals only get a size warning.
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 16;
+/// Below this height the header shrinks to one line and Sensors is dropped.
+const COMPACT_HEIGHT: u16 = 30;
+/// Below this width Sensors is stacked above the other panels.
+const NARROW_WIDTH: u16 = 100;
 
 const DOUBLE_SQUIRCLE_BORDER: symbols::border::Set<'static> = symbols::border::Set {
     top_left: symbols::line::ROUNDED.top_left,
@@ -24,66 +43,341 @@ const DOUBLE_SQUIRCLE_BORDER: symbols::border::Set<'static> = symbols::border::S
 };
 
 pub(crate) fn draw(frame: &mut Frame, app: &App) {
+    let theme = &*app.theme;
     let area = frame.area();
 
     // Don't force a background color - let the terminal's default show through
-    let base_style = match Theme::BG {
+    let base_style = match theme.bg {
         Some(bg) => Style::new().bg(bg),
         None => Style::new(),
     };
     frame.render_widget(Block::new().style(base_style), area);
 
-    // Standardized vertical layout: Header (5), Body (Min 0), Footer (5)
-    // We reduce the vertical margin to 0 to let the lines hit the edges if desired, 
-    // but keep horizontal margin for breathing room.
-    let [header_area, body_area, footer_area] = Layout::vertical([
-        Constraint::Length(4),
-        Constraint::Min(0),
-        Constraint::Length(3),
-    ])
-    .horizontal_margin(SPACING)
-    .areas(area);
+    let Some(screen) = Screen::layout(area) else {
+        draw_too_small(frame, area, theme);
+        return;
+    };
 
-    draw_header(frame, header_area);
-    draw_body(frame, body_area, app);
-    draw_footer(frame, footer_area, app);
+    draw_header(frame, screen.header, app);
+    draw_controls(frame, screen.controls, app);
+    // Without room for Sensors the log takes the Keyboard panel's place.
+    match (screen.sensors, app.log.open) {
+        (Some(sensors), true) => {
+            draw_rgb(frame, screen.rgb, app);
+            draw_log(frame, sensors, theme, &app.log);
+        }
+        (Some(sensors), false) => {
+            draw_rgb(frame, screen.rgb, app);
+            draw_sensors(frame, sensors, app);
+        }
+        (None, true) => draw_log(frame, screen.rgb, theme, &app.log),
+        (None, false) => draw_rgb(frame, screen.rgb, app),
+    }
+    draw_footer(frame, screen.footer, app);
+
+    if let Some(popup) = &app.profiles_popup {
+        draw_profiles(frame, area, app, popup);
+    }
+    if let Some(confirm) = &app.confirm {
+        draw_confirm(frame, area, theme, confirm);
+    }
+    if app.help_open {
+        draw_help(frame, area, app);
+    }
 }
 
-fn draw_body(frame: &mut Frame, area: Rect, app: &App) {
-    // 50/50 split for better visual balance on modern screens
-    let [left, right] = Layout::horizontal([
-        Constraint::Percentage(50),
-        Constraint::Percentage(50),
-    ])
-    .spacing(SPACING)
-    .areas(area);
+fn draw_confirm(frame: &mut Frame, area: Rect, theme: &Theme, confirm: &PendingConfirm) {
+    let text = vec![
+        Line::styled(
+            format!("Write {} = {}?", confirm.id.label(), confirm.value),
+            Style::new().fg(theme.state_warning).bold(),
+        ),
+        Line::default(),
+        Line::styled(confirm.consequence, Style::new().fg(theme.text_primary)),
+    ];
 
-    // Even split for controls and keyboard
-    let [controls, rgb] = Layout::vertical([
-        Constraint::Percentage(50),
-        Constraint::Percentage(50),
-    ])
-    .spacing(SPACING)
-    .areas(left);
+    let popup = centered(area, 52, 9);
+    let title = format!(" Confirm {} ", confirm.id.label());
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(text)
+            .wrap(Wrap { trim: true })
+            .block(popup_block(theme, &title, " y confirm · n / Esc cancel ")),
+        popup,
+    );
+}
+
+/// A `width` x `height` rect centered in `area`, clamped to fit.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [popup] = Layout::horizontal([Constraint::Length(width.min(area.width))])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Length(height.min(area.height))])
+        .flex(layout::Flex::Center)
+        .areas(popup);
+    popup
+}
 
-    draw_controls(frame, controls, app);
-    draw_rgb(frame, rgb, app);
-    draw_sensors(frame, right, app);
+fn popup_block<'a>(theme: &Theme, title: &'a str, hint: &'a str) -> Block<'a> {
+    Block::bordered()
+        .border_set(DOUBLE_SQUIRCLE_BORDER)
+        .border_style(Style::new().fg(theme.border_focus))
+        .title(title)
+        .title_bottom(Line::from(hint).right_aligned())
+        .padding(Padding::horizontal(1))
+}
+
+fn draw_profiles(frame: &mut Frame, area: Rect, app: &App, popup: &ProfilesPopup) {
+    let theme = &*app.theme;
+    let mut lines: Vec<Line> = app
+        .profiles()
+        .iter()
+        .enumerate()
+        .map(|(index, profile)| {
+            let selected = index == popup.selected && popup.naming.is_none();
+            let slot = if index < 9 {
+                format!("Ctrl+{}", index + 1)
+            } else {
+                String::new()
+            };
+            let style = if selected {
+                style_with_bg(Style::new().fg(theme.value_selected).bold(), theme.elevated)
+            } else {
+                Style::new().fg(theme.text_primary)
+            };
+            Line::from(vec![
+                Span::styled(if selected { "▸ " } else { "  " }, style),
+                Span::styled(format!("{:<26}", profile.name), style),
+                Span::styled(slot, Style::new().fg(theme.text_tertiary)),
+            ])
+        })
+        .collect();
+
+    if lines.is_empty() {
+        lines.push(Line::styled(
+            "No profiles yet: press s to save the current setup",
+            Style::new().fg(theme.text_secondary),
+        ));
+    }
+    if let Some(naming) = &popup.naming {
+        let prompt = if naming.target.is_some() {
+            "Rename to"
+        } else {
+            "Save as"
+        };
+        lines.push(Line::default());
+        lines.push(Line::from(vec![
+            Span::styled(format!("{prompt}: "), Style::new().fg(theme.text_secondary)),
+            Span::styled(
+                format!("{}▏", naming.buffer),
+                Style::new().fg(theme.state_warning).bold(),
+            ),
+        ]));
+    }
+
+    let hint = if popup.naming.is_some() {
+        " Enter saves · Esc cancels "
+    } else {
+        " Enter apply · s save · n rename · d delete · Esc "
+    };
+    let popup_area = centered(area, 56, lines.len() as u16 + 2);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(
+        Paragraph::new(lines).block(popup_block(theme, " Profiles ", hint)),
+        popup_area,
+    );
+}
+
+/// Centered list of every binding, built from the keymap so it cannot drift.
+fn draw_help(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &*app.theme;
+    let mut lines = Vec::new();
+    for section in HelpSection::ALL {
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        lines.push(Line::styled(
+            section.label(),
+            Style::new().fg(theme.brand_primary).bold(),
+        ));
+        for action in Action::ALL
+            .into_iter()
+            .filter(|action| action.section() == section)
+        {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:<18}", app.keymap.label(action)),
+                    Style::new().fg(theme.text_primary).bold(),
+                ),
+                Span::styled(action.description(), Style::new().fg(theme.text_secondary)),
+            ]));
+        }
+    }
+
+    let popup = centered(area, 54, lines.len() as u16 + 2);
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines).block(popup_block(theme, " Keybindings ", " Esc / ? closes ")),
+        popup,
+    );
+}
+
+/// Where each part of the screen goes at the current terminal size.
+#[derive(Debug, PartialEq, Eq)]
+struct Screen {
+    header: Rect,
+    controls: Rect,
+    rgb: Rect,
+    /// Dropped on short terminals.
+    sensors: Option<Rect>,
+    footer: Rect,
+}
+
+impl Screen {
+    /// `None` when the terminal is below `MIN_WIDTH` x `MIN_HEIGHT`.
+    fn layout(area: Rect) -> Option<Self> {
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            return None;
+        }
+
+        let compact = area.height < COMPACT_HEIGHT;
+        // Keep a horizontal margin for breathing room, but let the lines hit the edges.
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(if compact { 1 } else { 4 }),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .horizontal_margin(SPACING)
+        .areas(area);
+
+        let side_by_side = |area: Rect| -> [Rect; 2] {
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .spacing(SPACING)
+                .areas(area)
+        };
+
+        let (controls, rgb, sensors) = if compact {
+            let [controls, rgb] = side_by_side(body);
+            (controls, rgb, None)
+        } else if area.width < NARROW_WIDTH {
+            let [sensors, lower] =
+                Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)])
+                    .spacing(SPACING)
+                    .areas(body);
+            let [controls, rgb] = side_by_side(lower);
+            (controls, rgb, Some(sensors))
+        } else {
+            // 50/50 split for better visual balance on modern screens
+            let [left, right] = side_by_side(body);
+            // Even split for controls and keyboard
+            let [controls, rgb] =
+                Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .spacing(SPACING)
+                    .areas(left);
+            (controls, rgb, Some(right))
+        };
+
+        Some(Self {
+            header,
+            controls,
+            rgb,
+            sensors,
+            footer,
+        })
+    }
+}
+
+fn draw_too_small(frame: &mut Frame, area: Rect, theme: &Theme) {
+    let text = vec![
+        Line::styled(
+            "Terminal too small",
+            Style::new().fg(theme.state_warning).bold(),
+        ),
+        Line::styled(
+            format!(
+                "need ≥ {MIN_WIDTH}×{MIN_HEIGHT}, have {}×{}",
+                area.width, area.height
+            ),
+            Style::new().fg(theme.text_secondary),
+        ),
+    ];
+
+    let [message] = Layout::vertical([Constraint::Length(2)])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        message,
+    );
+}
+
+fn draw_log(frame: &mut Frame, area: Rect, theme: &Theme, log: &StatusLog) {
+    let mut block = Block::bordered()
+        .border_set(DOUBLE_SQUIRCLE_BORDER)
+        .border_style(Style::new().fg(theme.border_focus))
+        .title(Line::from(vec![
+            Span::styled(" ☰ ", Style::new().fg(theme.brand_primary)),
+            Span::styled("Status Log ", Style::new().fg(theme.text_primary).bold()),
+        ]))
+        .title_bottom(
+            Line::styled(
+                format!(" {}/{} · PgUp/PgDn ", log.scroll + 1, log.len().max(1)),
+                Style::new().fg(theme.text_disabled),
+            )
+            .right_aligned(),
+        );
+    if let Some(bg) = theme.surface {
+        block = block.style(Style::new().bg(bg));
+    }
+
+    let lines: Vec<Line> = log
+        .visible()
+        .map(|entry| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {} ", clock_label(entry.at)),
+                    Style::new().fg(theme.text_disabled),
+                ),
+                Span::styled(
+                    entry.text.as_str(),
+                    Style::new().fg(message_color(theme, entry.level)),
+                ),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block),
+        area,
+    );
+}
+
+/// Local wall-clock time as `HH:MM:SS`.
+fn clock_label(at: SystemTime) -> String {
+    local_tm(at).map_or_else(
+        || "--:--:--".to_string(),
+        |tm| format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec),
+    )
 }
 
 fn panel_block<'a>(title: &'a str, panel: FocusPanel, app: &App) -> Block<'a> {
+    let theme = &*app.theme;
     let focused = app.focus == panel;
     let border = if focused {
-        pulse_color(app, Theme::BORDER_FOCUS, Theme::BRAND_TERTIARY)
+        pulse_color(app, theme.border_focus, theme.brand_tertiary)
     } else {
-        Theme::BORDER_IDLE
+        theme.border_idle
     };
 
     let title_style = Style::new()
         .fg(if focused {
-            Theme::TEXT_PRIMARY
+            theme.text_primary
         } else {
-            Theme::TEXT_SECONDARY
+            theme.text_secondary
         })
         .bold();
 
@@ -93,10 +387,13 @@ fn panel_block<'a>(title: &'a str, panel: FocusPanel, app: &App) -> Block<'a> {
         FocusPanel::Sensors => " 📊 ",
     };
 
-    let title_spans = vec![
-        Span::styled(icon, Style::new().fg(Theme::BRAND_PRIMARY)),
+    let mut title_spans = vec![
+        Span::styled(icon, Style::new().fg(theme.brand_primary)),
         Span::styled(format!("{title} "), title_style),
     ];
+    if app.read_only && panel != FocusPanel::Sensors {
+        title_spans.push(Span::styled("🔒 ", Style::new().fg(theme.state_warning)));
+    }
 
     // Apply background color only if it's Some, otherwise use terminal default
     let mut block = Block::bordered()
@@ -105,7 +402,7 @@ fn panel_block<'a>(title: &'a str, panel: FocusPanel, app: &App) -> Block<'a> {
         .title(Line::from(title_spans));
 
     // Apply optional background
-    block = match Theme::SURFACE {
+    block = match theme.surface {
         Some(bg) => block.style(Style::new().bg(bg)),
         None => block,
     };
@@ -132,31 +429,38 @@ fn blend(a: Color, b: Color, mix: f64) -> Color {
     Color::Rgb(channel(ar, br), channel(ag, bg), channel(ab, bb))
 }
 
-fn draw_header(f: &mut Frame, area: Rect) {
+fn draw_header(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &*app.theme;
     let block = Block::default()
         .borders(Borders::BOTTOM)
         .border_set(symbols::border::DOUBLE)
-        .border_style(Style::new().fg(Theme::BORDER_FRAME));
+        .border_style(Style::new().fg(theme.border_frame));
 
-    f.render_widget(block, area);
+    // A one-line header is just the title.
+    if area.height > 1 {
+        f.render_widget(block, area);
+    }
 
     let title = Line::from(vec![
-        Span::styled(" ◆ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
+        Span::styled(" ◆ ", Style::new().fg(theme.brand_primary).bold()),
         Span::styled(
             "A R C H - S E N S E",
-            Style::new().fg(Theme::BRAND_PRIMARY).bold(),
+            Style::new().fg(theme.brand_primary).bold(),
         ),
-        Span::styled(" ◆ ", Style::new().fg(Theme::BRAND_PRIMARY)),
+        Span::styled(" ◆ ", Style::new().fg(theme.brand_primary)),
         Span::styled(
             "Acer Predator Control Center",
-            Style::new().fg(Theme::TEXT_SECONDARY),
+            Style::new().fg(theme.text_secondary),
         ),
+        Span::styled(" · ", Style::new().fg(theme.text_disabled)),
+        Span::styled(app.model.as_str(), Style::new().fg(theme.text_primary)),
     ])
     .centered();
 
 
-    // Vertically center text in the 5-row area (row 2 is middle)
-    let title_area = Rect::new(area.x, area.y + 1, area.width, 1);
+    // Vertically center text in the 4-row area (row 2 is middle)
+    let title_row = if area.height > 1 { 1 } else { 0 };
+    let title_area = Rect::new(area.x, area.y + title_row, area.width, 1).intersection(area);
     f.render_widget(Paragraph::new(title), title_area);
 }
 
@@ -169,7 +473,15 @@ fn style_with_bg(base: Style, bg: Option<Color>) -> Style {
 }
 
 fn draw_controls(frame: &mut Frame, area: Rect, app: &App) {
-    let block = panel_block(" Controls", FocusPanel::Controls, app);
+    let theme = &*app.theme;
+    let title = if app.filter_editing {
+        format!(" Controls /{}▏", app.control_filter)
+    } else if !app.control_filter.is_empty() {
+        format!(" Controls /{}", app.control_filter)
+    } else {
+        " Controls".to_string()
+    };
+    let block = panel_block(&title, FocusPanel::Controls, app);
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
@@ -181,52 +493,89 @@ fn draw_controls(frame: &mut Frame, area: Rect, app: &App) {
     if app.controls.is_empty() {
         frame.render_widget(
             Paragraph::new(" Waiting for hardware controls...")
-                .style(Style::new().fg(Theme::TEXT_SECONDARY))
+                .style(Style::new().fg(theme.text_secondary))
                 .alignment(Alignment::Center),
             content_area,
         );
         return;
     }
 
-    let rows = app
-        .controls
+    let visible = app.visible_controls();
+    if visible.is_empty() {
+        frame.render_widget(
+            Paragraph::new(format!(" No controls match '{}'", app.control_filter))
+                .style(Style::new().fg(theme.text_secondary))
+                .alignment(Alignment::Center),
+            content_area,
+        );
+        return;
+    }
+
+    let rows = visible
         .iter()
-        .enumerate()
-        .map(|(index, item)| {
+        .map(|&index| {
+            let item = &app.controls[index];
             let selected = app.focus == FocusPanel::Controls && index == app.selected_control;
             let pending = item.pending.is_some();
             let error = item.last_error.is_some();
+            let editing = app.editing.as_ref().filter(|edit| edit.id == item.id);
+            let external = app.external_change_age(item.id);
             
             // Define the row background style
             let row_style = if selected {
-                style_with_bg(Style::new(), Theme::ELEVATED)
+                style_with_bg(Style::new(), theme.elevated)
             } else {
                 Style::new()
             };
 
             let base_style = if selected {
-                Style::new().fg(Theme::TEXT_PRIMARY).bold()
+                Style::new().fg(theme.text_primary).bold()
             } else {
-                Style::new().fg(Theme::TEXT_PRIMARY)
+                Style::new().fg(theme.text_primary)
             };
             
-            let value_style = if error {
-                Style::new().fg(Theme::STATE_ERROR)
+            let value_style = if editing.is_some_and(|edit| edit.flashing()) {
+                Style::new()
+                    .fg(theme.text_primary)
+                    .bg(theme.state_error)
+                    .bold()
+            } else if editing.is_some() {
+                Style::new().fg(theme.state_warning).bold()
+            } else if error {
+                Style::new().fg(theme.state_error)
+            } else if !item.writable {
+                Style::new().fg(theme.text_disabled)
             } else if pending {
-                Style::new().fg(Theme::STATE_WARNING).bold()
+                Style::new().fg(theme.state_warning).bold()
+            } else if external.is_some_and(|age| age < EXTERNAL_CHANGE_FLASH) {
+                Style::new()
+                    .fg(theme.text_primary)
+                    .bg(theme.state_warning)
+                    .bold()
+            } else if external.is_some() {
+                Style::new().fg(theme.state_warning).bold()
             } else if selected {
-                Style::new().fg(Theme::VALUE_SELECTED).bold()
+                Style::new().fg(theme.value_selected).bold()
             } else {
-                Style::new().fg(Theme::VALUE_PRIMARY)
+                Style::new().fg(theme.value_primary)
             };
             
             let marker = if selected { "▸ " } else { "  " };
-            let state = if app.control_pending == Some(item.id) {
+            let applying = app.control_pending == Some(item.id);
+            let state = if applying {
                 "APPLY"
+            } else if editing.is_some() {
+                "EDIT"
             } else if pending {
                 "PREVIEW"
             } else if error {
                 "ERROR"
+            } else if external.is_some() {
+                "CHANGED"
+            } else if app.recently_appeared(item.id) {
+                "NEW"
+            } else if !item.writable {
+                "LOCKED"
             } else {
                 ""
             };
@@ -234,12 +583,16 @@ fn draw_controls(frame: &mut Frame, area: Rect, app: &App) {
             Row::new(vec![
                 Cell::from(marker).style(base_style),
                 Cell::from(item.label()).style(base_style),
-                Cell::from(item.visible_value()).style(value_style),
-                Cell::from(state).style(Style::new().fg(control_state_color(
-                    app.control_pending == Some(item.id),
-                    pending,
-                    error,
-                ))),
+                Cell::from(
+                    editing
+                        .map_or_else(|| item.visible_value(), |edit| format!("{}▏", edit.buffer)),
+                )
+                .style(value_style),
+                Cell::from(state).style(Style::new().fg(match state {
+                    "NEW" => theme.state_success,
+                    "CHANGED" => theme.state_warning,
+                    _ => control_state_color(theme, applying, pending, error),
+                })),
             ]).style(row_style)
         })
         .collect::<Vec<_>>();
@@ -251,9 +604,15 @@ fn draw_controls(frame: &mut Frame, area: Rect, app: &App) {
         Constraint::Length(8),
     ];
 
-    frame.render_widget(
+    // The state only scrolls the selected row into view; the highlight is drawn per row.
+    let selected = visible
+        .iter()
+        .position(|&index| index == app.selected_control);
+    let mut state = TableState::default().with_selected(selected);
+    frame.render_stateful_widget(
         Table::new(rows, widths).column_spacing(SPACING),
         content_area,
+        &mut state,
     );
 }
 
@@ -267,25 +626,66 @@ fn draw_rgb(frame: &mut Frame, area: Rect, app: &App) {
         .margin(SPACING)
         .split(inner)[0];
 
-    let [rows_area, palette_area] = Layout::vertical([
-        Constraint::Min(5),
+    let notice = keyboard_notice(app);
+    let [rows_area, notice_area, palette_area] = Layout::vertical([
+        Constraint::Min(6),
+        Constraint::Length(if notice.is_empty() { 0 } else { 3 }),
         Constraint::Length(1),
     ])
     .spacing(SPACING)
     .areas(content_area);
 
     draw_rgb_rows(frame, rows_area, app);
+    if !notice.is_empty() {
+        let notice = Paragraph::new(notice).wrap(Wrap { trim: true });
+        frame.render_widget(notice, notice_area);
+    }
     draw_palette(frame, palette_area, app);
 }
 
+/// What is wrong with the keyboard and how to fix it, once it has been
+/// looked for. Empty while it is usable.
+fn keyboard_notice(app: &App) -> Vec<Line<'static>> {
+    let theme = &*app.theme;
+    if !app.keyboard_scanned() {
+        return Vec::new();
+    }
+
+    let (title, fix) = match &app.keyboard {
+        UsbAccess::Accessible(_) => return Vec::new(),
+        UsbAccess::PermissionDenied(model) => (
+            format!("⚠ {} ({}) access denied", model.name, model.id_label()),
+            "Run `arch-sense --install-permissions`; `arch-sense --doctor` prints the udev rule"
+                .to_string(),
+        ),
+        UsbAccess::NotFound => (
+            "⚠ No keyboard found".to_string(),
+            format!(
+                "Probed {}; other models can be added as keyboard_override",
+                app.probed_keyboards
+            ),
+        ),
+        UsbAccess::Error(error) => ("⚠ Keyboard not usable".to_string(), error.clone()),
+    };
+    vec![
+        Line::styled(title, Style::new().fg(theme.state_warning).bold()),
+        Line::styled(fix, Style::new().fg(theme.text_secondary)),
+    ]
+}
+
 fn draw_rgb_rows(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &*app.theme;
     let effect = app.rgb.effect();
     let fields = [
-        (RgbField::Effect, effect.name.to_string()),
-        (RgbField::Color, color_value(app)),
-        (RgbField::Brightness, format!("{}%", app.rgb.brightness)),
-        (RgbField::Speed, format!("{}%", app.rgb.speed)),
-        (RgbField::Direction, direction_value(app)),
+        (RgbField::Effect, Line::from(effect.name)),
+        (RgbField::Zone, Line::from(zone_value(app))),
+        (RgbField::Color, color_line(app)),
+        (
+            RgbField::Brightness,
+            Line::from(format!("{}%", app.rgb.brightness)),
+        ),
+        (RgbField::Speed, Line::from(format!("{}%", app.rgb.speed))),
+        (RgbField::Direction, Line::from(direction_value(app))),
     ];
 
     let rows = fields
@@ -295,21 +695,23 @@ fn draw_rgb_rows(frame: &mut Frame, area: Rect, app: &App) {
             let selected = app.focus == FocusPanel::Rgb && index == app.selected_rgb_field;
             
             let row_style = if selected {
-                style_with_bg(Style::new(), Theme::ELEVATED)
+                style_with_bg(Style::new(), theme.elevated)
             } else {
                 Style::new()
             };
 
             let style = if selected {
-                Style::new().fg(Theme::TEXT_PRIMARY).bold()
+                Style::new().fg(theme.text_primary).bold()
             } else {
-                Style::new().fg(Theme::TEXT_PRIMARY)
+                Style::new().fg(theme.text_primary)
             };
             
-            let value_style = if selected {
-                Style::new().fg(Theme::VALUE_SELECTED).bold()
+            let value_style = if field == RgbField::Color && app.color_input.is_some() {
+                Style::new().fg(theme.state_warning).bold()
+            } else if selected {
+                Style::new().fg(theme.value_selected).bold()
             } else {
-                Style::new().fg(Theme::VALUE_PRIMARY)
+                Style::new().fg(theme.value_primary)
             };
 
             Row::new(vec![
@@ -329,14 +731,65 @@ fn draw_rgb_rows(frame: &mut Frame, area: Rect, app: &App) {
     frame.render_widget(Table::new(rows, widths), area);
 }
 
-fn color_value(app: &App) -> String {
-    if !app.rgb.effect().has_color {
-        "Not used".to_string()
+fn zone_value(app: &App) -> String {
+    if app.rgb.is_zones() {
+        format!("{} of {ZONE_COUNT}", app.selected_zone + 1)
     } else {
-        app.rgb.color().name.to_string()
+        "Not used".to_string()
     }
 }
 
+fn color_line(app: &App) -> Line<'static> {
+    if app.rgb.is_thermal() {
+        let tint = app.rgb.thermal_color(app.sensors.hottest_temp());
+        let range = format!("By temperature ({THERMAL_LOW_C}–{THERMAL_HIGH_C} °C) ");
+        return Line::from(vec![
+            Span::raw(range),
+            Span::styled("██", Style::new().fg(to_color(tint))),
+        ]);
+    }
+    if !app.rgb.uses_color() {
+        return Line::from("Not used");
+    }
+
+    let (text, swatch) = match &app.color_input {
+        // Hex entry only shows a swatch once the digits form a colour.
+        Some(input @ ColorInput::Hex(buffer)) => (
+            format!("#{}▏", buffer.trim_start_matches('#')),
+            input.preview(),
+        ),
+        Some(
+            input @ ColorInput::Hsv {
+                hue,
+                saturation,
+                value,
+            },
+        ) => (
+            format!("H {hue}° S {saturation}% V {value}%"),
+            input.preview(),
+        ),
+        None if app.rgb.is_zones() => {
+            let zone = app.rgb.zones[app.selected_zone];
+            let name = COLOR_PALETTE
+                .iter()
+                .find(|color| color.rgb == zone)
+                .map_or_else(|| zone.hex(), |color| color.name.to_string());
+            (name, Some(zone))
+        }
+        None => match app.rgb.custom {
+            Some(custom) => (custom.hex(), Some(custom)),
+            None => (app.rgb.color().name.to_string(), None),
+        },
+    };
+
+    let mut spans = vec![Span::raw(text)];
+    if let Some(rgb) = swatch {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled("██", Style::new().fg(to_color(rgb))));
+    }
+    Line::from(spans)
+}
+
 fn direction_value(app: &App) -> String {
     if app.rgb.effect().has_direction {
         app.rgb.direction_name().to_string()
@@ -346,14 +799,20 @@ fn direction_value(app: &App) -> String {
 }
 
 fn draw_palette(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &*app.theme;
+    if app.rgb.is_zones() {
+        draw_zones(frame, area, app);
+        return;
+    }
+
     let mut swatches = vec![Span::styled(
         " 🎨 Palette  ",
-        Style::new().fg(Theme::TEXT_SECONDARY),
+        Style::new().fg(theme.text_secondary),
     )];
     for (index, color) in COLOR_PALETTE.iter().enumerate() {
-        let selected = index == app.rgb.color_idx;
+        let selected = app.rgb.custom.is_none() && index == app.rgb.color_idx;
         let style = if index == RANDOM_COLOR_INDEX {
-            Style::new().fg(Theme::BRAND_TERTIARY).bold()
+            Style::new().fg(theme.brand_tertiary).bold()
         } else {
             Style::new().fg(to_color(color.rgb)).bold()
         };
@@ -365,11 +824,44 @@ fn draw_palette(frame: &mut Frame, area: Rect, app: &App) {
         }
         swatches.push(Span::raw(" "));
     }
+    if let Some(custom) = app.rgb.custom {
+        swatches.push(Span::styled("◆", Style::new().fg(to_color(custom)).bold()));
+    }
 
     frame.render_widget(Paragraph::new(Line::from(swatches)).centered(), area);
 }
 
+fn draw_zones(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &*app.theme;
+    let mut cells = vec![Span::styled(
+        " ▦ Zones  ",
+        Style::new().fg(theme.text_secondary),
+    )];
+    for (index, zone) in app.rgb.zones.iter().enumerate() {
+        let selected = index == app.selected_zone;
+        let label_style = if selected {
+            Style::new().fg(theme.value_selected).bold()
+        } else {
+            Style::new().fg(theme.text_secondary)
+        };
+
+        cells.push(Span::styled(
+            if selected {
+                format!("▸{} ", index + 1)
+            } else {
+                format!(" {} ", index + 1)
+            },
+            label_style,
+        ));
+        cells.push(Span::styled("████", Style::new().fg(to_color(*zone))));
+        cells.push(Span::raw("  "));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(cells)).centered(), area);
+}
+
 fn draw_sensors(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &*app.theme;
     let block = panel_block("Sensors", FocusPanel::Sensors, app);
     let inner = block.inner(area);
     frame.render_widget(block, area);
@@ -379,103 +871,189 @@ fn draw_sensors(frame: &mut Frame, area: Rect, app: &App) {
         .margin(SPACING)
         .split(inner)[0];
 
-    let [temps_area, fans_area] = Layout::vertical([
+    let [battery_area, temps_area, fans_area] = Layout::vertical([
+        Constraint::Length(1),
         Constraint::Percentage(50),
         Constraint::Percentage(50),
     ])
     .spacing(SPACING)
     .areas(content_area);
 
+    draw_battery(frame, battery_area, theme, &app.power);
+
     draw_overlay_chart(
         frame,
         temps_area,
+        theme,
         "Temperatures",
-        &app.sensors.cpu_temp,
-        &app.sensors.cpu_temp_history,
-        &app.sensors.gpu_temp,
-        &app.sensors.gpu_temp_history,
+        ChartSeries {
+            metric: &app.sensors.cpu_temp,
+            history: &app.sensors.cpu_temp_history,
+            mode: None,
+            percent: None,
+            delta: SensorsState::delta(&app.sensors.cpu_temp_history, app.snapshot_interval),
+        },
+        ChartSeries {
+            metric: &app.sensors.gpu_temp,
+            history: &app.sensors.gpu_temp_history,
+            mode: None,
+            percent: None,
+            delta: SensorsState::delta(&app.sensors.gpu_temp_history, app.snapshot_interval),
+        },
         MetricKind::Temp,
-        None,
-        None,
     );
     draw_overlay_chart(
         frame,
         fans_area,
+        theme,
         "Fan Speeds",
-        &app.sensors.cpu_fan,
-        &app.sensors.cpu_fan_history,
-        &app.sensors.gpu_fan,
-        &app.sensors.gpu_fan_history,
+        ChartSeries {
+            metric: &app.sensors.cpu_fan,
+            history: &app.sensors.cpu_fan_history,
+            mode: Some(app.sensors.cpu_fan_mode),
+            percent: app.sensors.cpu_fan_percent,
+            delta: None,
+        },
+        ChartSeries {
+            metric: &app.sensors.gpu_fan,
+            history: &app.sensors.gpu_fan_history,
+            mode: Some(app.sensors.gpu_fan_mode),
+            percent: app.sensors.gpu_fan_percent,
+            delta: None,
+        },
         MetricKind::Fan,
-        Some(app.sensors.cpu_fan_mode),
-        Some(app.sensors.gpu_fan_mode),
     );
 }
 
+fn draw_battery(frame: &mut Frame, area: Rect, theme: &Theme, power: &PowerStatus) {
+    let color = match power.battery_percent {
+        Some(percent) if percent <= 15 => theme.state_error,
+        Some(percent) if percent <= 30 => theme.state_warning,
+        Some(_) => theme.state_success,
+        None => theme.text_disabled,
+    };
+
+    let line = Line::from(vec![
+        Span::styled(
+            format!("{:<14}", "Battery"),
+            Style::new().fg(theme.text_primary).bold(),
+        ),
+        Span::styled(battery_text(power), Style::new().fg(color).bold()),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+/// "81% · Charging · 33.0 W · AC", with N/A for anything the battery does not report.
+fn battery_text(power: &PowerStatus) -> String {
+    let percent = power
+        .battery_percent
+        .map_or_else(|| "N/A".to_string(), |percent| format!("{percent}%"));
+    let status = power.battery_status.as_deref().unwrap_or("N/A");
+    let watts = power
+        .battery_watts
+        .map_or_else(|| "N/A".to_string(), |watts| format!("{watts:.1} W"));
+    let ac = match power.ac_online {
+        Some(true) => "AC",
+        Some(false) => "On Battery",
+        None => "AC N/A",
+    };
+
+    format!("{percent} · {status} · {watts} · {ac}")
+}
+
 #[derive(Clone, Copy)]
 enum MetricKind {
     Temp,
     Fan,
 }
 
+/// One CPU or GPU line of an overlay chart.
+struct ChartSeries<'a> {
+    metric: &'a AnimatedMetric,
+    history: &'a VecDeque<Option<u64>>,
+    mode: Option<FanMode>,
+    /// Manual fan percentage shown before the RPM reading.
+    percent: Option<u8>,
+    /// Change over the last minute, shown for temperatures.
+    delta: Option<i64>,
+}
+
 fn draw_overlay_chart(
     frame: &mut Frame,
     area: Rect,
+    theme: &Theme,
     title: &str,
-    cpu_metric: &AnimatedMetric,
-    cpu_history: &VecDeque<u64>,
-    gpu_metric: &AnimatedMetric,
-    gpu_history: &VecDeque<u64>,
+    cpu: ChartSeries,
+    gpu: ChartSeries,
     kind: MetricKind,
-    cpu_mode: Option<FanMode>,
-    gpu_mode: Option<FanMode>,
 ) {
-    if area.height < 5 {
+    if area.height < 6 {
         return;
     }
 
-    let [header_area, chart_area] =
-        Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
-        .spacing(1) // Add space between header and chart
-        .areas(area);
+    let ChartSeries {
+        metric: cpu_metric,
+        history: cpu_history,
+        mode: cpu_mode,
+        percent: cpu_percent,
+        delta: cpu_delta,
+    } = cpu;
+    let ChartSeries {
+        metric: gpu_metric,
+        history: gpu_history,
+        mode: gpu_mode,
+        percent: gpu_percent,
+        delta: gpu_delta,
+    } = gpu;
+
+    let [header_area, stats_area, chart_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Min(0),
+    ])
+    .spacing(1) // Add space between header and chart
+    .areas(area);
 
     let cpu_color = if cpu_metric.error.is_some() {
-        Theme::TEXT_DISABLED
+        theme.text_disabled
     } else {
-        metric_sample_color(kind, cpu_metric.value, cpu_metric.max)
+        metric_sample_color(theme, kind, cpu_metric.value, cpu_metric.max)
     };
     let gpu_color = if gpu_metric.error.is_some() {
-        Theme::TEXT_DISABLED
+        theme.text_disabled
     } else {
-        metric_sample_color(kind, gpu_metric.value, gpu_metric.max)
+        metric_sample_color(theme, kind, gpu_metric.value, gpu_metric.max)
     };
 
-    let cpu_val = metric_value(cpu_metric, kind);
-    let gpu_val = metric_value(gpu_metric, kind);
+    let cpu_val = metric_value(cpu_metric, kind, cpu_percent);
+    let gpu_val = metric_value(gpu_metric, kind, gpu_percent);
 
     // Header with polished legend
     let mut header_spans = vec![
-        Span::styled(format!("{title:<14}"), Style::new().fg(Theme::TEXT_PRIMARY).bold()),
+        Span::styled(
+            format!("{title:<14}"),
+            Style::new().fg(theme.text_primary).bold(),
+        ),
         Span::styled("● ", Style::new().fg(cpu_color)),
-        Span::styled("CPU ", Style::new().fg(Theme::TEXT_SECONDARY)),
+        Span::styled("CPU ", Style::new().fg(theme.text_secondary)),
         Span::styled(format!("{cpu_val} "), Style::new().fg(cpu_color).bold()),
     ];
 
     if let Some(mode) = cpu_mode {
         header_spans.push(Span::styled(
             format!("[{}] ", mode.label()),
-            Style::new().fg(fan_mode_color(mode)),
+            Style::new().fg(fan_mode_color(theme, mode)),
         ));
     }
 
     header_spans.push(Span::styled(" ● ", Style::new().fg(gpu_color)));
-    header_spans.push(Span::styled("GPU ", Style::new().fg(Theme::TEXT_SECONDARY)));
+    header_spans.push(Span::styled("GPU ", Style::new().fg(theme.text_secondary)));
     header_spans.push(Span::styled(format!("{gpu_val} "), Style::new().fg(gpu_color).bold()));
 
     if let Some(mode) = gpu_mode {
         header_spans.push(Span::styled(
             format!("[{}]", mode.label()),
-            Style::new().fg(fan_mode_color(mode)),
+            Style::new().fg(fan_mode_color(theme, mode)),
         ));
     }
 
@@ -486,80 +1064,109 @@ fn draw_overlay_chart(
     let cpu_data = visible_history(cpu_history, width);
     let gpu_data = visible_history(gpu_history, width);
 
-    let cpu_points: Vec<(f64, f64)> = cpu_data
-        .iter()
-        .enumerate()
-        .map(|(i, &v)| (i as f64, v as f64))
-        .collect();
-    let gpu_points: Vec<(f64, f64)> = gpu_data
+    let stats = Line::from(vec![
+        Span::styled(format!("{:<14}", ""), Style::new()),
+        Span::styled(
+            format!("CPU {}  ", history_stats(&cpu_data, cpu_delta, kind)),
+            Style::new().fg(theme.text_tertiary),
+        ),
+        Span::styled(
+            format!("GPU {}", history_stats(&gpu_data, gpu_delta, kind)),
+            Style::new().fg(theme.text_tertiary),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(stats), stats_area);
+
+    let cpu_segments = history_segments(&cpu_data);
+    let gpu_segments = history_segments(&gpu_data);
+
+    // One dataset per unbroken run, so missing samples leave a visible gap.
+    let datasets: Vec<Dataset> = cpu_segments
         .iter()
-        .enumerate()
-        .map(|(i, &v)| (i as f64, v as f64))
+        .map(|points| (points, cpu_color))
+        .chain(gpu_segments.iter().map(|points| (points, gpu_color)))
+        .map(|(points, color)| {
+            let graph_type = if points.len() == 1 {
+                GraphType::Scatter
+            } else {
+                GraphType::Line
+            };
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(graph_type)
+                .style(Style::default().fg(color))
+                .data(points)
+        })
         .collect();
 
-    let datasets = vec![
-        Dataset::default()
-            .marker(symbols::Marker::Braille)
-            .graph_type(GraphType::Line)
-            .style(Style::default().fg(cpu_color))
-            .data(&cpu_points),
-        Dataset::default()
-            .marker(symbols::Marker::Braille)
-            .graph_type(GraphType::Line)
-            .style(Style::default().fg(gpu_color))
-            .data(&gpu_points),
-    ];
-
     let y_max = cpu_metric.max.max(gpu_metric.max);
     let chart = Chart::new(datasets)
         .block(Block::new().padding(Padding::new(1, 1, 0, 0)))
-        .x_axis(
-            Axis::default()
-                .bounds([0.0, width as f64])
-                .labels(vec![
-                    Span::styled("Past", Style::new().fg(Theme::TEXT_TERTIARY)),
-                    Span::styled("Now", Style::new().fg(Theme::TEXT_TERTIARY)),
-                ]),
-        )
-        .y_axis(
-            Axis::default()
-                .bounds([0.0, y_max])
-                .labels(vec![
-                    Span::styled("0", Style::new().fg(Theme::TEXT_TERTIARY)),
-                    Span::styled(format!("{:.0}", y_max / 2.0), Style::new().fg(Theme::TEXT_TERTIARY)),
-                    Span::styled(format!("{:.0}", y_max), Style::new().fg(Theme::TEXT_TERTIARY)),
-                ]),
-        );
+        .x_axis(Axis::default().bounds([0.0, width as f64]).labels(vec![
+            Span::styled("Past", Style::new().fg(theme.text_tertiary)),
+            Span::styled("Now", Style::new().fg(theme.text_tertiary)),
+        ]))
+        .y_axis(Axis::default().bounds([0.0, y_max]).labels(vec![
+            Span::styled("0", Style::new().fg(theme.text_tertiary)),
+            Span::styled(
+                format!("{:.0}", y_max / 2.0),
+                Style::new().fg(theme.text_tertiary),
+            ),
+            Span::styled(
+                format!("{:.0}", y_max),
+                Style::new().fg(theme.text_tertiary),
+            ),
+        ]));
 
     frame.render_widget(chart, chart_area);
 }
 
-fn metric_value(metric: &AnimatedMetric, kind: MetricKind) -> String {
-    if metric.target.is_none() {
-        return "N/A".to_string();
-    }
-
-    match kind {
+fn metric_value(metric: &AnimatedMetric, kind: MetricKind, percent: Option<u8>) -> String {
+    let reading = metric.target.map(|_| match kind {
         MetricKind::Temp => format!("{:.0}°C", metric.value),
         MetricKind::Fan => format!("{:.0} RPM", metric.value),
+    });
+
+    match (percent, reading) {
+        (Some(percent), Some(reading)) => format!("{percent}% · {reading}"),
+        (Some(percent), None) => format!("{percent}%"),
+        (None, Some(reading)) => reading,
+        (None, None) => "N/A".to_string(),
     }
 }
 
-fn metric_sample_color(kind: MetricKind, value: f64, max: f64) -> Color {
+/// "min 41 · max 78 · Δ60s +6" over the visible samples, skipping gaps.
+fn history_stats(data: &[Option<u64>], delta: Option<i64>, kind: MetricKind) -> String {
+    let mut samples = data.iter().flatten();
+    let Some(&first) = samples.next() else {
+        return "no samples".to_string();
+    };
+    let (min, max) = samples.fold((first, first), |(min, max), &sample| {
+        (min.min(sample), max.max(sample))
+    });
+
+    let mut stats = format!("min {min} · max {max}");
+    if let (MetricKind::Temp, Some(delta)) = (kind, delta) {
+        stats.push_str(&format!(" · Δ60s {delta:+}"));
+    }
+    stats
+}
+
+fn metric_sample_color(theme: &Theme, kind: MetricKind, value: f64, max: f64) -> Color {
     match kind {
-        MetricKind::Temp => Theme::temp_color(value),
-        MetricKind::Fan => Theme::fan_rpm_color(value, max),
+        MetricKind::Temp => theme.temp_color(value),
+        MetricKind::Fan => theme.fan_rpm_color(value, max),
     }
 }
 
-fn fan_mode_color(mode: FanMode) -> Color {
+fn fan_mode_color(theme: &Theme, mode: FanMode) -> Color {
     match mode {
-        FanMode::Auto => Theme::TEXT_SECONDARY,
-        FanMode::Max => Theme::STATE_WARNING,
+        FanMode::Auto => theme.text_secondary,
+        FanMode::Max => theme.state_warning,
     }
 }
 
-fn visible_history(history: &VecDeque<u64>, width: usize) -> Vec<u64> {
+fn visible_history(history: &VecDeque<Option<u64>>, width: usize) -> Vec<Option<u64>> {
     if width == 0 {
         return Vec::new();
     }
@@ -572,11 +1179,35 @@ fn visible_history(history: &VecDeque<u64>, width: usize) -> Vec<u64> {
         .collect()
 }
 
+/// Splits samples into runs of consecutive readings, as chart points.
+fn history_segments(data: &[Option<u64>]) -> Vec<Vec<(f64, f64)>> {
+    let mut segments: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut in_gap = true;
+
+    for (i, sample) in data.iter().enumerate() {
+        match sample {
+            Some(value) => {
+                if in_gap {
+                    segments.push(Vec::new());
+                }
+                if let Some(segment) = segments.last_mut() {
+                    segment.push((i as f64, *value as f64));
+                }
+                in_gap = false;
+            }
+            None => in_gap = true,
+        }
+    }
+
+    segments
+}
+
 fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
+    let theme = &*app.theme;
     let block = Block::default()
         .borders(Borders::TOP)
         .border_set(symbols::border::DOUBLE)
-        .border_style(Style::new().fg(Theme::BORDER_FRAME));
+        .border_style(Style::new().fg(theme.border_frame));
 
     frame.render_widget(block, area);
 
@@ -584,134 +1215,384 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
 
     // 1. Navigation & Selection (Context Sensitive)
     match app.focus {
+        FocusPanel::Controls if app.filter_editing => {
+            hints.extend(vec![
+                Span::styled(" a-z ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Filter ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" ↑↓ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Select Control ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" ↵ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Keep ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" Esc ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Clear ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+            ]);
+        }
+        FocusPanel::Controls if app.editing.is_some() => {
+            hints.extend(vec![
+                Span::styled(" 0-9 ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Type Value ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" ↵ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Apply ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" Esc ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Cancel ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+            ]);
+        }
         FocusPanel::Controls => {
             hints.extend(vec![
-                Span::styled(" ↑↓ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-                Span::styled("Select Control ", Style::new().fg(Theme::TEXT_SECONDARY)),
-                Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
+                Span::styled(" ↑↓ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Select Control ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
             ]);
 
             if let Some(control) = app.selected_control() {
                 match &control.kind {
                     crate::models::ControlKind::Toggle => {
                         hints.extend(vec![
-                            Span::styled(" ↵ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-                            Span::styled(format!("Toggle {} ", control.label()), Style::new().fg(Theme::TEXT_SECONDARY)),
-                            Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
+                            Span::styled(" ↵ ", Style::new().fg(theme.brand_primary).bold()),
+                            Span::styled(
+                                format!("Toggle {} ", control.label()),
+                                Style::new().fg(theme.text_secondary),
+                            ),
+                            Span::styled(" • ", Style::new().fg(theme.text_disabled)),
                         ]);
                     }
                     crate::models::ControlKind::Choice(_) => {
                         hints.extend(vec![
-                            Span::styled(" ←→ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-                            Span::styled(format!("Choose {} ", control.label()), Style::new().fg(Theme::TEXT_SECONDARY)),
-                            Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
-                            Span::styled(" ↵ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-                            Span::styled("Apply ", Style::new().fg(Theme::TEXT_SECONDARY)),
-                            Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
+                            Span::styled(" ←→ ", Style::new().fg(theme.brand_primary).bold()),
+                            Span::styled(
+                                format!("Choose {} ", control.label()),
+                                Style::new().fg(theme.text_secondary),
+                            ),
+                            Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                            Span::styled(" ↵ ", Style::new().fg(theme.brand_primary).bold()),
+                            Span::styled("Apply ", Style::new().fg(theme.text_secondary)),
+                            Span::styled(" • ", Style::new().fg(theme.text_disabled)),
                         ]);
+                        if control.typed_hint().is_some() {
+                            hints.extend(vec![
+                                Span::styled(
+                                    format!(" {} ", app.keymap.primary_label(Action::Edit)),
+                                    Style::new().fg(theme.brand_primary).bold(),
+                                ),
+                                Span::styled("Type Value ", Style::new().fg(theme.text_secondary)),
+                                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                            ]);
+                        }
                     }
                 }
             }
         }
+        FocusPanel::Rgb if matches!(app.color_input, Some(ColorInput::Hex(_))) => {
+            hints.extend(vec![
+                Span::styled(" 0-9 A-F ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Type Hex ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" ↵ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Preview ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" Esc ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Cancel ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+            ]);
+        }
+        FocusPanel::Rgb if app.color_input.is_some() => {
+            hints.extend(vec![
+                Span::styled(" ←→ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Hue ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" ↑↓ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Value ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" Shift+←→ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Saturation ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" ↵ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Preview ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+            ]);
+        }
         FocusPanel::Rgb => {
             hints.extend(vec![
-                Span::styled(" ↑↓ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-                Span::styled("Select Field ", Style::new().fg(Theme::TEXT_SECONDARY)),
-                Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
-                Span::styled(" ←→ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-                Span::styled("Adjust Value ", Style::new().fg(Theme::TEXT_SECONDARY)),
-                Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
+                Span::styled(" ↑↓ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Select Field ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(" ←→ ", Style::new().fg(theme.brand_primary).bold()),
+                Span::styled("Adjust Value ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+            ]);
+            if RgbField::ALL[app.selected_rgb_field].is_slider() {
+                hints.extend(vec![
+                    Span::styled(" Shift+←→ ", Style::new().fg(theme.brand_primary).bold()),
+                    Span::styled("±1 ", Style::new().fg(theme.text_secondary)),
+                    Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                    Span::styled(" Home/End ", Style::new().fg(theme.brand_primary).bold()),
+                    Span::styled("0/100 ", Style::new().fg(theme.text_secondary)),
+                    Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                ]);
+            }
+            hints.extend(vec![
+                Span::styled(
+                    format!(" {} ", app.keymap.primary_label(Action::Zones)),
+                    Style::new().fg(theme.brand_primary).bold(),
+                ),
+                Span::styled(
+                    if app.rgb.is_zones() {
+                        "Leave Zones "
+                    } else {
+                        "Zones "
+                    },
+                    Style::new().fg(theme.text_secondary),
+                ),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                Span::styled(
+                    format!(" {} ", app.keymap.primary_label(Action::AutoApply)),
+                    Style::new().fg(theme.brand_primary).bold(),
+                ),
+                Span::styled(
+                    if app.auto_apply() {
+                        "Auto-apply: On "
+                    } else {
+                        "Auto-apply: Off "
+                    },
+                    Style::new().fg(theme.text_secondary),
+                ),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
             ]);
 
-            if app.rgb_dirty {
+            if RgbField::ALL[app.selected_rgb_field] == RgbField::Color && app.rgb.uses_color() {
+                hints.extend(vec![
+                    Span::styled(
+                        format!(" {} ", app.keymap.primary_label(Action::HexColor)),
+                        Style::new().fg(theme.brand_primary).bold(),
+                    ),
+                    Span::styled("Hex ", Style::new().fg(theme.text_secondary)),
+                    Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                    Span::styled(
+                        format!(" {} ", app.keymap.primary_label(Action::ColorPicker)),
+                        Style::new().fg(theme.brand_primary).bold(),
+                    ),
+                    Span::styled("Picker ", Style::new().fg(theme.text_secondary)),
+                    Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+                ]);
+            }
+
+            if app.rgb_dirty && !app.auto_apply() {
                 hints.extend(vec![
-                    Span::styled(" ↵ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-                    Span::styled("Apply RGB Changes ", Style::new().fg(Theme::TEXT_SECONDARY)),
-                    Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
+                    Span::styled(" ↵ ", Style::new().fg(theme.brand_primary).bold()),
+                    Span::styled("Apply RGB Changes ", Style::new().fg(theme.text_secondary)),
+                    Span::styled(" • ", Style::new().fg(theme.text_disabled)),
                 ]);
             }
         }
         FocusPanel::Sensors => {
             hints.extend(vec![
-                Span::styled(" R ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-                Span::styled("Refresh Sensors ", Style::new().fg(Theme::TEXT_SECONDARY)),
-                Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
+                Span::styled(
+                    format!(" {} ", app.keymap.primary_label(Action::Refresh)),
+                    Style::new().fg(theme.brand_primary).bold(),
+                ),
+                Span::styled("Refresh Sensors ", Style::new().fg(theme.text_secondary)),
+                Span::styled(" • ", Style::new().fg(theme.text_disabled)),
             ]);
         }
     }
 
     // 2. Global Navigation (Always present but at the end)
     hints.extend(vec![
-        Span::styled(" ⇥ ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-        Span::styled("Switch Panel ", Style::new().fg(Theme::TEXT_SECONDARY)),
-        Span::styled(" • ", Style::new().fg(Theme::TEXT_DISABLED)),
-        Span::styled(" q ", Style::new().fg(Theme::BRAND_PRIMARY).bold()),
-        Span::styled("Quit ", Style::new().fg(Theme::TEXT_SECONDARY)),
+        Span::styled(
+            format!(" {} ", app.keymap.primary_label(Action::Help)),
+            Style::new().fg(theme.brand_primary).bold(),
+        ),
+        Span::styled("Help ", Style::new().fg(theme.text_secondary)),
+        Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+        Span::styled(
+            format!(" {} ", app.keymap.primary_label(Action::Profiles)),
+            Style::new().fg(theme.brand_primary).bold(),
+        ),
+        Span::styled("Profiles ", Style::new().fg(theme.text_secondary)),
+        Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+        Span::styled(" ⇥ ", Style::new().fg(theme.brand_primary).bold()),
+        Span::styled("Switch Panel ", Style::new().fg(theme.text_secondary)),
+        Span::styled(" • ", Style::new().fg(theme.text_disabled)),
+        Span::styled(
+            format!(" {} ", app.keymap.primary_label(Action::Quit)),
+            Style::new().fg(theme.brand_primary).bold(),
+        ),
+        Span::styled("Quit ", Style::new().fg(theme.text_secondary)),
     ]);
 
     // 3. Status Section
-    hints.push(Span::styled("  │  ", Style::new().fg(Theme::BORDER_IDLE)));
+    hints.push(Span::styled("  │  ", Style::new().fg(theme.border_idle)));
     
     // Determine system status: prioritize hardware errors over message logs
     let (status_color, status_text) = if !app.module_loaded {
-        (Theme::STATE_ERROR, "Kernel Module Missing")
+        (theme.state_error, "Kernel Module Missing".to_string())
     } else {
         match &app.keyboard {
-            UsbAccess::PermissionDenied => (Theme::STATE_WARNING, "USB Permission Denied"),
-            UsbAccess::NotFound => (Theme::STATE_WARNING, "Keyboard Not Found"),
-            UsbAccess::Error(e) => (Theme::STATE_ERROR, e.as_str()),
-            UsbAccess::Accessible => {
-                if app.message.level == crate::app::MessageLevel::Info || app.message.level == crate::app::MessageLevel::Success {
-                     (Theme::STATE_SUCCESS, "Ready")
+            UsbAccess::PermissionDenied(_) => {
+                (theme.state_warning, "USB Permission Denied".to_string())
+            }
+            UsbAccess::NotFound => (
+                theme.state_warning,
+                format!("Keyboard Not Found (probed {})", app.probed_keyboards),
+            ),
+            UsbAccess::Error(e) => (theme.state_error, e.clone()),
+            UsbAccess::Accessible(model) => {
+                if app.message.level == crate::app::MessageLevel::Info
+                    || app.message.level == crate::app::MessageLevel::Success
+                {
+                    (theme.state_success, format!("Ready · {}", model.name))
                 } else {
-                     (message_color(app.message.level), app.message.text.as_str())
+                    (
+                        message_color(theme, app.message.level),
+                        app.message.text.clone(),
+                    )
                 }
             }
         }
     };
 
+    if app.read_only {
+        hints.push(Span::styled(
+            " READ-ONLY ",
+            Style::new()
+                .fg(theme.text_primary)
+                .bg(theme.state_warning)
+                .bold(),
+        ));
+    }
+
     hints.push(Span::styled(" ● ", Style::new().fg(status_color)));
     hints.push(Span::styled(
         status_text,
-        Style::new().fg(Theme::TEXT_PRIMARY),
+        Style::new().fg(theme.text_primary),
     ));
 
+    if app.thermal_reverted {
+        hints.push(Span::styled(
+            " PROFILE OVERRIDDEN ",
+            Style::new()
+                .fg(theme.text_primary)
+                .bg(theme.state_warning)
+                .bold(),
+        ));
+    }
+
+    if app.calibration_active() {
+        let percent = app
+            .power
+            .battery_percent
+            .map(|percent| format!(" {percent}%"))
+            .unwrap_or_default();
+        hints.push(Span::styled(
+            format!("  CALIBRATING{percent} "),
+            Style::new().fg(theme.state_warning).bold(),
+        ));
+    }
+
     if let Some(note) = &app.hardware_note {
         hints.push(Span::styled(
             format!(" ({})", note),
-            Style::new().fg(Theme::TEXT_DISABLED).italic(),
+            Style::new().fg(theme.text_disabled).italic(),
         ));
     }
 
     // Render content on row 2 (middle of the 5-row footer area)
-    let content_area = Rect::new(area.x, area.y + 2, area.width, 1);
+    let content_area = Rect::new(area.x, area.y + 2, area.width, 1).intersection(area);
     frame.render_widget(
         Paragraph::new(Line::from(hints)).centered(),
         content_area,
     );
 }
 
-fn message_color(level: MessageLevel) -> Color {
+fn message_color(theme: &Theme, level: MessageLevel) -> Color {
     match level {
-        MessageLevel::Info => Theme::STATE_INFO,
-        MessageLevel::Success => Theme::STATE_SUCCESS,
-        MessageLevel::Warning => Theme::STATE_WARNING,
-        MessageLevel::Error => Theme::STATE_ERROR,
+        MessageLevel::Info => theme.state_info,
+        MessageLevel::Success => theme.state_success,
+        MessageLevel::Warning => theme.state_warning,
+        MessageLevel::Error => theme.state_error,
     }
 }
 
-fn control_state_color(applying: bool, pending: bool, error: bool) -> Color {
+fn control_state_color(theme: &Theme, applying: bool, pending: bool, error: bool) -> Color {
     if applying {
-        Theme::STATE_INFO
+        theme.state_info
     } else if error {
-        Theme::STATE_ERROR
+        theme.state_error
     } else if pending {
-        Theme::STATE_WARNING
+        theme.state_warning
     } else {
-        Theme::TEXT_DISABLED
+        theme.text_disabled
     }
 }
 
 fn to_color(rgb: Rgb) -> Color {
     Color::Rgb(rgb.r, rgb.g, rgb.b)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    fn overlaps(a: Rect, b: Rect) -> bool {
+        !a.intersection(b).is_empty()
+    }
+
+    #[test]
+    fn layout_adapts_to_terminal_size() {
+        let wide = Screen::layout(Rect::new(0, 0, 160, 48)).unwrap();
+        let sensors = wide.sensors.unwrap();
+        assert_eq!(wide.header.height, 4);
+        assert!(sensors.x > wide.controls.x && wide.rgb.y > wide.controls.y);
+
+        let narrow = Screen::layout(Rect::new(0, 0, 80, 40)).unwrap();
+        let sensors = narrow.sensors.unwrap();
+        assert!(sensors.bottom() <= narrow.controls.y);
+        assert!(narrow.rgb.x > narrow.controls.x);
+
+        let short = Screen::layout(Rect::new(0, 0, 80, 20)).unwrap();
+        assert_eq!(short.header.height, 1);
+        assert_eq!(short.sensors, None);
+
+        for screen in [wide, narrow, short] {
+            let mut parts = vec![screen.header, screen.controls, screen.rgb, screen.footer];
+            parts.extend(screen.sensors);
+            for (index, part) in parts.iter().enumerate() {
+                let later = &parts[index + 1..];
+                assert!(!part.is_empty(), "{screen:?}");
+                assert!(later.iter().all(|other| !overlaps(*part, *other)));
+            }
+        }
+
+        assert_eq!(Screen::layout(Rect::new(0, 0, 59, 40)), None);
+        assert_eq!(Screen::layout(Rect::new(0, 0, 120, 15)), None);
+    }
+
+    #[test]
+    fn tiny_terminal_only_shows_the_required_size() {
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        terminal
+            .draw(|frame| draw_too_small(frame, frame.area(), &Theme::DEFAULT))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim()
+                    .to_string()
+            })
+            .collect();
+        assert!(rows.iter().any(|row| row == "Terminal too small"));
+        assert!(rows.iter().any(|row| row == "need ≥ 60×16, have 40×10"));
+    }
+}
@@ -0,0 +1,161 @@
+//! The invoking user's home and XDG directories.
+//!
+//! Under `sudo` our environment may still describe root, so the user behind
+//! it is looked up in the passwd database instead, and what we create in
+//! their home is handed back to them.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::os::unix::fs::chown;
+use std::path::{Path, PathBuf};
+
+use crate::permissions::is_root;
+
+/// The user behind `sudo`, from the passwd database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SudoUser {
+    home: PathBuf,
+    uid: u32,
+    gid: u32,
+}
+
+impl SudoUser {
+    /// Gives `path` to this user; best effort, like the write it follows.
+    pub fn hand_back(&self, path: &Path) {
+        let _ = chown(path, Some(self.uid), Some(self.gid));
+    }
+}
+
+/// Set only when running as root through `sudo` for another user.
+pub fn sudo_user() -> Option<SudoUser> {
+    if !is_root() {
+        return None;
+    }
+    let user = env::var("SUDO_USER")
+        .ok()
+        .filter(|user| !user.is_empty() && user != "root")?;
+    passwd_entry(&fs::read_to_string("/etc/passwd").ok()?, &user)
+}
+
+fn passwd_entry(passwd: &str, user: &str) -> Option<SudoUser> {
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() < 7 || fields[0] != user {
+            return None;
+        }
+        Some(SudoUser {
+            home: PathBuf::from(fields[5]),
+            uid: fields[2].parse().ok()?,
+            gid: fields[3].parse().ok()?,
+        })
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XdgDir {
+    Config,
+    Data,
+}
+
+impl XdgDir {
+    fn var(self) -> &'static str {
+        match self {
+            Self::Config => "XDG_CONFIG_HOME",
+            Self::Data => "XDG_DATA_HOME",
+        }
+    }
+
+    /// Where the directory is under a home when the variable is unset.
+    fn in_home(self) -> &'static str {
+        match self {
+            Self::Config => ".config",
+            Self::Data => ".local/share",
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME` or `$XDG_DATA_HOME`, else its default under `$HOME`.
+/// For a sudo user the default under their own home is used.
+pub fn xdg_dir(dir: XdgDir, sudo_user: Option<&SudoUser>) -> Option<PathBuf> {
+    resolve_xdg_dir(dir, sudo_user.map(|user| user.home.as_path()), |name| {
+        env::var_os(name)
+    })
+}
+
+fn resolve_xdg_dir(
+    dir: XdgDir,
+    sudo_home: Option<&Path>,
+    var: impl Fn(&str) -> Option<OsString>,
+) -> Option<PathBuf> {
+    if let Some(home) = sudo_home {
+        return Some(home.join(dir.in_home()));
+    }
+    let var = |name| var(name).filter(|value| !value.is_empty());
+    var(dir.var())
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(dir.in_home())))
+}
+
+/// Creates `dir` and its missing parents, handing each one it made to
+/// `sudo_user`.
+pub fn create_dir_all(dir: &Path, sudo_user: Option<&SudoUser>) -> io::Result<()> {
+    let created: Vec<&Path> = dir.ancestors().take_while(|path| !path.exists()).collect();
+    fs::create_dir_all(dir)?;
+    if let Some(user) = sudo_user {
+        for path in created {
+            user.hand_back(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sudo_home_comes_from_passwd() {
+        let passwd = "root:x:0:0::/root:/bin/bash\n\
+                      alice:x:1000:1000:Alice:/var/home/alice:/bin/zsh\n";
+        let user = passwd_entry(passwd, "alice").unwrap();
+        assert_eq!(user.home, PathBuf::from("/var/home/alice"));
+        assert_eq!((user.uid, user.gid), (1000, 1000));
+        assert!(passwd_entry(passwd, "bob").is_none());
+    }
+
+    #[test]
+    fn xdg_dirs_prefer_the_variable_then_home_unless_under_sudo() {
+        let env = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(known, _)| *known == name)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+        let set = env(&[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/me")]);
+        let empty = env(&[("XDG_DATA_HOME", ""), ("HOME", "/home/me")]);
+
+        assert_eq!(
+            resolve_xdg_dir(XdgDir::Config, None, set),
+            Some(PathBuf::from("/xdg"))
+        );
+        assert_eq!(
+            resolve_xdg_dir(XdgDir::Data, None, set),
+            Some(PathBuf::from("/home/me/.local/share"))
+        );
+        assert_eq!(
+            resolve_xdg_dir(XdgDir::Data, None, empty),
+            Some(PathBuf::from("/home/me/.local/share"))
+        );
+        assert_eq!(resolve_xdg_dir(XdgDir::Config, None, env(&[])), None);
+
+        // Root's variables do not describe the sudo user.
+        let home = Path::new("/var/home/alice");
+        assert_eq!(
+            resolve_xdg_dir(XdgDir::Config, Some(home), set),
+            Some(PathBuf::from("/var/home/alice/.config"))
+        );
+    }
+}